@@ -0,0 +1,80 @@
+//! Generates Rust types from sample JSON documents.
+//!
+//! ```text
+//! valu3-codegen [--name <Name>] [--enum-limit <N>] [--each] [FILE...]
+//! ```
+//!
+//! Every file is one sample, or standard input when no file is given. With `--each`, the
+//! elements of a top-level array are the samples instead.
+use std::io::Read;
+use std::process::ExitCode;
+use valu3::prelude::*;
+
+const USAGE: &str = "usage: valu3-codegen [--name <Name>] [--enum-limit <N>] [--each] [FILE...]";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(source) => {
+            print!("{}", source);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut args: impl Iterator<Item = String>) -> Result<String, String> {
+    let mut name = "Root".to_string();
+    let mut enum_limit = None;
+    let mut each = false;
+    let mut files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = args.next().ok_or(USAGE)?,
+            "--enum-limit" => {
+                let limit = args.next().ok_or(USAGE)?;
+                let limit = limit
+                    .parse()
+                    .map_err(|_| format!("invalid enum limit: {}", limit))?;
+                enum_limit = Some(limit);
+            }
+            "--each" => each = true,
+            "-h" | "--help" => return Ok(format!("{}\n", USAGE)),
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ => files.push(arg),
+        }
+    }
+
+    let mut documents = Vec::new();
+    if files.is_empty() {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("<stdin>: {}", error))?;
+        documents.push(("<stdin>".to_string(), text));
+    }
+    for file in files {
+        let text =
+            std::fs::read_to_string(&file).map_err(|error| format!("{}: {}", file, error))?;
+        documents.push((file, text));
+    }
+
+    let mut samples = Vec::new();
+    for (file, text) in documents {
+        let value =
+            Value::json_to_value(&text).map_err(|error| format!("{}: {:?}", file, error))?;
+        match value {
+            Value::Array(array) if each => samples.extend(array.values),
+            value => samples.push(value),
+        }
+    }
+
+    let mut codegen = RustCodegen::new(&name);
+    if let Some(limit) = enum_limit {
+        codegen = codegen.enum_limit(limit);
+    }
+    Ok(codegen.infer(&samples))
+}
@@ -0,0 +1,445 @@
+//! Generation of Rust types from sample values.
+//!
+//! [`RustCodegen`] infers a JSON Schema from samples with [`SchemaInferrer`] and translates it
+//! into Rust source: objects become structs, arrays become `Vec`, members missing from some
+//! samples or sometimes `null` become `Option`, and small closed sets of strings become enums.
+//! Every generated type derives `ToValue` and `FromValue`, with fields and variants named the
+//! Rust way and renamed to their JSON keys where those differ.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let samples = [
+//!     Value::json_to_value(r#"{"id": 1, "status": "open", "owner": {"name": "ana"}}"#).unwrap(),
+//!     Value::json_to_value(r#"{"id": 2, "status": "open", "tags": ["x"]}"#).unwrap(),
+//!     Value::json_to_value(r#"{"id": 3, "status": "closed", "owner": null}"#).unwrap(),
+//! ];
+//!
+//! let source = RustCodegen::new("Ticket").infer(&samples);
+//! // pub struct Ticket {
+//! //     pub id: i64,
+//! //     pub owner: Option<Owner>,
+//! //     pub status: Status,
+//! //     pub tags: Option<Vec<String>>,
+//! // }
+//! // ...
+//! ```
+use crate::prelude::*;
+use std::collections::BTreeSet;
+
+/// The most distinct strings turned into an enum by default.
+const ENUM_LIMIT: usize = 8;
+
+const KEYWORDS: [&str; 51] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+/// The names generated types must not shadow, as the generated code refers to them.
+const RESERVED: [&str; 6] = ["Value", "Object", "Array", "Option", "Vec", "String"];
+
+/// Generates Rust type definitions from sample values or from a JSON Schema.
+#[derive(Debug, Clone)]
+pub struct RustCodegen {
+    name: String,
+    enum_limit: usize,
+}
+
+/// The state of a single generation: the items emitted so far and the type names taken.
+struct Generator {
+    items: Vec<String>,
+    names: BTreeSet<String>,
+}
+
+impl RustCodegen {
+    /// Creates a generator whose root type is called `name`.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            enum_limit: ENUM_LIMIT,
+        }
+    }
+
+    /// Sets the most distinct strings turned into an enum; zero disables enums.
+    pub fn enum_limit(mut self, limit: usize) -> Self {
+        self.enum_limit = limit;
+        self
+    }
+
+    /// Generates the types describing a set of samples.
+    pub fn infer<'a, I>(&self, samples: I) -> String
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        let mut inferrer = SchemaInferrer::new().enums(self.enum_limit);
+        for sample in samples {
+            inferrer.add(sample);
+        }
+        self.generate(&inferrer.schema())
+    }
+
+    /// Generates the types described by a schema. Only `type`, `properties`, `required`,
+    /// `items` and `enum` are taken into account; anything else is typed as [`Value`].
+    pub fn generate(&self, schema: &Value) -> String {
+        let mut generator = Generator {
+            items: Vec::new(),
+            names: RESERVED.iter().map(|name| name.to_string()).collect(),
+        };
+
+        let root = generator.rust_type(schema, &self.name, self.enum_limit);
+        if root != type_name(&self.name) {
+            generator
+                .items
+                .insert(0, format!("pub type {} = {};\n", self.name, root));
+        }
+
+        let mut source = String::from("use valu3::prelude::*;\n");
+        for item in generator.items {
+            source.push('\n');
+            source.push_str(&item);
+        }
+        source
+    }
+}
+
+impl Generator {
+    /// Returns the Rust type of a schema, emitting the structs and enums it needs, named after
+    /// `hint`.
+    fn rust_type(&mut self, schema: &Value, hint: &str, enum_limit: usize) -> String {
+        let (types, nullable) = match schema {
+            Value::Object(_) => schema_types(schema),
+            _ => return "Value".to_string(),
+        };
+
+        let rust_type = match types.as_slice() {
+            [kind] => match kind.as_str() {
+                "integer" => "i64".to_string(),
+                "number" => "f64".to_string(),
+                "boolean" => "bool".to_string(),
+                "string" => self
+                    .enum_type(schema, hint, enum_limit)
+                    .unwrap_or_else(|| "String".to_string()),
+                "array" => {
+                    let item = match schema.get("items") {
+                        Some(items) => self.rust_type(items, &singular(hint), enum_limit),
+                        None => "Value".to_string(),
+                    };
+                    format!("Vec<{}>", item)
+                }
+                "object" => self.struct_type(schema, hint, enum_limit),
+                _ => "Value".to_string(),
+            },
+            [a, b] if a == "integer" && b == "number" || a == "number" && b == "integer" => {
+                "f64".to_string()
+            }
+            _ => "Value".to_string(),
+        };
+
+        if nullable && rust_type != "Value" {
+            format!("Option<{}>", rust_type)
+        } else {
+            rust_type
+        }
+    }
+
+    fn struct_type(&mut self, schema: &Value, hint: &str, enum_limit: usize) -> String {
+        let properties = match schema.get("properties") {
+            Some(Value::Object(properties)) if !properties.is_empty() => properties,
+            _ => return "Value".to_string(),
+        };
+        let required = match schema.get("required") {
+            Some(Value::Array(required)) => required
+                .values
+                .iter()
+                .map(|name| name.as_string())
+                .collect::<BTreeSet<_>>(),
+            _ => BTreeSet::new(),
+        };
+
+        let name = self.reserve(hint);
+        let index = self.items.len();
+        self.items.push(String::new());
+
+        let mut keys = properties
+            .keys()
+            .into_iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>();
+        keys.sort();
+
+        // Keys that already are field names keep them, others are numbered when they clash.
+        let mut taken = keys
+            .iter()
+            .filter(|key| field_name(key) == **key)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let mut fields = String::new();
+        for key in keys {
+            let property = properties.get(key.as_str()).unwrap();
+            let mut rust_type = self.rust_type(property, &key, enum_limit);
+            if !required.contains(&key) && !rust_type.starts_with("Option<") {
+                rust_type = format!("Option<{}>", rust_type);
+            }
+
+            let field = match field_name(&key) {
+                field if field == key => field,
+                field => unique(field, &mut taken),
+            };
+            if field.trim_start_matches("r#") != key {
+                fields.push_str(&format!("    #[attr(rename = {:?})]\n", key));
+            }
+            fields.push_str(&format!("    pub {}: {},\n", field, rust_type));
+        }
+
+        self.items[index] = format!(
+            "#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]\npub struct {} {{\n{}}}\n",
+            name, fields
+        );
+        name
+    }
+
+    /// Emits an enum for a closed set of strings, when they make distinct variants.
+    fn enum_type(&mut self, schema: &Value, hint: &str, enum_limit: usize) -> Option<String> {
+        let values = match schema.get("enum") {
+            Some(Value::Array(values)) => values
+                .values
+                .iter()
+                .filter(|value| !value.is_null())
+                .map(|value| match value {
+                    Value::String(value) => Some(value.as_string()),
+                    _ => None,
+                })
+                .collect::<Option<BTreeSet<_>>>()?,
+            _ => return None,
+        };
+        let names = values
+            .iter()
+            .map(|value| type_name(value))
+            .collect::<BTreeSet<_>>();
+        if values.is_empty() || values.len() > enum_limit || names.len() != values.len() {
+            return None;
+        }
+
+        let name = self.reserve(hint);
+        let mut variants = String::new();
+        for value in values {
+            let variant = type_name(&value);
+            if variant != value {
+                variants.push_str(&format!("    #[attr(rename = {:?})]\n", value));
+            }
+            variants.push_str(&format!("    {},\n", variant));
+        }
+        self.items.push(format!(
+            "#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]\npub enum {} {{\n{}}}\n",
+            name, variants
+        ));
+        Some(name)
+    }
+
+    /// Takes a type name derived from `hint`, numbered when it is already taken.
+    fn reserve(&mut self, hint: &str) -> String {
+        let base = type_name(hint);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while self.names.contains(&name) {
+            name = format!("{}{}", base, suffix);
+            suffix += 1;
+        }
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+/// Returns the non-null types of a schema and whether it accepts `null`.
+fn schema_types(schema: &Value) -> (Vec<String>, bool) {
+    let types = match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_string()],
+        Some(Value::Array(kinds)) => kinds.values.iter().map(|kind| kind.as_string()).collect(),
+        _ => Vec::new(),
+    };
+    let nullable = types.iter().any(|kind| kind == "null");
+    let types = types.into_iter().filter(|kind| kind != "null").collect();
+    (types, nullable)
+}
+
+/// Returns a snake_case field name for a key, as a raw identifier for keywords.
+fn field_name(key: &str) -> String {
+    let mut name = String::new();
+    let mut previous = '_';
+    for c in key.chars() {
+        let c = if c.is_ascii_alphanumeric() { c } else { '_' };
+        if c.is_ascii_uppercase() && (previous.is_ascii_lowercase() || previous.is_ascii_digit()) {
+            name.push('_');
+        }
+        if c != '_' || previous != '_' {
+            name.push(c.to_ascii_lowercase());
+        }
+        previous = c;
+    }
+    let name = name.trim_matches('_').to_string();
+
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else if ["crate", "self", "super"].contains(&name.as_str()) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+/// Numbers a name when it is already taken.
+fn unique(base: String, taken: &mut BTreeSet<String>) -> String {
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while taken.contains(&name) {
+        name = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// Returns a PascalCase type name for a key.
+fn type_name(key: &str) -> String {
+    let mut name = String::new();
+    let mut upper = true;
+    for c in key.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+        } else if upper {
+            name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'T');
+    }
+    if name == "Self" {
+        name.push('_');
+    }
+    name
+}
+
+/// Returns the name of the items of an array, dropping a plural `s`.
+fn singular(name: &str) -> String {
+    match name.strip_suffix('s') {
+        Some(stem) if !stem.is_empty() && !stem.ends_with('s') => stem.to_string(),
+        _ => format!("{}Item", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_infer() {
+        let samples = [
+            json(
+                r#"{"id": 1, "status": "open", "score": 1, "owner": {"name": "ana", "user-id": 3}, "items": [{"sku": "a"}]}"#,
+            ),
+            json(r#"{"id": 2, "status": "open", "score": 2.5, "tags": ["x"], "items": []}"#),
+            json(
+                r#"{"id": 3, "status": "closed", "score": 0, "owner": null, "items": [{"sku": "b", "qty": 2}], "type": "misc"}"#,
+            ),
+        ];
+
+        let source = RustCodegen::new("Ticket").infer(&samples);
+        assert_eq!(
+            source,
+            r#"use valu3::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]
+pub struct Ticket {
+    pub id: i64,
+    pub items: Vec<Item>,
+    pub owner: Option<Owner>,
+    pub score: f64,
+    pub status: Status,
+    pub tags: Option<Vec<String>>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]
+pub struct Item {
+    pub qty: Option<i64>,
+    pub sku: String,
+}
+
+#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]
+pub struct Owner {
+    pub name: String,
+    #[attr(rename = "user-id")]
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, ToValue, FromValue)]
+pub enum Status {
+    #[attr(rename = "closed")]
+    Closed,
+    #[attr(rename = "open")]
+    Open,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let source = RustCodegen::new("Root").enum_limit(0).infer(&[
+            json(r#"{"a": {"b": {"x": 1}}, "b": {"y": "s"}, "kind": "k"}"#),
+            json(r#"{"a": {"b": {"x": 2}}, "b": {"y": "s"}, "kind": "k"}"#),
+        ]);
+        assert!(source.contains("pub struct B {\n    pub x: i64,\n}"));
+        assert!(source.contains("pub struct B2 {\n    pub y: String,\n}"));
+        assert!(source.contains("pub kind: String,"));
+
+        let source = RustCodegen::new("Root").infer(&[json(r#"{"value": {"x": 1}}"#)]);
+        assert!(source.contains("pub value: Value2,"));
+    }
+
+    #[test]
+    fn test_field_names() {
+        let cases = [
+            ("id", "id"),
+            ("userId", "user_id"),
+            ("HTTPStatus", "httpstatus"),
+            ("first-name", "first_name"),
+            ("__meta__", "meta"),
+            ("2fa", "_2fa"),
+            ("", "_"),
+            ("type", "r#type"),
+            ("self", "self_"),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(field_name(key), expected, "{}", key);
+        }
+
+        let source = RustCodegen::new("Root").infer(&[json(r#"{"userId": 1, "user_id": 2}"#)]);
+        assert!(source.contains(
+            "    #[attr(rename = \"userId\")]\n    pub user_id_2: i64,\n    pub user_id: i64,\n"
+        ));
+    }
+
+    #[test]
+    fn test_non_objects() {
+        assert_eq!(
+            RustCodegen::new("Numbers").infer(&[json("[1, 2.5]")]),
+            "use valu3::prelude::*;\n\npub type Numbers = Vec<f64>;\n"
+        );
+        assert_eq!(
+            RustCodegen::new("Mixed").infer(&[json("[1, \"a\"]"), json("{}")]),
+            "use valu3::prelude::*;\n\npub type Mixed = Value;\n"
+        );
+    }
+}
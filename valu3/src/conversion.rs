@@ -0,0 +1,419 @@
+//! Fallible conversions from `Value` that report where and why they failed.
+//!
+//! [`FromValueBehavior`] returns `None` when a value does not fit a type, which leaves no clue
+//! as to which part of a large document was wrong. [`TryFromValue`] returns an
+//! [`Error::Conversion`] instead, holding the path of the offending value, such as
+//! `items[3].price`, the type that was expected there and what was found.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! #[derive(TryFromValue)]
+//! struct Item {
+//!     price: u8,
+//! }
+//!
+//! #[derive(TryFromValue)]
+//! struct Order {
+//!     items: Vec<Item>,
+//! }
+//!
+//! let value = Value::json_to_value(r#"{"items": [{"price": 300}]}"#).unwrap();
+//! match Order::try_from_value(value) {
+//!     Err(Error::Conversion(error)) => {
+//!         assert_eq!(error.to_string(), "items[0].price: expected u8, found number 300");
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+use crate::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// A step of the path to a value inside a document.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A value that could not be converted to the type expected at its location.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConversionError {
+    path: Vec<PathSegment>,
+    expected: String,
+    found: String,
+}
+
+impl ConversionError {
+    /// Creates an error for a value that does not fit the `expected` type.
+    pub fn new(expected: impl Into<String>, found: &Value) -> Self {
+        Self {
+            path: Vec::new(),
+            expected: expected.into(),
+            found: describe(found),
+        }
+    }
+
+    /// Creates an error for a required value that is missing.
+    pub fn missing(expected: impl Into<String>) -> Self {
+        Self {
+            path: Vec::new(),
+            expected: expected.into(),
+            found: "no value".to_string(),
+        }
+    }
+
+    /// Places the error under a key of an object.
+    pub fn at_key(mut self, key: &str) -> Self {
+        self.path.insert(0, PathSegment::Key(key.to_string()));
+        self
+    }
+
+    /// Places the error under an index of an array.
+    pub fn at_index(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// Returns the path of the value, such as `items[3].price`, or an empty string for the
+    /// root. Keys that are not plain names are quoted, as in `labels["a.b"]`.
+    pub fn path(&self) -> String {
+        format_path(&self.path)
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+}
+
+/// Renders a path as `items[3].price`, quoting the keys that are not plain names.
+pub(crate) fn format_path(segments: &[PathSegment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            PathSegment::Key(key) if is_plain(key) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            PathSegment::Key(key) => path.push_str(&format!("[{:?}]", key)),
+            PathSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+fn is_plain(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '$')
+}
+
+/// Describes a value for error messages: its kind, and its content when it is short.
+pub(crate) fn describe(value: &Value) -> String {
+    const MAX_LEN: usize = 32;
+
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Undefined => "undefined".to_string(),
+        Value::Boolean(boolean) => format!("boolean {}", boolean),
+        Value::Number(number) => format!("number {}", number),
+        Value::String(string) => {
+            let string = string.as_string();
+            if string.chars().count() > MAX_LEN {
+                let head: String = string.chars().take(MAX_LEN).collect();
+                format!("string {:?}...", head)
+            } else {
+                format!("string {:?}", string)
+            }
+        }
+        Value::Array(_) => "array".to_string(),
+        Value::Object(_) => "object".to_string(),
+        Value::DateTime(_) => "date-time".to_string(),
+    }
+}
+
+/// Returns the name of a type without module paths, such as `Page<User>`, as error messages
+/// and generated schemas show it.
+pub fn short_type_name<T: ?Sized>() -> String {
+    let mut name = String::new();
+    let mut segment = String::new();
+    let mut chars = std::any::type_name::<T>().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            segment.clear();
+        } else if c.is_alphanumeric() || c == '_' {
+            segment.push(c);
+        } else {
+            name.push_str(&segment);
+            segment.clear();
+            name.push(c);
+        }
+    }
+    name.push_str(&segment);
+    name
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path())?;
+        }
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl From<ConversionError> for Error {
+    fn from(error: ConversionError) -> Self {
+        Error::Conversion(error)
+    }
+}
+
+impl Error {
+    /// Places a conversion error under a key of an object. Other errors are returned as they
+    /// are.
+    pub fn at_key(self, key: &str) -> Self {
+        match self {
+            Error::Conversion(error) => Error::Conversion(error.at_key(key)),
+            error => error,
+        }
+    }
+
+    /// Places a conversion error under an index of an array. Other errors are returned as
+    /// they are.
+    pub fn at_index(self, index: usize) -> Self {
+        match self {
+            Error::Conversion(error) => Error::Conversion(error.at_index(index)),
+            error => error,
+        }
+    }
+}
+
+/// A trait for converting `Value` to types, reporting why the conversion failed.
+pub trait TryFromValue: Sized {
+    /// Converts a `Value` into a type, or returns why it cannot.
+    fn try_from_value(value: Value) -> Result<Self, Error>;
+
+    /// Returns the value of an absent field, or `None` when the field is required.
+    fn try_from_missing() -> Option<Self> {
+        None
+    }
+
+    /// Returns the keys a derived struct consumes, so that a flattened sibling gets the rest.
+    fn field_keys() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the name of the type used in error messages.
+    fn expected() -> String {
+        short_type_name::<Self>()
+    }
+}
+
+macro_rules! try_from_value_behavior {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl TryFromValue for $ty {
+                fn try_from_value(value: Value) -> Result<Self, Error> {
+                    match <$ty as FromValueBehavior>::from_value(value.clone()) {
+                        Some(item) => Ok(item),
+                        None => Err(ConversionError::new(Self::expected(), &value).into()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_value_behavior!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64, String, bool);
+
+impl TryFromValue for Value {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl<T: TryFromValue> TryFromValue for Vec<T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Array(array) => array
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| T::try_from_value(item).map_err(|e| e.at_index(index)))
+                .collect(),
+            _ => Err(ConversionError::new(Self::expected(), &value).into()),
+        }
+    }
+}
+
+/// Converts the entries of an object, keyed as `key` maps them.
+fn try_entries<K, T, F>(value: Value, expected: String, key: F) -> Result<Vec<(K, T)>, Error>
+where
+    T: TryFromValue,
+    F: Fn(&ValueKey) -> K,
+{
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .map(|(name, item)| {
+                T::try_from_value(item.clone())
+                    .map(|item| (key(name), item))
+                    .map_err(|e| e.at_key(&name.to_string()))
+            })
+            .collect(),
+        _ => Err(ConversionError::new(expected, &value).into()),
+    }
+}
+
+#[cfg(feature = "cstring")]
+impl<T: TryFromValue> TryFromValue for HashMap<CString, T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        try_entries(value, Self::expected(), |key| key.as_string_b().extract())
+            .map(|entries| entries.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "cstring")]
+impl<T: TryFromValue> TryFromValue for BTreeMap<CString, T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        try_entries(value, Self::expected(), |key| key.as_string_b().extract())
+            .map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<T: TryFromValue> TryFromValue for HashMap<String, T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        try_entries(value, Self::expected(), |key| key.to_string())
+            .map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<T: TryFromValue> TryFromValue for BTreeMap<String, T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        try_entries(value, Self::expected(), |key| key.to_string())
+            .map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<T: TryFromValue> TryFromValue for Option<T> {
+    fn try_from_value(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Null | Value::Undefined => Ok(None),
+            _ => T::try_from_value(value).map(Some),
+        }
+    }
+
+    fn try_from_missing() -> Option<Self> {
+        Some(None)
+    }
+
+    fn field_keys() -> Vec<String> {
+        T::field_keys()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use std::collections::BTreeMap;
+
+    fn conversion_error(error: Error) -> ConversionError {
+        match error {
+            Error::Conversion(error) => error,
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(u8::try_from_value(Value::from(200)), Ok(200));
+        assert_eq!(f64::try_from_value(Value::from(1.5)), Ok(1.5));
+        assert_eq!(
+            String::try_from_value(Value::from("a")),
+            Ok("a".to_string())
+        );
+
+        let error = conversion_error(u8::try_from_value(Value::from(300)).unwrap_err());
+        assert_eq!(error.path(), "");
+        assert_eq!(error.expected(), "u8");
+        assert_eq!(error.found(), "number 300");
+        assert_eq!(error.to_string(), "expected u8, found number 300");
+
+        let error = conversion_error(bool::try_from_value(Value::from("yes")).unwrap_err());
+        assert_eq!(error.to_string(), "expected bool, found string \"yes\"");
+    }
+
+    #[test]
+    fn test_paths() {
+        let value_a = || Value::json_to_value(r#"{"a": [[1], [2, "x"]]}"#).unwrap();
+        let error = conversion_error(
+            BTreeMap::<String, Vec<Vec<i32>>>::try_from_value(value_a()).unwrap_err(),
+        );
+        assert_eq!(error.path(), "a[1][1]");
+        assert_eq!(
+            <BTreeMap<String, Vec<Vec<i32>>> as FromValueBehavior>::from_value(value_a()),
+            None
+        );
+        assert_eq!(
+            error.to_string(),
+            "a[1][1]: expected i32, found string \"x\""
+        );
+
+        let error = conversion_error(
+            BTreeMap::<String, BTreeMap<String, String>>::try_from_value(
+                Value::json_to_value(r#"{"b.c": {"d": null}}"#).unwrap(),
+            )
+            .unwrap_err(),
+        );
+        assert_eq!(error.path(), "[\"b.c\"].d");
+        assert_eq!(error.found(), "null");
+    }
+
+    #[test]
+    fn test_option() {
+        assert_eq!(Option::<u8>::try_from_value(Value::Null), Ok(None));
+        assert_eq!(Option::<u8>::try_from_value(Value::from(1)), Ok(Some(1)));
+        assert_eq!(Option::<u8>::try_from_missing(), Some(None));
+        assert_eq!(u8::try_from_missing(), None);
+
+        let error = conversion_error(Option::<u8>::try_from_value(Value::from(-1)).unwrap_err());
+        assert_eq!(error.expected(), "u8");
+    }
+
+    #[test]
+    fn test_missing() {
+        let error = ConversionError::missing("u8")
+            .at_key("price")
+            .at_index(3)
+            .at_key("items");
+        assert_eq!(
+            error.to_string(),
+            "items[3].price: expected u8, found no value"
+        );
+        assert_eq!(Error::NotNumber.at_key("price"), Error::NotNumber,);
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name::<ConversionError>(), "ConversionError");
+        assert_eq!(
+            short_type_name::<BTreeMap<String, Vec<Option<Value>>>>(),
+            "BTreeMap<String, Vec<Option<Value>>>"
+        );
+    }
+}
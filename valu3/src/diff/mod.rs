@@ -0,0 +1,429 @@
+//! Structural diff of two `Value`s.
+//!
+//! `Value::diff` walks two values side by side and reports every node that was added, removed
+//! or changed, addressed by its JSON Pointer. Objects are compared key by key and arrays are
+//! aligned on their longest common subsequence, so an element inserted at the front of an array
+//! shows up as one addition instead of a change to every element. Numbers compare by value, so
+//! `1` and `1.0` are the same, and objects by their members alone.
+//!
+//! A `Diff` is a plain list of `Change`s for programmatic use, and renders as a unified-diff
+//! style report, optionally colored, through `Diff::report` or `Display`. The
+//! `assert_value_eq!` macro prints that report when two values differ.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let old = Value::json_to_value(r#"{"name": "a", "tags": ["x", "y"]}"#).unwrap();
+//! let new = Value::json_to_value(r#"{"name": "b", "tags": ["y"]}"#).unwrap();
+//!
+//! let diff = old.diff(&new);
+//! assert_eq!(diff.len(), 2);
+//! println!("{}", diff.report(true));
+//! ```
+use crate::patch::equivalent;
+use crate::prelude::*;
+use std::fmt::{Display, Formatter};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// A difference between two values.
+///
+/// Paths of removed nodes point into the old value; paths of added and changed nodes point
+/// into the new one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A node only present in the new value.
+    Added { path: JsonPointer, value: Value },
+    /// A node only present in the old value.
+    Removed { path: JsonPointer, value: Value },
+    /// A scalar whose value changed.
+    Changed {
+        path: JsonPointer,
+        old: Value,
+        new: Value,
+    },
+    /// A node whose type changed, such as a number replaced by a string.
+    TypeChanged {
+        path: JsonPointer,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl Change {
+    /// Returns the location of the change.
+    pub fn path(&self) -> &JsonPointer {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. }
+            | Change::TypeChanged { path, .. } => path,
+        }
+    }
+}
+
+/// The changes between two values, in document order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Returns `true` when the two values are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Change> {
+        self.changes.iter()
+    }
+
+    /// Renders the changes as a unified-diff style report, with ANSI colors when `colored` is
+    /// set. Every change gets a `@@ path @@` header followed by the old value on `-` lines and
+    /// the new value on `+` lines.
+    pub fn report(&self, colored: bool) -> String {
+        let paint = |color: &str, line: String| {
+            if colored {
+                format!("{}{}{}\n", color, line, RESET)
+            } else {
+                format!("{}\n", line)
+            }
+        };
+        let lines = |sign: char, value: &Value| {
+            value
+                .to_json(JsonMode::Indented)
+                .lines()
+                .map(|line| format!("{} {}", sign, line))
+                .collect::<Vec<_>>()
+        };
+
+        let mut report = String::new();
+        if self.is_empty() {
+            return report;
+        }
+        report.push_str(&paint(RED, "--- old".to_string()));
+        report.push_str(&paint(GREEN, "+++ new".to_string()));
+
+        for change in &self.changes {
+            let path = match change.path().to_string() {
+                path if path.is_empty() => "(root)".to_string(),
+                path => path,
+            };
+            let (header, old, new) = match change {
+                Change::Added { value, .. } => (format!("@@ {} @@ added", path), None, Some(value)),
+                Change::Removed { value, .. } => {
+                    (format!("@@ {} @@ removed", path), Some(value), None)
+                }
+                Change::Changed { old, new, .. } => {
+                    (format!("@@ {} @@ changed", path), Some(old), Some(new))
+                }
+                Change::TypeChanged { old, new, .. } => (
+                    format!(
+                        "@@ {} @@ type changed from {} to {}",
+                        path,
+                        type_name(old),
+                        type_name(new)
+                    ),
+                    Some(old),
+                    Some(new),
+                ),
+            };
+
+            report.push_str(&paint(CYAN, header));
+            for line in old.map(|old| lines('-', old)).unwrap_or_default() {
+                report.push_str(&paint(RED, line));
+            }
+            for line in new.map(|new| lines('+', new)).unwrap_or_default() {
+                report.push_str(&paint(GREEN, line));
+            }
+        }
+
+        report
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.report(false))
+    }
+}
+
+impl<'a> IntoIterator for &'a Diff {
+    type Item = &'a Change;
+    type IntoIter = std::slice::Iter<'a, Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+impl IntoIterator for Diff {
+    type Item = Change;
+    type IntoIter = std::vec::IntoIter<Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+/// One step of the alignment of two arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// The elements at these positions are equivalent.
+    Same(usize, usize),
+    /// The element at the first position was replaced by the one at the second.
+    Changed(usize, usize),
+    /// The element at this position of the old array is gone.
+    Removed(usize),
+    /// The element at this position of the new array is new.
+    Added(usize),
+}
+
+/// Aligns two arrays on their longest common subsequence. Between two common elements, removed
+/// and added elements are paired into `Changed` steps first, then the remaining removals and
+/// additions follow, in that order.
+pub(crate) fn align(source: &[Value], target: &[Value]) -> Vec<Step> {
+    let prefix = source
+        .iter()
+        .zip(target)
+        .take_while(|(lhs, rhs)| equivalent(lhs, rhs))
+        .count();
+    let suffix = source[prefix..]
+        .iter()
+        .rev()
+        .zip(target[prefix..].iter().rev())
+        .take_while(|(lhs, rhs)| equivalent(lhs, rhs))
+        .count();
+    let rows = source.len() - suffix;
+    let columns = target.len() - suffix;
+
+    let mut lengths = vec![vec![0usize; columns - prefix + 1]; rows - prefix + 1];
+    for i in (prefix..rows).rev() {
+        for j in (prefix..columns).rev() {
+            let (x, y) = (i - prefix, j - prefix);
+            lengths[x][y] = if equivalent(&source[i], &target[j]) {
+                lengths[x + 1][y + 1] + 1
+            } else {
+                lengths[x + 1][y].max(lengths[x][y + 1])
+            };
+        }
+    }
+
+    let mut steps = (0..prefix).map(|i| Step::Same(i, i)).collect::<Vec<_>>();
+    let (mut i, mut j) = (prefix, prefix);
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    loop {
+        let common = i < rows && j < columns && equivalent(&source[i], &target[j]);
+        if common || (i == rows && j == columns) {
+            let paired = removed.len().min(added.len());
+            steps.extend((0..paired).map(|k| Step::Changed(removed[k], added[k])));
+            steps.extend(removed[paired..].iter().map(|&i| Step::Removed(i)));
+            steps.extend(added[paired..].iter().map(|&j| Step::Added(j)));
+            removed.clear();
+            added.clear();
+
+            if !common {
+                break;
+            }
+            steps.push(Step::Same(i, j));
+            i += 1;
+            j += 1;
+        } else if j == columns
+            || (i < rows
+                && lengths[i + 1 - prefix][j - prefix] >= lengths[i - prefix][j + 1 - prefix])
+        {
+            removed.push(i);
+            i += 1;
+        } else {
+            added.push(j);
+            j += 1;
+        }
+    }
+
+    steps.extend((0..suffix).map(|k| Step::Same(rows + k, columns + k)));
+    steps
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Boolean(_) => "boolean",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        Value::Null => "null",
+        Value::Undefined => "undefined",
+        Value::DateTime(_) => "datetime",
+    }
+}
+
+fn sorted_keys(object: &Object) -> Vec<&ValueKey> {
+    let mut keys = object.keys();
+    keys.sort();
+    keys
+}
+
+fn diff(old: &Value, new: &Value, path: &JsonPointer, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for key in sorted_keys(old) {
+                if !new.contains_key(&key) {
+                    changes.push(Change::Removed {
+                        path: path.join(key),
+                        value: old.get(key).unwrap().clone(),
+                    });
+                }
+            }
+            for key in sorted_keys(new) {
+                let value = new.get(key).unwrap();
+                match old.get(key) {
+                    Some(current) => diff(current, value, &path.join(key), changes),
+                    None => changes.push(Change::Added {
+                        path: path.join(key),
+                        value: value.clone(),
+                    }),
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for step in align(&old.values, &new.values) {
+                match step {
+                    Step::Same(..) => {}
+                    Step::Changed(i, j) => {
+                        diff(&old.values[i], &new.values[j], &path.join(j), changes)
+                    }
+                    Step::Removed(i) => changes.push(Change::Removed {
+                        path: path.join(i),
+                        value: old.values[i].clone(),
+                    }),
+                    Step::Added(j) => changes.push(Change::Added {
+                        path: path.join(j),
+                        value: new.values[j].clone(),
+                    }),
+                }
+            }
+        }
+        (old, new) if equivalent(old, new) => {}
+        (old, new) if type_name(old) != type_name(new) => changes.push(Change::TypeChanged {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        (old, new) => changes.push(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+impl Value {
+    /// Computes the structural differences between this value and `other`.
+    pub fn diff(&self, other: &Value) -> Diff {
+        let mut changes = Vec::new();
+        diff(self, other, &JsonPointer::root(), &mut changes);
+        Diff { changes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{align, Step};
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn paths(diff: &Diff) -> Vec<String> {
+        diff.iter()
+            .map(|change| change.path().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_align() {
+        let source = json(r#"[1, 2, 3, 4, 5]"#);
+        let target = json(r#"[0, 1, 3, 9, 5, 6]"#);
+        let steps = align(
+            &source.as_array().unwrap().values,
+            &target.as_array().unwrap().values,
+        );
+
+        assert_eq!(
+            steps,
+            vec![
+                Step::Added(0),
+                Step::Same(0, 1),
+                Step::Removed(1),
+                Step::Same(2, 2),
+                Step::Changed(3, 3),
+                Step::Same(4, 4),
+                Step::Added(5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = json(r#"{"a": 1, "b": [1, 2, 3], "c": {"d": "e", "f": true}, "g": 1}"#);
+        let new = json(r#"{"a": 2, "b": [0, 1, 2, 3], "c": {"d": "e"}, "g": "1", "h": null}"#);
+        let diff = old.diff(&new);
+
+        assert_eq!(paths(&diff), vec!["/a", "/b/0", "/c/f", "/g", "/h"]);
+        assert!(matches!(diff.changes()[0], Change::Changed { .. }));
+        assert!(matches!(diff.changes()[1], Change::Added { .. }));
+        assert!(matches!(diff.changes()[2], Change::Removed { .. }));
+        assert!(matches!(diff.changes()[3], Change::TypeChanged { .. }));
+        assert!(matches!(diff.changes()[4], Change::Added { .. }));
+    }
+
+    #[test]
+    fn test_diff_equivalent() {
+        assert!(json(r#"{"a": [1, {"b": 2.0}]}"#)
+            .diff(&json(r#"{"a": [1.0, {"b": 2}]}"#))
+            .is_empty());
+        assert!(1.to_value().diff(&1u8.to_value()).is_empty());
+        assert!(!Value::Null.diff(&Value::Undefined).is_empty());
+    }
+
+    #[test]
+    fn test_diff_nested_arrays() {
+        let old = json(r#"[{"id": 1, "tags": ["a"]}, {"id": 2}]"#);
+        let new = json(r#"[{"id": 1, "tags": ["a", "b"]}, {"id": 2}]"#);
+
+        assert_eq!(paths(&old.diff(&new)), vec!["/0/tags/1"]);
+    }
+
+    #[test]
+    fn test_report() {
+        let old = json(r#"{"a": 1, "b": [1]}"#);
+        let new = json(r#"{"a": "1", "b": []}"#);
+        let diff = old.diff(&new);
+
+        assert_eq!(
+            diff.to_string(),
+            "--- old\n+++ new\n\
+             @@ /a @@ type changed from number to string\n- 1\n+ \"1\"\n\
+             @@ /b/0 @@ removed\n- 1\n"
+        );
+        assert!(diff.report(true).contains("\x1b[31m- 1\x1b[0m"));
+        assert_eq!(
+            Value::Null.diff(&true.to_value()).to_string(),
+            "--- old\n+++ new\n@@ (root) @@ type changed from null to boolean\n- null\n+ true\n"
+        );
+        assert_eq!(Value::Null.diff(&Value::Null).to_string(), "");
+    }
+}
@@ -0,0 +1,355 @@
+//! Flattening of nested `Value`s into single-level objects with compound keys, and back.
+//!
+//! `Value::flatten` turns `{"menu": {"items": [{"value": "New"}]}}` into
+//! `{"menu.items.0.value": "New"}`, the shape key-value stores and env files expect, and
+//! `Value::unflatten` rebuilds the nested structure. Empty objects and arrays are kept as leaves
+//! so that they survive the round trip. Keys are not escaped: a key containing the separator is
+//! split when unflattening.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let value = Value::json_to_value(r#"{"menu": {"items": [{"value": "New"}]}}"#).unwrap();
+//!
+//! let options = FlattenOptions::new().array_index(ArrayIndexStyle::Bracketed);
+//! let flat = value.flatten(&options);
+//! assert_eq!(flat.get("menu.items[0].value"), Some(&"New".to_value()));
+//!
+//! assert_eq!(Value::Object(flat).unflatten(&options), value);
+//! ```
+use crate::prelude::*;
+use std::collections::BTreeMap;
+
+/// How array indexes are written in flattened keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayIndexStyle {
+    /// As a segment of its own: `items.0.value`.
+    #[default]
+    Dotted,
+    /// In brackets after the array: `items[0].value`.
+    Bracketed,
+}
+
+/// The options of `Value::flatten` and `Value::unflatten`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenOptions {
+    separator: String,
+    array_index: ArrayIndexStyle,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: ".".to_string(),
+            array_index: ArrayIndexStyle::default(),
+        }
+    }
+}
+
+impl FlattenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the separator between key segments, `.` by default.
+    pub fn separator<T: Into<String>>(mut self, separator: T) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Sets how array indexes are written.
+    pub fn array_index(mut self, style: ArrayIndexStyle) -> Self {
+        self.array_index = style;
+        self
+    }
+
+    fn join(&self, prefix: &str, segment: &str) -> String {
+        if prefix.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}{}{}", prefix, self.separator, segment)
+        }
+    }
+
+    fn join_index(&self, prefix: &str, index: usize) -> String {
+        match self.array_index {
+            ArrayIndexStyle::Dotted => self.join(prefix, &index.to_string()),
+            ArrayIndexStyle::Bracketed => format!("{}[{}]", prefix, index),
+        }
+    }
+
+    /// Splits a flattened key into its segments. Numeric segments, or bracketed indexes with
+    /// the `Bracketed` style, become `ValueKey::Number`.
+    fn split(&self, key: &str) -> Vec<ValueKey> {
+        let pieces: Vec<&str> = if self.separator.is_empty() {
+            vec![key]
+        } else {
+            key.split(self.separator.as_str()).collect()
+        };
+
+        let mut segments = Vec::new();
+        for piece in pieces {
+            match self.array_index {
+                ArrayIndexStyle::Dotted => segments.push(segment(piece)),
+                ArrayIndexStyle::Bracketed => {
+                    let (name, indexes) = split_brackets(piece);
+                    if !name.is_empty() || indexes.is_empty() {
+                        segments.push(ValueKey::from(name));
+                    }
+                    segments.extend(indexes.into_iter().map(ValueKey::Number));
+                }
+            }
+        }
+        segments
+    }
+}
+
+fn segment(piece: &str) -> ValueKey {
+    match piece.parse::<usize>() {
+        Ok(index) if !piece.starts_with('+') && (piece == "0" || !piece.starts_with('0')) => {
+            ValueKey::Number(index)
+        }
+        _ => ValueKey::from(piece),
+    }
+}
+
+/// Splits `name[1][2]` into `name` and its trailing indexes. A piece whose brackets do not hold
+/// indexes is kept whole.
+fn split_brackets(piece: &str) -> (&str, Vec<usize>) {
+    let mut rest = piece;
+    let mut indexes = Vec::new();
+
+    while let Some(inner) = rest.strip_suffix(']') {
+        let open = match inner.rfind('[') {
+            Some(open) => open,
+            None => break,
+        };
+        match segment(&inner[open + 1..]) {
+            ValueKey::Number(index) => indexes.push(index),
+            _ => break,
+        }
+        rest = &inner[..open];
+    }
+
+    if indexes.is_empty() {
+        return (piece, indexes);
+    }
+    indexes.reverse();
+    (rest, indexes)
+}
+
+fn flatten(value: &Value, prefix: String, options: &FlattenOptions, flat: &mut Object) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            let mut keys = object.keys();
+            keys.sort();
+            for key in keys {
+                let prefix = options.join(&prefix, &key.to_string());
+                flatten(object.get(key).unwrap(), prefix, options, flat);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, value) in array.values.iter().enumerate() {
+                flatten(value, options.join_index(&prefix, index), options, flat);
+            }
+        }
+        value => {
+            flat.insert(prefix, value.clone());
+        }
+    }
+}
+
+enum Node {
+    Leaf(Value),
+    Branch(BTreeMap<ValueKey, Node>),
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[ValueKey], value: Value) {
+        let (first, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => {
+                *self = Node::Leaf(value);
+                return;
+            }
+        };
+
+        if let Node::Leaf(_) = self {
+            *self = Node::Branch(BTreeMap::new());
+        }
+        if let Node::Branch(children) = self {
+            children
+                .entry(first.clone())
+                .or_insert_with(|| Node::Branch(BTreeMap::new()))
+                .insert(rest, value);
+        }
+    }
+
+    /// Builds the value of a node. A branch whose keys are exactly the indexes `0..n` becomes
+    /// an `Array`; any other branch becomes an `Object`.
+    fn into_value(self) -> Value {
+        match self {
+            Node::Leaf(value) => value,
+            Node::Branch(children) => {
+                let is_array = children
+                    .keys()
+                    .enumerate()
+                    .all(|(position, key)| *key == ValueKey::Number(position));
+
+                if is_array && !children.is_empty() {
+                    Value::Array(Array::from(
+                        children
+                            .into_values()
+                            .map(Node::into_value)
+                            .collect::<Vec<_>>(),
+                    ))
+                } else {
+                    let mut object = Object::default();
+                    for (key, child) in children {
+                        object.insert(&key, child.into_value());
+                    }
+                    Value::Object(object)
+                }
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Flattens the value into a single-level object whose keys are the paths of the leaves,
+    /// joined with the separator of `options`. A scalar flattens to an object with the empty
+    /// key.
+    pub fn flatten(&self, options: &FlattenOptions) -> Object {
+        let mut flat = Object::default();
+        flatten(self, String::new(), options, &mut flat);
+        flat
+    }
+
+    /// Rebuilds the nested value of a flattened object. Numeric segments become
+    /// `ValueKey::Number` keys, and containers indexed `0..n` become arrays. Keys are applied in
+    /// sorted order, so when a key such as `a` is also the prefix of others such as `a.b`, the
+    /// nested keys win. Values other than objects are returned as they are.
+    ///
+    /// With the `Dotted` style an object whose keys are `"0"` to `"n"` cannot be told apart from
+    /// an array and comes back as one; the `Bracketed` style keeps the two distinct.
+    pub fn unflatten(&self, options: &FlattenOptions) -> Value {
+        let object = match self {
+            Value::Object(object) => object,
+            value => return value.clone(),
+        };
+
+        let mut entries = object
+            .iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+
+        let mut root = Node::Branch(BTreeMap::new());
+        for (key, value) in entries {
+            if key.is_empty() && object.len() == 1 {
+                return value.clone();
+            }
+            root.insert(&options.split(&key), value.clone());
+        }
+        root.into_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    const MENU: &str = r#"{"menu": {"id": "file", "popup": {"menuitem": [{"value": "New"}, {"value": "Open"}]}, "empty": {}, "none": []}}"#;
+
+    #[test]
+    fn test_flatten() {
+        let flat = json(MENU).flatten(&FlattenOptions::new());
+
+        assert_eq!(flat.len(), 5);
+        assert_eq!(flat.get("menu.id"), Some(&"file".to_value()));
+        assert_eq!(
+            flat.get("menu.popup.menuitem.0.value"),
+            Some(&"New".to_value())
+        );
+        assert_eq!(
+            flat.get("menu.popup.menuitem.1.value"),
+            Some(&"Open".to_value())
+        );
+        assert_eq!(
+            flat.get("menu.empty"),
+            Some(&Value::Object(Object::default()))
+        );
+        assert_eq!(flat.get("menu.none"), Some(&Value::Array(Array::new())));
+    }
+
+    #[test]
+    fn test_flatten_options() {
+        let options = FlattenOptions::new()
+            .separator("__")
+            .array_index(ArrayIndexStyle::Bracketed);
+        let flat = json(MENU).flatten(&options);
+
+        assert_eq!(
+            flat.get("menu__popup__menuitem[1]__value"),
+            Some(&"Open".to_value())
+        );
+
+        let flat = json(r#"[[1, 2], 3]"#).flatten(&options);
+        assert_eq!(flat.get("[0][1]"), Some(&2.to_value()));
+        assert_eq!(flat.get("[1]"), Some(&3.to_value()));
+
+        let flat = 1.to_value().flatten(&options);
+        assert_eq!(flat.get(""), Some(&1.to_value()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let documents = [MENU, r#"[[1, 2], {"a": [true]}]"#, r#""text""#, r#"{}"#];
+        let styles = [
+            FlattenOptions::new(),
+            FlattenOptions::new()
+                .separator("/")
+                .array_index(ArrayIndexStyle::Bracketed),
+        ];
+
+        for document in documents {
+            for options in &styles {
+                let value = json(document);
+                let flat = Value::Object(value.flatten(options));
+                assert_value_eq!(flat.unflatten(options), value, "{}", document);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unflatten_keys() {
+        let flat = Value::from(vec![
+            ("a.0", 1.to_value()),
+            ("a.2", 2.to_value()),
+            ("b.01", 3.to_value()),
+            ("c", 4.to_value()),
+            ("c.d", 5.to_value()),
+        ]);
+        let value = flat.unflatten(&FlattenOptions::new());
+
+        let a = value.get("a").unwrap().as_object().unwrap();
+        assert_eq!(a.get(0usize), Some(&1.to_value()));
+        assert_eq!(a.get(2usize), Some(&2.to_value()));
+        let mut keys = a.keys();
+        keys.sort();
+        assert_eq!(keys, vec![&ValueKey::Number(0), &ValueKey::Number(2)]);
+        assert_value_eq!(value.get("b").unwrap(), json(r#"{"01": 3}"#));
+        assert_value_eq!(value.get("c").unwrap(), json(r#"{"d": 5}"#));
+
+        let options = FlattenOptions::new().array_index(ArrayIndexStyle::Bracketed);
+        let flat = Value::from(vec![("a[0]", 1.to_value()), ("b.0", 2.to_value())]);
+        assert_value_eq!(
+            flat.unflatten(&options),
+            json(r#"{"a": [1], "b": {"0": 2}}"#)
+        );
+    }
+}
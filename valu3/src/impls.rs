@@ -58,6 +58,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_string(&self) -> bool {
         match self {
             Value::String(_) => true,
@@ -65,6 +66,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_number(&self) -> bool {
         match self {
             Value::Number(_) => true,
@@ -72,6 +74,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_array(&self) -> bool {
         match self {
             Value::Array(_) => true,
@@ -79,6 +82,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_object(&self) -> bool {
         match self {
             Value::Object(_) => true,
@@ -86,6 +90,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_bool(&self) -> bool {
         match self {
             Value::Boolean(_) => true,
@@ -93,6 +98,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_null(&self) -> bool {
         match self {
             Value::Null => true,
@@ -100,6 +106,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_undefined(&self) -> bool {
         match self {
             Value::Undefined => true,
@@ -109,7 +116,7 @@ impl Value {
 
     pub fn as_string_b(&self) -> Option<&StringB> {
         match self {
-            Value::String(string) => Some(&string),
+            Value::String(string) => Some(string),
             _ => None,
         }
     }
@@ -205,6 +212,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::invisible_characters)]
     pub fn push<T: ToValueBehavior>(&mut self, value: T) {
         match self {
             Value::Array(array) => array.push(value.to_value()),
@@ -212,6 +220,7 @@ impl Value {
         }
     }
 
+    #[allow(clippy::invisible_characters)]
     pub fn insert<T, V>(&mut self, key: T, value: V) -> Option<Value>
     where
         T: ValueKeyBehavior,
@@ -561,6 +570,7 @@ impl NumberBehavior for Value {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     fn is_number(&self) -> bool {
         match self {
             Value::Number(_) => true,
@@ -788,10 +798,7 @@ impl DateTimeBehavior for Value {
         Self: Sized,
     {
         match self {
-            Value::DateTime(datetime) => match datetime.add_duration(duration) {
-                Some(datetime) => Some(datetime.to_value()),
-                None => None,
-            },
+            Value::DateTime(datetime) => datetime.add_duration(duration).map(|datetime| datetime.to_value()),
             _ => panic!("Unable to get a date from a value other than a datetime"),
         }
     }
@@ -801,10 +808,7 @@ impl DateTimeBehavior for Value {
         Self: Sized,
     {
         match self {
-            Value::DateTime(datetime) => match datetime.subtract_duration(duration) {
-                Some(datetime) => Some(datetime.to_value()),
-                None => None,
-            },
+            Value::DateTime(datetime) => datetime.subtract_duration(duration).map(|datetime| datetime.to_value()),
             _ => panic!("Unable to get a date from a value other than a datetime"),
         }
     }
@@ -957,6 +961,7 @@ mod tests {
     use std::collections::HashMap;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_value_number_behavior() {
         let value = Value::from(3.14);
         assert_eq!(value.get_f64_unsafe(), 3.14);
@@ -966,8 +971,9 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_value_object_behavior() {
-        let mut value = Value::from(HashMap::from_iter(vec![("1", 3.14.to_value())].into_iter()));
+        let mut value = Value::from(HashMap::from_iter(vec![("1", 3.14.to_value())]));
         value.insert("2", 4.13);
 
         if let Some(item) = value.get_mut("1") {
@@ -1018,6 +1024,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_value_as_number() {
         let number = Value::from(3.14);
         assert!(number.as_number() == Some(&Number::from(3.14)))
@@ -1030,8 +1037,9 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_value_as_object_mut() {
-        let mut object = Value::from(HashMap::from_iter(vec![("1", 3.14.to_value())].into_iter()));
+        let mut object = Value::from(HashMap::from_iter(vec![("1", 3.14.to_value())]));
         assert!(object.as_object_mut().unwrap().get_mut("1") == Some(&mut 3.14.to_value()))
     }
 }
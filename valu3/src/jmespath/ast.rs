@@ -0,0 +1,353 @@
+use super::JmesPathError;
+use crate::parser::json::unescape;
+use crate::prelude::*;
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
+
+#[derive(Parser)]
+#[grammar = "jmespath/jmespath.pest"]
+struct JmesPathParser;
+
+/// A node of a compiled JMESPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Current,
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Literal(Value),
+    Subexpr(Box<Node>, Box<Node>),
+    Projection(Box<Node>, Box<Node>),
+    ValueProjection(Box<Node>, Box<Node>),
+    FilterProjection(Box<Node>, Box<Node>, Box<Node>),
+    Flatten(Box<Node>),
+    MultiList(Vec<Node>),
+    MultiHash(Vec<(String, Node)>),
+    Comparison(Comparator, Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Pipe(Box<Node>, Box<Node>),
+    Function(String, Vec<Node>),
+    Expref(Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+/// A postfix segment of a chain such as `.foo`, `[0]` or `[?a]`, waiting to be folded into the
+/// tree.
+enum Segment {
+    Dot(Node),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Wildcard,
+    Values,
+    Filter(Node),
+    Flatten,
+}
+
+impl Segment {
+    /// The left binding power of the segment, as defined by the reference implementation.
+    fn binding_power(&self) -> u8 {
+        match self {
+            Segment::Flatten => 9,
+            Segment::Filter(_) => 21,
+            Segment::Dot(_) | Segment::Values => 40,
+            Segment::Index(_) | Segment::Slice(..) | Segment::Wildcard => 55,
+        }
+    }
+
+    /// The binding power used to parse the right hand side of a projection started by the
+    /// segment, or `None` if the segment does not start a projection.
+    fn projection_power(&self) -> Option<u8> {
+        match self {
+            Segment::Flatten => Some(9),
+            Segment::Wildcard | Segment::Values | Segment::Slice(..) => Some(20),
+            Segment::Filter(_) => Some(21),
+            Segment::Dot(_) | Segment::Index(_) => None,
+        }
+    }
+}
+
+const NOT_POWER: u8 = 45;
+
+pub(crate) fn parse(expression: &str) -> Result<Node, JmesPathError> {
+    let mut pairs = JmesPathParser::parse(Rule::jmespath, expression)
+        .map_err(|err| JmesPathError::Syntax(err.to_string()))?;
+
+    match pairs.next() {
+        Some(pair) => build_expression(pair),
+        None => Err(JmesPathError::Syntax("empty expression".to_string())),
+    }
+}
+
+fn build_expression(pair: Pair<Rule>) -> Result<Node, JmesPathError> {
+    match pair.as_rule() {
+        Rule::expression => fold(pair.into_inner(), |lhs, _, rhs| {
+            Node::Pipe(Box::new(lhs), Box::new(rhs))
+        }),
+        Rule::or_expr => fold(pair.into_inner(), |lhs, _, rhs| {
+            Node::Or(Box::new(lhs), Box::new(rhs))
+        }),
+        Rule::and_expr => fold(pair.into_inner(), |lhs, _, rhs| {
+            Node::And(Box::new(lhs), Box::new(rhs))
+        }),
+        Rule::cmp_expr => fold(pair.into_inner(), |lhs, comparator, rhs| {
+            Node::Comparison(comparator.unwrap(), Box::new(lhs), Box::new(rhs))
+        }),
+        Rule::unary => build_unary(pair.into_inner()),
+        _ => Err(unexpected(&pair)),
+    }
+}
+
+/// Folds a left associative sequence of operands, optionally separated by comparators.
+fn fold<F>(mut pairs: Pairs<Rule>, join: F) -> Result<Node, JmesPathError>
+where
+    F: Fn(Node, Option<Comparator>, Node) -> Node,
+{
+    let mut node = match pairs.next() {
+        Some(pair) => build_expression(pair)?,
+        None => return Err(JmesPathError::Syntax("missing operand".to_string())),
+    };
+
+    while let Some(pair) = pairs.next() {
+        let (comparator, operand) = if pair.as_rule() == Rule::comparator {
+            let comparator = match pair.as_str() {
+                "==" => Comparator::Equal,
+                "!=" => Comparator::NotEqual,
+                "<" => Comparator::LessThan,
+                "<=" => Comparator::LessThanOrEqual,
+                ">" => Comparator::GreaterThan,
+                _ => Comparator::GreaterThanOrEqual,
+            };
+            match pairs.next() {
+                Some(operand) => (Some(comparator), operand),
+                None => return Err(JmesPathError::Syntax("missing operand".to_string())),
+            }
+        } else {
+            (None, pair)
+        };
+
+        node = join(node, comparator, build_expression(operand)?);
+    }
+
+    Ok(node)
+}
+
+fn build_unary(pairs: Pairs<Rule>) -> Result<Node, JmesPathError> {
+    let mut nots = 0;
+    let mut head = None;
+    let mut segments = Vec::new();
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::not => nots += 1,
+            _ if head.is_none() => match build_segment(pair.clone())? {
+                // A bracket or wildcard at the start of a chain applies to the current node.
+                Some(segment) => {
+                    head = Some(Node::Current);
+                    segments.push(segment);
+                }
+                None => head = Some(build_head(pair)?),
+            },
+            _ => match build_segment(pair.clone())? {
+                Some(segment) => segments.push(segment),
+                None => return Err(unexpected(&pair)),
+            },
+        }
+    }
+
+    let head = head.unwrap_or(Node::Current);
+
+    if nots == 0 {
+        return Ok(build_chain(head, segments));
+    }
+
+    // `!` binds tighter than `.`, so only the segments above its binding power belong to the
+    // negated operand; the rest are applied to the negation itself.
+    let split = segments
+        .iter()
+        .position(|segment| segment.binding_power() <= NOT_POWER)
+        .unwrap_or(segments.len());
+    let rest = segments.split_off(split);
+
+    let mut node = build_chain(head, segments);
+    for _ in 0..nots {
+        node = Node::Not(Box::new(node));
+    }
+
+    Ok(build_chain(node, rest))
+}
+
+/// Folds a chain of segments into a tree. A segment that starts a projection takes every
+/// following segment that binds tighter than it as the right hand side of the projection.
+fn build_chain(head: Node, segments: Vec<Segment>) -> Node {
+    let mut node = head;
+    let mut segments = segments.into_iter().peekable();
+
+    while let Some(segment) = segments.next() {
+        let rhs = match segment.projection_power() {
+            Some(power) => {
+                let mut rhs = Vec::new();
+                while let Some(next) = segments.next_if(|next| next.binding_power() > power) {
+                    rhs.push(next);
+                }
+                Some(Box::new(build_chain(Node::Current, rhs)))
+            }
+            None => None,
+        };
+
+        node = match (segment, rhs) {
+            (Segment::Dot(child), _) => Node::Subexpr(Box::new(node), Box::new(child)),
+            (Segment::Index(index), _) => {
+                Node::Subexpr(Box::new(node), Box::new(Node::Index(index)))
+            }
+            (Segment::Slice(start, stop, step), Some(rhs)) => Node::Projection(
+                Box::new(Node::Subexpr(
+                    Box::new(node),
+                    Box::new(Node::Slice(start, stop, step)),
+                )),
+                rhs,
+            ),
+            (Segment::Wildcard, Some(rhs)) => Node::Projection(Box::new(node), rhs),
+            (Segment::Values, Some(rhs)) => Node::ValueProjection(Box::new(node), rhs),
+            (Segment::Filter(condition), Some(rhs)) => {
+                Node::FilterProjection(Box::new(node), Box::new(condition), rhs)
+            }
+            (Segment::Flatten, Some(rhs)) => {
+                Node::Projection(Box::new(Node::Flatten(Box::new(node))), rhs)
+            }
+            (_, None) => unreachable!("projection segments always carry a right hand side"),
+        };
+    }
+
+    node
+}
+
+fn build_segment(pair: Pair<Rule>) -> Result<Option<Segment>, JmesPathError> {
+    let segment = match pair.as_rule() {
+        Rule::dot => {
+            let child = match pair.into_inner().next() {
+                Some(child) => child,
+                None => return Err(JmesPathError::Syntax("expected an identifier".to_string())),
+            };
+            match child.as_rule() {
+                Rule::wildcard_values => Segment::Values,
+                _ => Segment::Dot(build_head(child)?),
+            }
+        }
+        Rule::index => Segment::Index(parse_number(pair.into_inner().next())?.unwrap_or(0)),
+        Rule::slice => {
+            let mut parts = pair.into_inner();
+            let start = parse_number(parts.next().and_then(|part| part.into_inner().next()))?;
+            let stop = parse_number(parts.next().and_then(|part| part.into_inner().next()))?;
+            let step = parse_number(parts.next().and_then(|part| part.into_inner().next()))?;
+            Segment::Slice(start, stop, step)
+        }
+        Rule::wildcard_index => Segment::Wildcard,
+        Rule::wildcard_values => Segment::Values,
+        Rule::flatten => Segment::Flatten,
+        Rule::filter => match pair.into_inner().next() {
+            Some(condition) => Segment::Filter(build_expression(condition)?),
+            None => return Err(JmesPathError::Syntax("expected a condition".to_string())),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(segment))
+}
+
+fn build_head(pair: Pair<Rule>) -> Result<Node, JmesPathError> {
+    let node = match pair.as_rule() {
+        Rule::unquoted => Node::Field(pair.as_str().to_string()),
+        Rule::quoted => Node::Field(unescape(inner_str(pair))),
+        Rule::current => Node::Current,
+        Rule::literal => {
+            let json = inner_str(pair).replace("\\`", "`");
+            match Value::json_to_value(&json) {
+                Ok(value) => Node::Literal(value),
+                Err(_) => {
+                    return Err(JmesPathError::Syntax(format!(
+                        "invalid JSON literal: `{}`",
+                        json
+                    )))
+                }
+            }
+        }
+        Rule::raw_string => Node::Literal(inner_str(pair).replace("\\'", "'").to_value()),
+        Rule::paren | Rule::expref => {
+            let rule = pair.as_rule();
+            let inner = match pair.into_inner().next() {
+                Some(inner) => build_expression(inner)?,
+                None => return Err(JmesPathError::Syntax("expected an expression".to_string())),
+            };
+            match rule {
+                Rule::expref => Node::Expref(Box::new(inner)),
+                _ => inner,
+            }
+        }
+        Rule::function_call => {
+            let mut inner = pair.into_inner();
+            let name = match inner.next() {
+                Some(name) => name.as_str().to_string(),
+                None => return Err(JmesPathError::Syntax("expected a function name".to_string())),
+            };
+            let args = inner.map(build_expression).collect::<Result<Vec<_>, _>>()?;
+            Node::Function(name, args)
+        }
+        Rule::multi_list => Node::MultiList(
+            pair.into_inner()
+                .map(build_expression)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Rule::multi_hash => Node::MultiHash(
+            pair.into_inner()
+                .map(|key_value| {
+                    let mut inner = key_value.into_inner();
+                    let key = match inner.next() {
+                        Some(key) if key.as_rule() == Rule::quoted => unescape(inner_str(key)),
+                        Some(key) => key.as_str().to_string(),
+                        None => return Err(JmesPathError::Syntax("expected a key".to_string())),
+                    };
+                    match inner.next() {
+                        Some(value) => Ok((key, build_expression(value)?)),
+                        None => Err(JmesPathError::Syntax("expected a value".to_string())),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        _ => return Err(unexpected(&pair)),
+    };
+
+    Ok(node)
+}
+
+/// Returns the body of a delimited token such as a quoted identifier or a literal.
+fn inner_str<'a>(pair: Pair<'a, Rule>) -> &'a str {
+    pair.into_inner().next().map(|inner| inner.as_str()).unwrap_or("")
+}
+
+fn parse_number(pair: Option<Pair<Rule>>) -> Result<Option<i64>, JmesPathError> {
+    match pair {
+        Some(pair) => match pair.as_str().parse::<i64>() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => Err(JmesPathError::Syntax(format!(
+                "invalid number: {}",
+                pair.as_str()
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn unexpected(pair: &Pair<Rule>) -> JmesPathError {
+    JmesPathError::Syntax(format!("unexpected token: {}", pair.as_str()))
+}
@@ -0,0 +1,349 @@
+use super::ast::Node;
+use super::interpreter::{as_f64, interpret, is_null, type_name};
+use super::JmesPathError;
+use crate::prelude::*;
+use std::cmp::Ordering;
+
+/// An evaluated function argument. Expression references are passed unevaluated so the
+/// function can apply them to each element it visits.
+pub(crate) enum Argument<'a> {
+    Value(Value),
+    Expref(&'a Node),
+}
+
+/// The arity of every function in the standard library: the minimum number of arguments and
+/// whether more can follow.
+pub(crate) fn arity(name: &str) -> Option<(usize, bool)> {
+    let arity = match name {
+        "abs" | "avg" | "ceil" | "floor" | "keys" | "length" | "max" | "min" | "reverse"
+        | "sort" | "sum" | "to_array" | "to_string" | "to_number" | "type" | "values" => (1, false),
+        "contains" | "ends_with" | "join" | "map" | "max_by" | "min_by" | "sort_by"
+        | "starts_with" => (2, false),
+        "merge" | "not_null" => (1, true),
+        _ => return None,
+    };
+
+    Some(arity)
+}
+
+/// Validates the number of arguments of a call.
+pub(crate) fn check_arity(name: &str, count: usize) -> Result<(), JmesPathError> {
+    match arity(name) {
+        None => Err(JmesPathError::UnknownFunction(name.to_string())),
+        Some((min, variadic)) if count < min || (!variadic && count > min) => {
+            Err(JmesPathError::InvalidArity(format!(
+                "{}() takes {}{} argument(s), {} given",
+                name,
+                if variadic { "at least " } else { "" },
+                min,
+                count
+            )))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+pub(crate) fn call(name: &str, args: Vec<Argument>) -> Result<Value, JmesPathError> {
+    check_arity(name, args.len())?;
+
+    let mut args = args.into_iter();
+
+    match name {
+        "abs" => Ok(number_value(number(name, next(&mut args))?.abs())),
+        "avg" => {
+            let numbers = numbers(name, next(&mut args))?;
+            if numbers.is_empty() {
+                Ok(Value::Null)
+            } else {
+                Ok(number_value(
+                    numbers.iter().sum::<f64>() / numbers.len() as f64,
+                ))
+            }
+        }
+        "ceil" => Ok(number_value(number(name, next(&mut args))?.ceil())),
+        "floor" => Ok(number_value(number(name, next(&mut args))?.floor())),
+        "contains" => {
+            let subject = value(name, next(&mut args))?;
+            let search = value(name, next(&mut args))?;
+            match subject {
+                Value::Array(array) => Ok(Value::Boolean(
+                    array
+                        .values
+                        .iter()
+                        .any(|item| super::interpreter::is_equal(item, &search)),
+                )),
+                Value::String(string) => Ok(Value::Boolean(match search {
+                    Value::String(search) => string.as_str().contains(search.as_str()),
+                    _ => false,
+                })),
+                other => Err(invalid_type(name, "array or string", &other)),
+            }
+        }
+        "ends_with" => {
+            let subject = string(name, next(&mut args))?;
+            let suffix = string(name, next(&mut args))?;
+            Ok(Value::Boolean(subject.ends_with(&suffix)))
+        }
+        "starts_with" => {
+            let subject = string(name, next(&mut args))?;
+            let prefix = string(name, next(&mut args))?;
+            Ok(Value::Boolean(subject.starts_with(&prefix)))
+        }
+        "join" => {
+            let glue = string(name, next(&mut args))?;
+            let strings = strings(name, next(&mut args))?;
+            Ok(strings.join(&glue).to_value())
+        }
+        "keys" => Ok(object(name, next(&mut args))?
+            .keys()
+            .into_iter()
+            .map(|key| key.to_string().to_value())
+            .collect::<Vec<_>>()
+            .to_value()),
+        "values" => Ok(object(name, next(&mut args))?
+            .values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .to_value()),
+        "length" => match value(name, next(&mut args))? {
+            Value::String(string) => Ok(string.as_str().chars().count().to_value()),
+            Value::Array(array) => Ok(array.len().to_value()),
+            Value::Object(object) => Ok(object.len().to_value()),
+            other => Err(invalid_type(name, "string, array or object", &other)),
+        },
+        "map" => {
+            let expression = expref(name, next(&mut args))?;
+            let array = array(name, next(&mut args))?;
+            let values = array
+                .values
+                .iter()
+                .map(|item| interpret(expression, item))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(values.to_value())
+        }
+        "max" | "min" => {
+            let items = sortable(name, array(name, next(&mut args))?.values)?;
+            let ordering = if name == "max" {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            Ok(items
+                .into_iter()
+                .reduce(|best, item| {
+                    if compare_sortable(&item, &best) == ordering {
+                        item
+                    } else {
+                        best
+                    }
+                })
+                .unwrap_or(Value::Null))
+        }
+        "max_by" | "min_by" => {
+            let array = array(name, next(&mut args))?;
+            let expression = expref(name, next(&mut args))?;
+            let keys = sort_keys(name, &array, expression)?;
+            let ordering = if name == "max_by" {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            let best = (0..keys.len()).reduce(|best, index| {
+                if compare_sortable(&keys[index], &keys[best]) == ordering {
+                    index
+                } else {
+                    best
+                }
+            });
+            Ok(best
+                .and_then(|index| array.get(index).cloned())
+                .unwrap_or(Value::Null))
+        }
+        "merge" => {
+            let mut merged = Object::default();
+            for arg in args {
+                for (key, value) in object(name, arg)?.iter() {
+                    merged.insert(key, value.clone());
+                }
+            }
+            Ok(merged.to_value())
+        }
+        "not_null" => {
+            for arg in args {
+                let value = value(name, arg)?;
+                if !is_null(&value) {
+                    return Ok(value);
+                }
+            }
+            Ok(Value::Null)
+        }
+        "reverse" => match value(name, next(&mut args))? {
+            Value::String(string) => Ok(string.as_str().chars().rev().collect::<String>().to_value()),
+            Value::Array(mut array) => {
+                array.values.reverse();
+                Ok(array.to_value())
+            }
+            other => Err(invalid_type(name, "string or array", &other)),
+        },
+        "sort" => {
+            let mut items = sortable(name, array(name, next(&mut args))?.values)?;
+            items.sort_by(compare_sortable);
+            Ok(items.to_value())
+        }
+        "sort_by" => {
+            let array = array(name, next(&mut args))?;
+            let expression = expref(name, next(&mut args))?;
+            let keys = sort_keys(name, &array, expression)?;
+            let mut indexes = (0..keys.len()).collect::<Vec<_>>();
+            indexes.sort_by(|a, b| compare_sortable(&keys[*a], &keys[*b]));
+            Ok(indexes
+                .into_iter()
+                .filter_map(|index| array.get(index).cloned())
+                .collect::<Vec<_>>()
+                .to_value())
+        }
+        "sum" => Ok(number_value(numbers(name, next(&mut args))?.iter().sum())),
+        "to_array" => match value(name, next(&mut args))? {
+            Value::Array(array) => Ok(array.to_value()),
+            other => Ok(vec![other].to_value()),
+        },
+        "to_string" => match value(name, next(&mut args))? {
+            Value::String(string) => Ok(string.to_value()),
+            other => Ok(other.to_json(JsonMode::Inline).to_value()),
+        },
+        "to_number" => match value(name, next(&mut args))? {
+            Value::Number(number) => Ok(number.to_value()),
+            Value::String(string) => Ok(match Number::try_from(string.as_str()) {
+                Ok(number) if number.to_f64().map(f64::is_finite).unwrap_or(false) => {
+                    number.to_value()
+                }
+                _ => Value::Null,
+            }),
+            _ => Ok(Value::Null),
+        },
+        "type" => Ok(type_name(&value(name, next(&mut args))?).to_value()),
+        _ => Err(JmesPathError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// Takes the next argument, whose presence was guaranteed by `check_arity`.
+fn next<'a>(args: &mut std::vec::IntoIter<Argument<'a>>) -> Argument<'a> {
+    args.next().expect("arity is checked before the call")
+}
+
+/// Converts a float result back into a number, keeping integral results as integers.
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        (number as i64).to_value()
+    } else {
+        number.to_value()
+    }
+}
+
+fn invalid_type(name: &str, expected: &str, found: &Value) -> JmesPathError {
+    JmesPathError::InvalidType(format!(
+        "{}() expected {}, found {}",
+        name,
+        expected,
+        type_name(found)
+    ))
+}
+
+fn value(name: &str, arg: Argument) -> Result<Value, JmesPathError> {
+    match arg {
+        Argument::Value(value) => Ok(value),
+        Argument::Expref(_) => Err(JmesPathError::InvalidType(format!(
+            "{}() does not accept an expression reference",
+            name
+        ))),
+    }
+}
+
+fn expref<'a>(name: &str, arg: Argument<'a>) -> Result<&'a Node, JmesPathError> {
+    match arg {
+        Argument::Expref(node) => Ok(node),
+        Argument::Value(value) => Err(invalid_type(name, "an expression reference", &value)),
+    }
+}
+
+fn number(name: &str, arg: Argument) -> Result<f64, JmesPathError> {
+    let value = value(name, arg)?;
+    as_f64(&value).ok_or_else(|| invalid_type(name, "number", &value))
+}
+
+fn string(name: &str, arg: Argument) -> Result<String, JmesPathError> {
+    match value(name, arg)? {
+        Value::String(string) => Ok(string.as_string()),
+        Value::DateTime(datetime) => Ok(datetime.to_string()),
+        other => Err(invalid_type(name, "string", &other)),
+    }
+}
+
+fn array(name: &str, arg: Argument) -> Result<Array, JmesPathError> {
+    match value(name, arg)? {
+        Value::Array(array) => Ok(array),
+        other => Err(invalid_type(name, "array", &other)),
+    }
+}
+
+fn object(name: &str, arg: Argument) -> Result<Object, JmesPathError> {
+    match value(name, arg)? {
+        Value::Object(object) => Ok(object),
+        other => Err(invalid_type(name, "object", &other)),
+    }
+}
+
+fn numbers(name: &str, arg: Argument) -> Result<Vec<f64>, JmesPathError> {
+    array(name, arg)?
+        .values
+        .iter()
+        .map(|item| as_f64(item).ok_or_else(|| invalid_type(name, "array of numbers", item)))
+        .collect()
+}
+
+fn strings(name: &str, arg: Argument) -> Result<Vec<String>, JmesPathError> {
+    array(name, arg)?
+        .values
+        .into_iter()
+        .map(|item| match item {
+            Value::String(string) => Ok(string.as_string()),
+            other => Err(invalid_type(name, "array of strings", &other)),
+        })
+        .collect()
+}
+
+/// Ensures every item is a number, or every item is a string.
+fn sortable(name: &str, items: Vec<Value>) -> Result<Vec<Value>, JmesPathError> {
+    let expected = match items.first() {
+        None => return Ok(items),
+        Some(first) => type_name(first),
+    };
+
+    match items
+        .iter()
+        .find(|item| type_name(item) != expected || !matches!(expected, "number" | "string"))
+    {
+        Some(item) => Err(invalid_type(name, "array of numbers or strings", item)),
+        None => Ok(items),
+    }
+}
+
+fn sort_keys(name: &str, array: &Array, expression: &Node) -> Result<Vec<Value>, JmesPathError> {
+    let keys = array
+        .values
+        .iter()
+        .map(|item| interpret(expression, item))
+        .collect::<Result<Vec<_>, _>>()?;
+    sortable(name, keys)
+}
+
+fn compare_sortable(lhs: &Value, rhs: &Value) -> Ordering {
+    match (lhs, rhs) {
+        (Value::Number(_), Value::Number(_)) => as_f64(lhs)
+            .partial_cmp(&as_f64(rhs))
+            .unwrap_or(Ordering::Equal),
+        (Value::String(lhs), Value::String(rhs)) => lhs.as_str().cmp(rhs.as_str()),
+        _ => Ordering::Equal,
+    }
+}
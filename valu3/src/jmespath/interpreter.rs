@@ -0,0 +1,281 @@
+use super::ast::{Comparator, Node};
+use super::functions::{self, Argument};
+use super::JmesPathError;
+use crate::prelude::*;
+
+/// Evaluates a node against the current value.
+pub(crate) fn interpret(node: &Node, data: &Value) -> Result<Value, JmesPathError> {
+    match node {
+        Node::Current => Ok(data.clone()),
+        Node::Field(name) => Ok(match data {
+            Value::Object(object) => object.get(name.as_str()).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }),
+        Node::Index(index) => Ok(match data {
+            Value::Array(array) => resolve_index(*index, array.len())
+                .and_then(|index| array.get(index).cloned())
+                .unwrap_or(Value::Null),
+            _ => Value::Null,
+        }),
+        Node::Slice(start, stop, step) => match data {
+            Value::Array(array) => Ok(slice(array, *start, *stop, *step)?.to_value()),
+            _ => Ok(Value::Null),
+        },
+        Node::Literal(value) => Ok(value.clone()),
+        Node::Subexpr(lhs, rhs) => interpret(rhs, &interpret(lhs, data)?),
+        Node::Pipe(lhs, rhs) => interpret(rhs, &interpret(lhs, data)?),
+        Node::Projection(lhs, rhs) => match interpret(lhs, data)? {
+            Value::Array(array) => project(array.values.iter(), rhs),
+            _ => Ok(Value::Null),
+        },
+        Node::ValueProjection(lhs, rhs) => match interpret(lhs, data)? {
+            Value::Object(object) => project(object.values().into_iter(), rhs),
+            _ => Ok(Value::Null),
+        },
+        Node::FilterProjection(lhs, condition, rhs) => match interpret(lhs, data)? {
+            Value::Array(array) => {
+                let mut matches = Vec::new();
+                for item in array.values.iter() {
+                    if is_truthy(&interpret(condition, item)?) {
+                        matches.push(item);
+                    }
+                }
+                project(matches.into_iter(), rhs)
+            }
+            _ => Ok(Value::Null),
+        },
+        Node::Flatten(node) => match interpret(node, data)? {
+            Value::Array(array) => {
+                let mut flattened = Array::new();
+                for item in array {
+                    match item {
+                        Value::Array(inner) => flattened.values.extend(inner),
+                        item => flattened.push(item),
+                    }
+                }
+                Ok(flattened.to_value())
+            }
+            _ => Ok(Value::Null),
+        },
+        Node::MultiList(nodes) => {
+            if is_null(data) {
+                return Ok(Value::Null);
+            }
+            let values = nodes
+                .iter()
+                .map(|node| interpret(node, data))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(values.to_value())
+        }
+        Node::MultiHash(pairs) => {
+            if is_null(data) {
+                return Ok(Value::Null);
+            }
+            let mut object = Object::default();
+            for (key, node) in pairs {
+                object.insert(key.as_str(), interpret(node, data)?);
+            }
+            Ok(object.to_value())
+        }
+        Node::Comparison(comparator, lhs, rhs) => {
+            let lhs = interpret(lhs, data)?;
+            let rhs = interpret(rhs, data)?;
+            Ok(compare(*comparator, &lhs, &rhs))
+        }
+        Node::And(lhs, rhs) => {
+            let lhs = interpret(lhs, data)?;
+            if is_truthy(&lhs) {
+                interpret(rhs, data)
+            } else {
+                Ok(lhs)
+            }
+        }
+        Node::Or(lhs, rhs) => {
+            let lhs = interpret(lhs, data)?;
+            if is_truthy(&lhs) {
+                Ok(lhs)
+            } else {
+                interpret(rhs, data)
+            }
+        }
+        Node::Not(node) => Ok(Value::Boolean(!is_truthy(&interpret(node, data)?))),
+        Node::Function(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    Node::Expref(node) => Ok(Argument::Expref(node)),
+                    node => interpret(node, data).map(Argument::Value),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            functions::call(name, args)
+        }
+        // An expression reference only has a meaning as a function argument.
+        Node::Expref(_) => Ok(Value::Null),
+    }
+}
+
+/// Applies the right hand side of a projection to every item, dropping the null results.
+fn project<'a, I>(items: I, rhs: &Node) -> Result<Value, JmesPathError>
+where
+    I: Iterator<Item = &'a Value>,
+{
+    let mut results = Array::new();
+    for item in items {
+        let value = interpret(rhs, item)?;
+        if !is_null(&value) {
+            results.push(value);
+        }
+    }
+    Ok(results.to_value())
+}
+
+fn compare(comparator: Comparator, lhs: &Value, rhs: &Value) -> Value {
+    match comparator {
+        Comparator::Equal => Value::Boolean(is_equal(lhs, rhs)),
+        Comparator::NotEqual => Value::Boolean(!is_equal(lhs, rhs)),
+        _ => match (as_f64(lhs), as_f64(rhs)) {
+            (Some(lhs), Some(rhs)) => Value::Boolean(match comparator {
+                Comparator::LessThan => lhs < rhs,
+                Comparator::LessThanOrEqual => lhs <= rhs,
+                Comparator::GreaterThan => lhs > rhs,
+                _ => lhs >= rhs,
+            }),
+            _ => Value::Null,
+        },
+    }
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        let index = len as i64 + index;
+        if index < 0 {
+            None
+        } else {
+            Some(index as usize)
+        }
+    } else {
+        Some(index as usize)
+    }
+}
+
+/// Slices an array with the same semantics as Python slices.
+pub(crate) fn slice(
+    array: &Array,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+) -> Result<Vec<Value>, JmesPathError> {
+    let step = step.unwrap_or(1);
+    if step == 0 {
+        return Err(JmesPathError::InvalidValue(
+            "slice step cannot be 0".to_string(),
+        ));
+    }
+
+    let len = array.len() as i64;
+    let bound = |value: Option<i64>, default: i64| match value {
+        None => default,
+        Some(value) if value < 0 => {
+            let value = len + value;
+            if value < 0 {
+                if step < 0 {
+                    -1
+                } else {
+                    0
+                }
+            } else {
+                value
+            }
+        }
+        Some(value) if value >= len => {
+            if step < 0 {
+                len - 1
+            } else {
+                len
+            }
+        }
+        Some(value) => value,
+    };
+
+    let (start, stop) = if step > 0 {
+        (bound(start, 0), bound(stop, len))
+    } else {
+        (bound(start, len - 1), bound(stop, -1))
+    };
+
+    let mut result = Vec::new();
+    let mut index = start;
+    while (step > 0 && index < stop) || (step < 0 && index > stop) {
+        if let Some(value) = array.get(index as usize) {
+            result.push(value.clone());
+        }
+        index += step;
+    }
+
+    Ok(result)
+}
+
+/// Returns `true` for JMESPath null, which includes `Value::Undefined`.
+pub(crate) fn is_null(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Undefined)
+}
+
+/// False, null, and empty strings, arrays and objects are false; everything else is true.
+pub(crate) fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Boolean(boolean) => *boolean,
+        Value::Null | Value::Undefined => false,
+        Value::String(string) => !string.is_empty(),
+        Value::Array(array) => !array.is_empty(),
+        Value::Object(object) => !object.is_empty(),
+        Value::Number(_) | Value::DateTime(_) => true,
+    }
+}
+
+/// Compares two values by content: numbers by their numeric value and objects regardless of
+/// their underlying map.
+pub(crate) fn is_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => lhs.to_f64() == rhs.to_f64(),
+        (Value::Array(lhs), Value::Array(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .values
+                    .iter()
+                    .zip(rhs.values.iter())
+                    .all(|(lhs, rhs)| is_equal(lhs, rhs))
+        }
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(key, lhs)| match rhs.get(key) {
+                    Some(rhs) => is_equal(lhs, rhs),
+                    None => false,
+                })
+        }
+        (Value::DateTime(datetime), Value::String(string))
+        | (Value::String(string), Value::DateTime(datetime)) => {
+            datetime.to_string() == string.as_str()
+        }
+        (lhs, rhs) if is_null(lhs) && is_null(rhs) => true,
+        (lhs, rhs) => lhs == rhs,
+    }
+}
+
+pub(crate) fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.to_f64(),
+        _ => None,
+    }
+}
+
+/// Returns the JMESPath type name of a value.
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Number(_) => "number",
+        Value::String(_) | Value::DateTime(_) => "string",
+        Value::Boolean(_) => "boolean",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        Value::Null | Value::Undefined => "null",
+    }
+}
@@ -0,0 +1,256 @@
+//! JMESPath query support for `Value`.
+//!
+//! Expressions are compiled once into a `JmesPath` and can then be searched against any number
+//! of documents. The full JMESPath specification is supported: sub-expressions, index and slice
+//! expressions, list, slice, object, flatten and filter projections, multiselect lists and
+//! hashes, pipes, boolean and comparison operators and the standard function library.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let value = Value::json_to_value(r#"{"people": [{"name": "a", "age": 20}, {"name": "b", "age": 30}]}"#).unwrap();
+//! let expression = JmesPath::compile("people[?age > `25`].name").unwrap();
+//!
+//! assert_eq!(expression.search(&value).unwrap(), vec!["b"].to_value());
+//! ```
+mod ast;
+mod functions;
+mod interpreter;
+
+use crate::prelude::*;
+use ast::Node;
+use std::fmt::{Display, Formatter};
+
+/// The errors raised while compiling or evaluating a JMESPath expression, named after the error
+/// types of the JMESPath specification.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum JmesPathError {
+    /// The expression is not valid JMESPath.
+    Syntax(String),
+    /// A function was called with an argument of the wrong type.
+    InvalidType(String),
+    /// A value is out of its allowed range, such as a slice step of zero.
+    InvalidValue(String),
+    /// A function was called with the wrong number of arguments.
+    InvalidArity(String),
+    /// The expression calls a function that does not exist.
+    UnknownFunction(String),
+}
+
+impl Display for JmesPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JmesPathError::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            JmesPathError::InvalidType(msg) => write!(f, "invalid type: {}", msg),
+            JmesPathError::InvalidValue(msg) => write!(f, "invalid value: {}", msg),
+            JmesPathError::InvalidArity(msg) => write!(f, "invalid arity: {}", msg),
+            JmesPathError::UnknownFunction(name) => write!(f, "unknown function: {}()", name),
+        }
+    }
+}
+
+impl From<JmesPathError> for Error {
+    fn from(error: JmesPathError) -> Self {
+        Error::JmesPath(error)
+    }
+}
+
+/// A compiled JMESPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JmesPath {
+    expression: String,
+    ast: Node,
+}
+
+impl JmesPath {
+    /// Compiles an expression. Syntax errors, unknown functions and calls with the wrong number
+    /// of arguments are reported here rather than on every search.
+    pub fn compile(expression: &str) -> Result<Self, Error> {
+        let ast = ast::parse(expression)?;
+        Self::validate(&ast)?;
+
+        Ok(Self {
+            expression: expression.to_string(),
+            ast,
+        })
+    }
+
+    /// Evaluates the expression against a value, returning a new `Value`.
+    pub fn search(&self, value: &Value) -> Result<Value, Error> {
+        Ok(interpreter::interpret(&self.ast, value)?)
+    }
+
+    /// Returns the source of the expression.
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+
+    fn validate(node: &Node) -> Result<(), JmesPathError> {
+        match node {
+            Node::Function(name, args) => {
+                functions::check_arity(name, args.len())?;
+                args.iter().try_for_each(Self::validate)
+            }
+            Node::Subexpr(lhs, rhs)
+            | Node::Projection(lhs, rhs)
+            | Node::ValueProjection(lhs, rhs)
+            | Node::Comparison(_, lhs, rhs)
+            | Node::And(lhs, rhs)
+            | Node::Or(lhs, rhs)
+            | Node::Pipe(lhs, rhs) => {
+                Self::validate(lhs)?;
+                Self::validate(rhs)
+            }
+            Node::FilterProjection(lhs, condition, rhs) => {
+                Self::validate(lhs)?;
+                Self::validate(condition)?;
+                Self::validate(rhs)
+            }
+            Node::Flatten(node) | Node::Not(node) | Node::Expref(node) => Self::validate(node),
+            Node::MultiList(nodes) => nodes.iter().try_for_each(Self::validate),
+            Node::MultiHash(pairs) => pairs.iter().try_for_each(|(_, node)| Self::validate(node)),
+            Node::Current
+            | Node::Field(_)
+            | Node::Index(_)
+            | Node::Slice(..)
+            | Node::Literal(_) => Ok(()),
+        }
+    }
+}
+
+impl Display for JmesPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl TryFrom<&str> for JmesPath {
+    type Error = Error;
+
+    fn try_from(expression: &str) -> Result<Self, Self::Error> {
+        Self::compile(expression)
+    }
+}
+
+impl Value {
+    /// Compiles and evaluates a JMESPath expression against the value. Prefer
+    /// `JmesPath::compile` when the same expression is searched more than once.
+    pub fn jmespath(&self, expression: &str) -> Result<Value, Error> {
+        JmesPath::compile(expression)?.search(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn data() -> Value {
+        Value::json_to_value(
+            r#"{
+                "people": [
+                    {"name": "alice", "age": 31, "tags": ["admin", "dev"]},
+                    {"name": "bob", "age": 25, "tags": ["dev"]},
+                    {"name": "carol", "age": 42, "tags": []}
+                ],
+                "owner": {"name": "alice", "email": "alice@example.com"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_field_and_index() {
+        let value = data();
+        assert_eq!(value.jmespath("owner.name"), Ok("alice".to_value()));
+        assert_eq!(value.jmespath("people[-1].name"), Ok("carol".to_value()));
+        assert_eq!(value.jmespath("people[5]"), Ok(Value::Null));
+    }
+
+    #[test]
+    fn test_projections() {
+        let value = data();
+        assert_eq!(
+            value.jmespath("people[*].name"),
+            Ok(vec!["alice", "bob", "carol"].to_value())
+        );
+        assert_eq!(
+            value.jmespath("people[].tags[]"),
+            Ok(vec!["admin", "dev", "dev"].to_value())
+        );
+        assert_eq!(
+            value.jmespath("people[?age > `30`].name | [0]"),
+            Ok("alice".to_value())
+        );
+        assert_eq!(
+            value.jmespath("people[:2].age"),
+            Ok(vec![31, 25].to_value())
+        );
+    }
+
+    #[test]
+    fn test_multiselect() {
+        let value = data();
+        let result = value.jmespath("owner.{who: name, contact: [email]}").unwrap();
+
+        assert_eq!(result.get("who"), Some(&"alice".to_value()));
+        assert_eq!(
+            result.get("contact"),
+            Some(&vec!["alice@example.com"].to_value())
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let value = data();
+        assert_eq!(value.jmespath("length(people)").unwrap().to_i64(), Some(3));
+        assert_eq!(
+            value.jmespath("sort_by(people, &age)[*].name"),
+            Ok(vec!["bob", "alice", "carol"].to_value())
+        );
+        assert_eq!(
+            value.jmespath("max_by(people, &age).name"),
+            Ok("carol".to_value())
+        );
+        assert_eq!(
+            value.jmespath("join(', ', people[?contains(tags, 'dev')].name)"),
+            Ok("alice, bob".to_value())
+        );
+    }
+
+    #[test]
+    fn test_compiled_expression_is_reusable() {
+        let expression = JmesPath::compile("a || b").unwrap();
+
+        assert_eq!(
+            expression.search(&Value::json_to_value(r#"{"a": 1}"#).unwrap()),
+            Ok(1.to_value())
+        );
+        assert_eq!(
+            expression.search(&Value::json_to_value(r#"{"b": 2}"#).unwrap()),
+            Ok(2.to_value())
+        );
+        assert_eq!(expression.to_string(), "a || b");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            JmesPath::compile("foo[?"),
+            Err(Error::JmesPath(JmesPathError::Syntax(_)))
+        ));
+        assert!(matches!(
+            JmesPath::compile("nope(@)"),
+            Err(Error::JmesPath(JmesPathError::UnknownFunction(_)))
+        ));
+        assert!(matches!(
+            JmesPath::compile("length(@, @)"),
+            Err(Error::JmesPath(JmesPathError::InvalidArity(_)))
+        ));
+        assert!(matches!(
+            data().jmespath("abs(owner)"),
+            Err(Error::JmesPath(JmesPathError::InvalidType(_)))
+        ));
+    }
+}
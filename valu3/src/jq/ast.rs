@@ -0,0 +1,448 @@
+use super::JqError;
+use crate::parser::json::unescape;
+use crate::prelude::*;
+use pest::iterators::Pair;
+use pest::Parser;
+
+#[derive(Parser)]
+#[grammar = "jq/jq.pest"]
+struct JqParser;
+
+/// A node of a compiled jq program.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Format(Vec<Part>),
+    Index(Box<Node>, Box<Node>),
+    Slice(Box<Node>, Option<Box<Node>>, Option<Box<Node>>),
+    Iterate(Box<Node>),
+    Pipe(Box<Node>, Box<Node>),
+    Comma(Box<Node>, Box<Node>),
+    Neg(Box<Node>),
+    Arithmetic(Operator, Box<Node>, Box<Node>),
+    Comparison(Comparator, Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Alternative(Box<Node>, Box<Node>),
+    Assign(Assignment, Box<Node>, Box<Node>),
+    Array(Option<Box<Node>>),
+    Object(Vec<(Node, Node)>),
+    Variable(String),
+    Bind(Box<Node>, String, Box<Node>),
+    Reduce(Box<Node>, String, Box<Node>, Box<Node>),
+    Foreach(Box<Node>, String, Box<Node>, Box<Node>, Option<Box<Node>>),
+    If(Vec<(Node, Node)>, Option<Box<Node>>),
+    Try(Box<Node>, Option<Box<Node>>),
+    Call(String, Vec<Node>),
+}
+
+/// A piece of an interpolated string.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Part {
+    Text(String),
+    Interpolation(Node),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Assignment {
+    /// `=`
+    Set,
+    /// `|=`
+    Update,
+    /// `+=`, `-=`, `*=`, `/=` and `%=`
+    Arithmetic(Operator),
+    /// `//=`
+    Alternative,
+}
+
+pub(crate) fn parse(program: &str) -> Result<Node, JqError> {
+    let mut pairs =
+        JqParser::parse(Rule::jq, program).map_err(|err| JqError::Syntax(err.to_string()))?;
+
+    match pairs.next() {
+        Some(pair) => build(pair),
+        None => Err(JqError::Syntax("empty program".to_string())),
+    }
+}
+
+/// Returns the children of a pair, leaving out the keywords.
+fn children(pair: Pair<Rule>) -> impl Iterator<Item = Pair<Rule>> {
+    pair.into_inner().filter(|pair| {
+        !matches!(
+            pair.as_rule(),
+            Rule::kw_and
+                | Rule::kw_or
+                | Rule::kw_as
+                | Rule::kw_if
+                | Rule::kw_then
+                | Rule::kw_elif
+                | Rule::kw_else
+                | Rule::kw_end
+                | Rule::kw_reduce
+                | Rule::kw_foreach
+                | Rule::kw_try
+                | Rule::kw_catch
+        )
+    })
+}
+
+fn build(pair: Pair<Rule>) -> Result<Node, JqError> {
+    match pair.as_rule() {
+        Rule::pipeline => {
+            let mut inner = pair.into_inner();
+            let lhs = build(expect(inner.next())?)?;
+            match inner.next() {
+                Some(rhs) => Ok(Node::Pipe(Box::new(lhs), Box::new(build(rhs)?))),
+                None => Ok(lhs),
+            }
+        }
+        Rule::comma => fold(pair.into_inner(), |lhs, _, rhs| {
+            Ok(Node::Comma(Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::alternative => {
+            let mut inner = pair.into_inner();
+            let lhs = build(expect(inner.next())?)?;
+            match inner.next() {
+                Some(rhs) => Ok(Node::Alternative(Box::new(lhs), Box::new(build(rhs)?))),
+                None => Ok(lhs),
+            }
+        }
+        Rule::assignment => fold(pair.into_inner(), |lhs, operator, rhs| {
+            let assignment = match operator.map(|operator| operator.as_str()) {
+                Some("=") => Assignment::Set,
+                Some("|=") => Assignment::Update,
+                Some("//=") => Assignment::Alternative,
+                Some(operator) => Assignment::Arithmetic(operator_from(&operator[..1])),
+                None => return Err(JqError::Syntax("missing operator".to_string())),
+            };
+            Ok(Node::Assign(assignment, Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::or_expr => fold(children(pair), |lhs, _, rhs| {
+            Ok(Node::Or(Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::and_expr => fold(children(pair), |lhs, _, rhs| {
+            Ok(Node::And(Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::comparison => fold(pair.into_inner(), |lhs, comparator, rhs| {
+            let comparator = match comparator.map(|comparator| comparator.as_str()) {
+                Some("==") => Comparator::Equal,
+                Some("!=") => Comparator::NotEqual,
+                Some("<") => Comparator::LessThan,
+                Some("<=") => Comparator::LessThanOrEqual,
+                Some(">") => Comparator::GreaterThan,
+                _ => Comparator::GreaterThanOrEqual,
+            };
+            Ok(Node::Comparison(comparator, Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::additive | Rule::multiplicative => fold(pair.into_inner(), |lhs, operator, rhs| {
+            let operator = operator_from(operator.map(|operator| operator.as_str()).unwrap_or(""));
+            Ok(Node::Arithmetic(operator, Box::new(lhs), Box::new(rhs)))
+        }),
+        Rule::unary => {
+            let mut negations = 0;
+            let mut node = None;
+            for child in pair.into_inner() {
+                match child.as_rule() {
+                    Rule::neg => negations += 1,
+                    Rule::binding => {
+                        let source = node.take().ok_or_else(|| unexpected(&child))?;
+                        let mut inner = children(child);
+                        let name = variable_name(expect(inner.next())?);
+                        let body = build(expect(inner.next())?)?;
+                        node = Some(Node::Bind(Box::new(source), name, Box::new(body)));
+                    }
+                    _ => {
+                        let mut operand = build(child)?;
+                        for _ in 0..negations {
+                            operand = Node::Neg(Box::new(operand));
+                        }
+                        node = Some(operand);
+                    }
+                }
+            }
+            node.ok_or_else(|| JqError::Syntax("expected an expression".to_string()))
+        }
+        Rule::postfix => {
+            let mut inner = pair.into_inner();
+            let mut node = build(expect(inner.next())?)?;
+            for suffix in inner {
+                node = build_suffix(node, suffix)?;
+            }
+            Ok(node)
+        }
+        _ => build_term(pair),
+    }
+}
+
+/// Folds a left associative sequence of operands separated by operators. Operators that are
+/// silent in the grammar reach the join function as `None`.
+fn fold<'a, I, F>(mut pairs: I, join: F) -> Result<Node, JqError>
+where
+    I: Iterator<Item = Pair<'a, Rule>>,
+    F: Fn(Node, Option<Pair<'a, Rule>>, Node) -> Result<Node, JqError>,
+{
+    let mut node = build(expect(pairs.next())?)?;
+
+    while let Some(pair) = pairs.next() {
+        let (operator, operand) = match pair.as_rule() {
+            Rule::assign_op | Rule::comparator | Rule::add_op | Rule::mul_op => {
+                (Some(pair), expect(pairs.next())?)
+            }
+            _ => (None, pair),
+        };
+
+        node = join(node, operator, build(operand)?)?;
+    }
+
+    Ok(node)
+}
+
+fn build_suffix(node: Node, suffix: Pair<Rule>) -> Result<Node, JqError> {
+    let node = match suffix.as_rule() {
+        Rule::optional => Node::Try(Box::new(node), None),
+        Rule::field => Node::Index(Box::new(node), Box::new(field_name(suffix)?)),
+        Rule::iterate => Node::Iterate(Box::new(node)),
+        Rule::index => Node::Index(
+            Box::new(node),
+            Box::new(build(expect(suffix.into_inner().next())?)?),
+        ),
+        Rule::slice => {
+            let mut bounds = suffix.into_inner().map(|bound| match bound.into_inner().next() {
+                Some(pipeline) => build(pipeline).map(|node| Some(Box::new(node))),
+                None => Ok(None),
+            });
+            let from = bounds.next().unwrap_or(Ok(None))?;
+            let to = bounds.next().unwrap_or(Ok(None))?;
+            Node::Slice(Box::new(node), from, to)
+        }
+        _ => return Err(unexpected(&suffix)),
+    };
+
+    Ok(node)
+}
+
+fn build_term(pair: Pair<Rule>) -> Result<Node, JqError> {
+    let node = match pair.as_rule() {
+        Rule::identity => Node::Identity,
+        Rule::recurse => Node::Recurse,
+        Rule::field => Node::Index(Box::new(Node::Identity), Box::new(field_name(pair)?)),
+        Rule::variable => Node::Variable(variable_name(pair)),
+        Rule::literal => Node::Literal(match pair.as_str() {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ => Value::Null,
+        }),
+        Rule::number => Node::Literal(parse_number(pair.as_str())?),
+        Rule::string => build_string(pair)?,
+        Rule::paren => build(expect(pair.into_inner().next())?)?,
+        Rule::array => match pair.into_inner().next() {
+            Some(pipeline) => Node::Array(Some(Box::new(build(pipeline)?))),
+            None => Node::Array(None),
+        },
+        Rule::object => Node::Object(
+            pair.into_inner()
+                .map(build_entry)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Rule::function_call => {
+            let mut inner = pair.into_inner();
+            let name = expect(inner.next())?.as_str().to_string();
+            let args = inner.map(build).collect::<Result<Vec<_>, _>>()?;
+            Node::Call(name, args)
+        }
+        Rule::reduce => {
+            let mut inner = children(pair);
+            let source = build(expect(inner.next())?)?;
+            let name = variable_name(expect(inner.next())?);
+            let init = build(expect(inner.next())?)?;
+            let update = build(expect(inner.next())?)?;
+            Node::Reduce(Box::new(source), name, Box::new(init), Box::new(update))
+        }
+        Rule::foreach => {
+            let mut inner = children(pair);
+            let source = build(expect(inner.next())?)?;
+            let name = variable_name(expect(inner.next())?);
+            let init = build(expect(inner.next())?)?;
+            let update = build(expect(inner.next())?)?;
+            let extract = match inner.next() {
+                Some(extract) => Some(Box::new(build(extract)?)),
+                None => None,
+            };
+            Node::Foreach(
+                Box::new(source),
+                name,
+                Box::new(init),
+                Box::new(update),
+                extract,
+            )
+        }
+        Rule::if_expr => {
+            let mut inner = children(pair);
+            let condition = build(expect(inner.next())?)?;
+            let then = build(expect(inner.next())?)?;
+            let mut branches = vec![(condition, then)];
+            let mut otherwise = None;
+            for branch in inner {
+                match branch.as_rule() {
+                    Rule::elif_branch => {
+                        let mut inner = children(branch);
+                        let condition = build(expect(inner.next())?)?;
+                        let then = build(expect(inner.next())?)?;
+                        branches.push((condition, then));
+                    }
+                    _ => {
+                        otherwise = Some(Box::new(build(expect(children(branch).next())?)?));
+                    }
+                }
+            }
+            Node::If(branches, otherwise)
+        }
+        Rule::try_expr => {
+            let mut inner = children(pair);
+            let body = build(expect(inner.next())?)?;
+            let handler = match inner.next() {
+                Some(handler) => Some(Box::new(build(handler)?)),
+                None => None,
+            };
+            Node::Try(Box::new(body), handler)
+        }
+        _ => return Err(unexpected(&pair)),
+    };
+
+    Ok(node)
+}
+
+/// Builds an object entry. Entries without a value, such as `{a}` or `{$a}`, take the field
+/// or the variable of the same name.
+fn build_entry(pair: Pair<Rule>) -> Result<(Node, Node), JqError> {
+    let mut inner = pair.into_inner();
+    let key = expect(inner.next())?;
+    let value = match inner.next() {
+        Some(value) => Some(build_object_value(value)?),
+        None => None,
+    };
+
+    match key.as_rule() {
+        Rule::variable => {
+            let name = variable_name(key);
+            let value = value.unwrap_or_else(|| Node::Variable(name.clone()));
+            Ok((Node::Literal(name.to_value()), value))
+        }
+        Rule::name => {
+            let key = Node::Literal(key.as_str().to_value());
+            let value =
+                value.unwrap_or_else(|| Node::Index(Box::new(Node::Identity), Box::new(key.clone())));
+            Ok((key, value))
+        }
+        Rule::string => {
+            let key = build_string(key)?;
+            let value =
+                value.unwrap_or_else(|| Node::Index(Box::new(Node::Identity), Box::new(key.clone())));
+            Ok((key, value))
+        }
+        _ => match value {
+            Some(value) => Ok((build(key)?, value)),
+            None => Err(JqError::Syntax(format!(
+                "expected a value for the key {}",
+                key.as_str()
+            ))),
+        },
+    }
+}
+
+fn build_object_value(pair: Pair<Rule>) -> Result<Node, JqError> {
+    let mut nodes = pair
+        .into_inner()
+        .map(build)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .rev();
+    let mut node = nodes
+        .next()
+        .ok_or_else(|| JqError::Syntax("expected a value".to_string()))?;
+    for lhs in nodes {
+        node = Node::Pipe(Box::new(lhs), Box::new(node));
+    }
+    Ok(node)
+}
+
+/// Builds a string literal, or a `Format` node when the string has interpolations.
+fn build_string(pair: Pair<Rule>) -> Result<Node, JqError> {
+    let mut parts = Vec::new();
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::string_chars => parts.push(Part::Text(unescape(part.as_str()))),
+            _ => parts.push(Part::Interpolation(build(expect(part.into_inner().next())?)?)),
+        }
+    }
+
+    match parts.as_slice() {
+        [] => Ok(Node::Literal("".to_value())),
+        [Part::Text(text)] => Ok(Node::Literal(text.to_value())),
+        _ => Ok(Node::Format(parts)),
+    }
+}
+
+fn field_name(pair: Pair<Rule>) -> Result<Node, JqError> {
+    let name = expect(pair.into_inner().next())?;
+    match name.as_rule() {
+        Rule::string => build_string(name),
+        _ => Ok(Node::Literal(name.as_str().to_value())),
+    }
+}
+
+fn variable_name(pair: Pair<Rule>) -> String {
+    pair.as_str().trim_start_matches('$').to_string()
+}
+
+fn operator_from(operator: &str) -> Operator {
+    match operator {
+        "+" => Operator::Add,
+        "-" => Operator::Subtract,
+        "*" => Operator::Multiply,
+        "/" => Operator::Divide,
+        _ => Operator::Modulo,
+    }
+}
+
+fn parse_number(number: &str) -> Result<Value, JqError> {
+    if !number.contains(['.', 'e', 'E']) {
+        if let Ok(number) = number.parse::<i64>() {
+            return Ok(number.to_value());
+        }
+    }
+
+    number
+        .parse::<f64>()
+        .map(|number| number.to_value())
+        .map_err(|_| JqError::Syntax(format!("invalid number: {}", number)))
+}
+
+fn expect(pair: Option<Pair<Rule>>) -> Result<Pair<Rule>, JqError> {
+    pair.ok_or_else(|| JqError::Syntax("unexpected end of program".to_string()))
+}
+
+fn unexpected(pair: &Pair<Rule>) -> JqError {
+    JqError::Syntax(format!("unexpected token: {}", pair.as_str()))
+}
@@ -0,0 +1,837 @@
+use super::ast::{Node, Operator};
+use super::interpreter::{
+    arithmetic, as_f64, collect, collect_paths, compare, delete_paths, describe,
+    entries, equals, eval, eval_paths, first, get_path, index, invalid_path, is_null, is_truthy,
+    iterate, number_value, recurse_paths, set_path, split, stop_token, to_text, type_error,
+    type_name, Env, Fault, Output, PathOutput,
+};
+use super::JqError;
+use crate::prelude::*;
+use regex::Regex;
+use std::cmp::Ordering;
+
+/// Returns `true` if a builtin with the given name takes the given number of arguments.
+pub(crate) fn exists(name: &str, arity: usize) -> bool {
+    matches!(
+        (name, arity),
+        (
+            "empty"
+                | "not"
+                | "length"
+                | "keys"
+                | "keys_unsorted"
+                | "values"
+                | "add"
+                | "any"
+                | "all"
+                | "flatten"
+                | "floor"
+                | "ceil"
+                | "round"
+                | "sqrt"
+                | "abs"
+                | "tostring"
+                | "tonumber"
+                | "tojson"
+                | "fromjson"
+                | "type"
+                | "ascii_downcase"
+                | "ascii_upcase"
+                | "sort"
+                | "unique"
+                | "min"
+                | "max"
+                | "reverse"
+                | "first"
+                | "last"
+                | "to_entries"
+                | "from_entries"
+                | "paths"
+                | "leaf_paths"
+                | "recurse"
+                | "error"
+                | "arrays"
+                | "objects"
+                | "iterables"
+                | "booleans"
+                | "numbers"
+                | "strings"
+                | "nulls"
+                | "scalars",
+            0
+        ) | (
+            "has"
+                | "in"
+                | "map"
+                | "map_values"
+                | "select"
+                | "recurse"
+                | "with_entries"
+                | "paths"
+                | "path"
+                | "del"
+                | "getpath"
+                | "delpaths"
+                | "sort_by"
+                | "group_by"
+                | "unique_by"
+                | "min_by"
+                | "max_by"
+                | "flatten"
+                | "range"
+                | "ltrimstr"
+                | "rtrimstr"
+                | "startswith"
+                | "endswith"
+                | "split"
+                | "join"
+                | "test"
+                | "contains"
+                | "inside"
+                | "first"
+                | "last"
+                | "any"
+                | "all"
+                | "error"
+                | "walk",
+            1
+        ) | ("setpath" | "range" | "limit", 2)
+    )
+}
+
+pub(crate) fn call(
+    name: &str,
+    args: &[Node],
+    input: &Value,
+    env: &Env,
+    out: Output,
+) -> Result<(), Fault> {
+    match (name, args) {
+        ("empty", []) => Ok(()),
+        ("not", []) => out(Value::Boolean(!is_truthy(input))),
+        ("error", []) => Err(Fault::Raised(Box::new(input.clone()))),
+        ("error", [message]) => eval(message, input, env, &mut |message| {
+            Err(Fault::Raised(Box::new(message)))
+        }),
+        ("length", []) => out(match input {
+            Value::Null | Value::Undefined => 0.to_value(),
+            Value::Boolean(_) => {
+                return Err(type_error(format!("{} has no length", describe(input))))
+            }
+            Value::Number(_) => number_value(as_f64(input).unwrap_or(0.0).abs()),
+            Value::String(_) | Value::DateTime(_) => to_text(input).chars().count().to_value(),
+            Value::Array(array) => array.len().to_value(),
+            Value::Object(object) => object.len().to_value(),
+        }),
+        ("keys" | "keys_unsorted", []) => out(keys(input)?),
+        ("values", []) => filter(input, !is_null(input), out),
+        ("arrays", []) => filter(input, matches!(input, Value::Array(_)), out),
+        ("objects", []) => filter(input, matches!(input, Value::Object(_)), out),
+        ("iterables", []) => filter(
+            input,
+            matches!(input, Value::Array(_) | Value::Object(_)),
+            out,
+        ),
+        ("booleans", []) => filter(input, matches!(input, Value::Boolean(_)), out),
+        ("numbers", []) => filter(input, matches!(input, Value::Number(_)), out),
+        ("strings", []) => filter(input, type_name(input) == "string", out),
+        ("nulls", []) => filter(input, is_null(input), out),
+        ("scalars", []) => filter(
+            input,
+            !matches!(input, Value::Array(_) | Value::Object(_)),
+            out,
+        ),
+        ("add", []) => {
+            let items = match input {
+                Value::Null | Value::Undefined => Vec::new(),
+                other => iterate(other)?,
+            };
+            let mut sum = Value::Null;
+            for item in items {
+                sum = arithmetic(Operator::Add, &sum, &item)?;
+            }
+            out(sum)
+        }
+        ("any", []) => out(Value::Boolean(iterate(input)?.iter().any(is_truthy))),
+        ("all", []) => out(Value::Boolean(iterate(input)?.iter().all(is_truthy))),
+        ("any" | "all", [condition]) => {
+            let mut results = Vec::new();
+            for item in iterate(input)? {
+                eval(condition, &item, env, &mut |result| {
+                    results.push(is_truthy(&result));
+                    Ok(())
+                })?;
+            }
+            out(Value::Boolean(match name {
+                "any" => results.into_iter().any(|result| result),
+                _ => results.into_iter().all(|result| result),
+            }))
+        }
+        ("flatten", []) => out(flatten(input, f64::INFINITY)?),
+        ("flatten", [depth]) => eval(depth, input, env, &mut |depth| match as_f64(&depth) {
+            Some(depth) if depth >= 0.0 => out(flatten(input, depth)?),
+            _ => Err(Fault::Error(JqError::Runtime(
+                "flatten depth must not be negative".to_string(),
+            ))),
+        }),
+        ("floor" | "ceil" | "round" | "sqrt" | "abs", []) => match as_f64(input) {
+            Some(number) => out(number_value(match name {
+                "floor" => number.floor(),
+                "ceil" => number.ceil(),
+                "round" => number.round(),
+                "sqrt" => number.sqrt(),
+                _ => number.abs(),
+            })),
+            None => Err(type_error(format!("{} number required", describe(input)))),
+        },
+        ("tostring", []) => out(to_text(input).to_value()),
+        ("tojson", []) => out(input.to_json(JsonMode::Inline).to_value()),
+        ("tonumber", []) => match input {
+            Value::Number(_) => out(input.clone()),
+            Value::String(string) => out(parse_number(string.as_str())?),
+            other => Err(type_error(format!(
+                "{} cannot be parsed as a number",
+                describe(other)
+            ))),
+        },
+        ("fromjson", []) => match input {
+            Value::String(string) => match Value::json_to_value(string.as_str()) {
+                Ok(value) => out(value),
+                Err(_) => Err(Fault::Error(JqError::Runtime(format!(
+                    "{} cannot be parsed as JSON",
+                    describe(input)
+                )))),
+            },
+            other => Err(type_error(format!("{} cannot be parsed", describe(other)))),
+        },
+        ("type", []) => out(type_name(input).to_value()),
+        ("ascii_downcase" | "ascii_upcase", []) => {
+            let string = string_input(name, input)?;
+            out(match name {
+                "ascii_downcase" => string.to_ascii_lowercase(),
+                _ => string.to_ascii_uppercase(),
+            }
+            .to_value())
+        }
+        ("sort", []) => {
+            let mut items = array_input(name, input)?;
+            items.sort_by(compare);
+            out(items.to_value())
+        }
+        ("sort_by", [key]) => out(sort_by(input, key, env)?
+            .into_iter()
+            .map(|(_, item)| item)
+            .collect::<Vec<_>>()
+            .to_value()),
+        ("unique", []) => {
+            let mut items = array_input(name, input)?;
+            items.sort_by(compare);
+            items.dedup_by(|lhs, rhs| equals(lhs, rhs));
+            out(items.to_value())
+        }
+        ("group_by" | "unique_by", [key]) => {
+            let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+            for (key, item) in sort_by(input, key, env)? {
+                match groups.last_mut() {
+                    Some((last, group)) if equals(last, &key) => group.push(item),
+                    _ => groups.push((key, vec![item])),
+                }
+            }
+            out(groups
+                .into_iter()
+                .map(|(_, group)| match name {
+                    "unique_by" => group.into_iter().next().unwrap_or(Value::Null),
+                    _ => group.to_value(),
+                })
+                .collect::<Vec<_>>()
+                .to_value())
+        }
+        ("min" | "max", []) => {
+            let items = array_input(name, input)?;
+            out(extreme(name == "max", items.into_iter().map(|item| (item.clone(), item))))
+        }
+        ("min_by" | "max_by", [key]) => {
+            let items = sort_by(input, key, env)?;
+            out(extreme(name == "max_by", items.into_iter()))
+        }
+        ("reverse", []) => match input {
+            Value::Null | Value::Undefined => out(Array::new().to_value()),
+            Value::String(string) => out(string.as_str().chars().rev().collect::<String>().to_value()),
+            Value::Array(array) => {
+                let mut values = array.values.clone();
+                values.reverse();
+                out(values.to_value())
+            }
+            other => Err(type_error(format!("Cannot reverse {}", describe(other)))),
+        },
+        ("first", []) => out(index(input, &0.to_value())?),
+        ("last", []) => out(index(input, &(-1).to_value())?),
+        ("first", [generator]) => match first(generator, input, env)? {
+            Some(value) => out(value),
+            None => Ok(()),
+        },
+        ("last", [generator]) => match collect(generator, input, env)?.pop() {
+            Some(value) => out(value),
+            None => Ok(()),
+        },
+        ("limit", [count, generator]) => eval(count, input, env, &mut |count| {
+            let count = as_f64(&count).unwrap_or(0.0);
+            if count <= 0.0 {
+                return Ok(());
+            }
+            let token = stop_token();
+            let mut emitted = 0.0;
+            let result = eval(generator, input, env, &mut |value| {
+                emitted += 1.0;
+                out(value)?;
+                if emitted >= count {
+                    Err(Fault::Stop(token))
+                } else {
+                    Ok(())
+                }
+            });
+            match result {
+                Err(Fault::Stop(stop)) if stop == token => Ok(()),
+                result => result,
+            }
+        }),
+        ("range", [upto]) => eval(upto, input, env, &mut |upto| {
+            range(0.0, number_argument(name, &upto)?, out)
+        }),
+        ("range", [from, upto]) => eval(from, input, env, &mut |from| {
+            eval(upto, input, env, &mut |upto| {
+                range(
+                    number_argument(name, &from)?,
+                    number_argument(name, &upto)?,
+                    out,
+                )
+            })
+        }),
+        ("to_entries", []) => out(to_entries(input)?),
+        ("from_entries", []) => out(from_entries(input)?),
+        ("with_entries", [update]) => {
+            let mut updated = Vec::new();
+            for entry in iterate(&to_entries(input)?)? {
+                updated.extend(collect(update, &entry, env)?);
+            }
+            out(from_entries(&updated.to_value())?)
+        }
+        ("has", [key]) => eval(key, input, env, &mut |key| {
+            out(Value::Boolean(has(input, &key)?))
+        }),
+        ("in", [object]) => eval(object, input, env, &mut |object| {
+            out(Value::Boolean(has(&object, input)?))
+        }),
+        ("map", [update]) => {
+            let mut values = Vec::new();
+            for item in iterate(input)? {
+                values.extend(collect(update, &item, env)?);
+            }
+            out(values.to_value())
+        }
+        ("map_values", [update]) => match input {
+            Value::Array(array) => {
+                let mut values = Vec::new();
+                for item in array.values.iter() {
+                    values.extend(first(update, item, env)?);
+                }
+                out(values.to_value())
+            }
+            Value::Object(object) => {
+                let mut updated = Object::default();
+                for (key, value) in object.iter() {
+                    if let Some(value) = first(update, value, env)? {
+                        updated.insert(key, value);
+                    }
+                }
+                out(updated.to_value())
+            }
+            other => Err(type_error(format!(
+                "Cannot iterate over {}",
+                describe(other)
+            ))),
+        },
+        ("select", [condition]) => eval(condition, input, env, &mut |result| {
+            filter(input, is_truthy(&result), out)
+        }),
+        ("recurse", []) => recurse_paths(Vec::new(), input, &mut |_, value| out(value)),
+        ("recurse", [step]) => recurse_with(step, input, env, out),
+        ("walk", [update]) => walk(update, input, env, out),
+        ("path", [path]) => eval_paths(path, &[], input, env, &mut |path, _| {
+            out(path.to_value())
+        }),
+        ("paths", []) => recurse_paths(Vec::new(), input, &mut |path, _| {
+            if path.is_empty() {
+                Ok(())
+            } else {
+                out(path.to_value())
+            }
+        }),
+        ("leaf_paths", []) => recurse_paths(Vec::new(), input, &mut |path, value| {
+            if path.is_empty() || matches!(value, Value::Array(_) | Value::Object(_)) {
+                Ok(())
+            } else {
+                out(path.to_value())
+            }
+        }),
+        ("paths", [condition]) => recurse_paths(Vec::new(), input, &mut |path, value| {
+            if path.is_empty() {
+                return Ok(());
+            }
+            eval(condition, &value, env, &mut |result| {
+                if is_truthy(&result) {
+                    out(path.clone().to_value())
+                } else {
+                    Ok(())
+                }
+            })
+        }),
+        ("getpath", [path]) => eval(path, input, env, &mut |path| {
+            out(get_path(input, &path_argument(&path)?)?)
+        }),
+        ("setpath", [path, value]) => eval(value, input, env, &mut |value| {
+            eval(path, input, env, &mut |path| {
+                let mut result = input.clone();
+                set_path(&mut result, &path_argument(&path)?, value.clone())?;
+                out(result)
+            })
+        }),
+        ("delpaths", [paths]) => eval(paths, input, env, &mut |paths| {
+            let paths = match paths {
+                Value::Array(paths) => paths
+                    .values
+                    .iter()
+                    .map(path_argument)
+                    .collect::<Result<Vec<_>, _>>()?,
+                other => {
+                    return Err(type_error(format!(
+                        "Paths must be specified as an array, found {}",
+                        describe(&other)
+                    )))
+                }
+            };
+            let mut result = input.clone();
+            delete_paths(&mut result, paths)?;
+            out(result)
+        }),
+        ("del", [path]) => {
+            let mut result = input.clone();
+            delete_paths(&mut result, collect_paths(path, input, env)?)?;
+            out(result)
+        }
+        ("ltrimstr" | "rtrimstr", [affix]) => eval(affix, input, env, &mut |affix| {
+            let trimmed = match (input, &affix) {
+                (Value::String(string), Value::String(affix)) => match name {
+                    "ltrimstr" => string.as_str().strip_prefix(affix.as_str()),
+                    _ => string.as_str().strip_suffix(affix.as_str()),
+                },
+                _ => None,
+            };
+            match trimmed {
+                Some(trimmed) => out(trimmed.to_value()),
+                None => out(input.clone()),
+            }
+        }),
+        ("startswith" | "endswith", [affix]) => eval(affix, input, env, &mut |affix| {
+            match (input, &affix) {
+                (Value::String(string), Value::String(affix)) => out(Value::Boolean(match name {
+                    "startswith" => string.as_str().starts_with(affix.as_str()),
+                    _ => string.as_str().ends_with(affix.as_str()),
+                })),
+                _ => Err(type_error(format!("{}() requires string inputs", name))),
+            }
+        }),
+        ("split", [separator]) => eval(separator, input, env, &mut |separator| {
+            match (input, &separator) {
+                (Value::String(string), Value::String(separator)) => {
+                    out(split(string.as_str(), separator.as_str()))
+                }
+                _ => Err(type_error(
+                    "split input and separator must be strings".to_string(),
+                )),
+            }
+        }),
+        ("join", [separator]) => eval(separator, input, env, &mut |separator| {
+            let separator = match separator {
+                Value::String(separator) => separator.as_string(),
+                other => {
+                    return Err(type_error(format!(
+                        "{} cannot be used as a separator",
+                        describe(&other)
+                    )))
+                }
+            };
+            let parts = iterate(input)?
+                .iter()
+                .map(|item| match item {
+                    Value::Null | Value::Undefined => Ok(String::new()),
+                    Value::Array(_) | Value::Object(_) => Err(type_error(format!(
+                        "Cannot join with {}",
+                        describe(item)
+                    ))),
+                    other => Ok(to_text(other)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            out(parts.join(&separator).to_value())
+        }),
+        ("test", [pattern]) => eval(pattern, input, env, &mut |pattern| {
+            let pattern = string_input(name, &pattern)?;
+            let regex = Regex::new(&pattern).map_err(|err| {
+                Fault::Error(JqError::Runtime(format!(
+                    "{} is not a valid regex: {}",
+                    pattern, err
+                )))
+            })?;
+            match input {
+                Value::String(string) => out(Value::Boolean(regex.is_match(string.as_str()))),
+                other => Err(type_error(format!(
+                    "{} cannot be matched, as it is not a string",
+                    describe(other)
+                ))),
+            }
+        }),
+        ("contains", [other]) => eval(other, input, env, &mut |other| {
+            out(Value::Boolean(contains(input, &other)?))
+        }),
+        ("inside", [other]) => eval(other, input, env, &mut |other| {
+            out(Value::Boolean(contains(&other, input)?))
+        }),
+        _ => Err(Fault::Error(JqError::UnknownFunction(format!(
+            "{}/{}",
+            name,
+            args.len()
+        )))),
+    }
+}
+
+/// Evaluates a builtin used as a path expression, as in `path(select(.a))` or `del(first(.[]))`.
+pub(crate) fn call_paths(
+    name: &str,
+    args: &[Node],
+    path: &[Value],
+    input: &Value,
+    env: &Env,
+    out: PathOutput,
+) -> Result<(), Fault> {
+    match (name, args) {
+        ("empty", []) => Ok(()),
+        ("error", _) => call(name, args, input, env, &mut |_| Ok(())),
+        ("select", [condition]) => eval(condition, input, env, &mut |result| {
+            if is_truthy(&result) {
+                out(path.to_vec(), input.clone())
+            } else {
+                Ok(())
+            }
+        }),
+        ("recurse", []) => recurse_paths(path.to_vec(), input, out),
+        ("first", [generator]) => {
+            let token = stop_token();
+            let mut found = None;
+            let result = eval_paths(generator, path, input, env, &mut |path, value| {
+                found = Some((path, value));
+                Err(Fault::Stop(token))
+            });
+            match result {
+                Err(Fault::Stop(stop)) if stop == token => {}
+                result => result?,
+            }
+            match found {
+                Some((path, value)) => out(path, value),
+                None => Ok(()),
+            }
+        }
+        ("last", [generator]) => {
+            let mut found = None;
+            eval_paths(generator, path, input, env, &mut |path, value| {
+                found = Some((path, value));
+                Ok(())
+            })?;
+            match found {
+                Some((path, value)) => out(path, value),
+                None => Ok(()),
+            }
+        }
+        ("getpath", [steps]) => eval(steps, input, env, &mut |steps| {
+            let steps = path_argument(&steps)?;
+            let value = get_path(input, &steps)?;
+            let mut path = path.to_vec();
+            path.extend(steps);
+            out(path, value)
+        }),
+        _ if exists(name, args.len()) => Err(invalid_path()),
+        _ => Err(Fault::Error(JqError::UnknownFunction(format!(
+            "{}/{}",
+            name,
+            args.len()
+        )))),
+    }
+}
+
+fn filter(input: &Value, keep: bool, out: Output) -> Result<(), Fault> {
+    if keep {
+        out(input.clone())
+    } else {
+        Ok(())
+    }
+}
+
+fn keys(input: &Value) -> Result<Value, Fault> {
+    match input {
+        Value::Object(object) => Ok(entries(object)
+            .into_iter()
+            .map(|(key, _)| key.to_value())
+            .collect::<Vec<_>>()
+            .to_value()),
+        Value::Array(array) => Ok((0..array.len()).collect::<Vec<_>>().to_value()),
+        other => Err(type_error(format!("{} has no keys", describe(other)))),
+    }
+}
+
+fn has(container: &Value, key: &Value) -> Result<bool, Fault> {
+    match (container, key) {
+        (Value::Object(object), Value::String(key)) => Ok(object.contains_key(&key.as_str())),
+        (Value::Array(array), Value::Number(_)) => {
+            let index = as_f64(key).unwrap_or(-1.0);
+            Ok(index >= 0.0 && index < array.len() as f64)
+        }
+        _ => Err(type_error(format!(
+            "Cannot check whether {} has a {} key",
+            type_name(container),
+            type_name(key)
+        ))),
+    }
+}
+
+fn contains(container: &Value, item: &Value) -> Result<bool, Fault> {
+    match (container, item) {
+        (Value::Object(container), Value::Object(item)) => {
+            for (key, value) in item.iter() {
+                match container.get(key) {
+                    Some(inner) if contains(inner, value)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        (Value::Array(container), Value::Array(item)) => {
+            for value in item.values.iter() {
+                let mut found = false;
+                for inner in container.values.iter() {
+                    if contains(inner, value).unwrap_or(false) {
+                        found = true;
+                        break;
+                    }
+                }
+                if !found {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (Value::String(container), Value::String(item)) => {
+            Ok(container.as_str().contains(item.as_str()))
+        }
+        _ if type_name(container) == type_name(item) => Ok(equals(container, item)),
+        _ => Err(type_error(format!(
+            "{} and {} cannot have their containment checked",
+            describe(container),
+            describe(item)
+        ))),
+    }
+}
+
+fn to_entries(input: &Value) -> Result<Value, Fault> {
+    match input {
+        Value::Object(object) => Ok(entries(object)
+            .into_iter()
+            .map(|(key, value)| {
+                let mut entry = Object::default();
+                entry.insert("key", key.to_value());
+                entry.insert("value", value.clone());
+                entry.to_value()
+            })
+            .collect::<Vec<_>>()
+            .to_value()),
+        other => Err(type_error(format!("{} has no keys", describe(other)))),
+    }
+}
+
+fn from_entries(input: &Value) -> Result<Value, Fault> {
+    let mut object = Object::default();
+
+    for entry in iterate(input)? {
+        let members = match &entry {
+            Value::Object(members) => members,
+            other => {
+                return Err(type_error(format!(
+                    "Cannot index {} with \"key\"",
+                    describe(other)
+                )))
+            }
+        };
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| members.get(*name))
+                .find(|value| !is_null(value))
+                .cloned()
+        };
+        let key = match field(&["key", "k", "name", "Name", "Key", "K"]) {
+            Some(Value::Array(_) | Value::Object(_)) | None => {
+                return Err(type_error(format!(
+                    "Cannot use {} as object key",
+                    describe(&entry)
+                )))
+            }
+            Some(key) => to_text(&key),
+        };
+        let value = field(&["value", "v", "Value", "V"]).unwrap_or(Value::Null);
+        object.insert(key.as_str(), value);
+    }
+
+    Ok(object.to_value())
+}
+
+fn flatten(input: &Value, depth: f64) -> Result<Value, Fault> {
+    fn flatten_into(values: &[Value], depth: f64, flattened: &mut Vec<Value>) {
+        for value in values {
+            match value {
+                Value::Array(array) if depth > 0.0 => {
+                    flatten_into(&array.values, depth - 1.0, flattened)
+                }
+                other => flattened.push(other.clone()),
+            }
+        }
+    }
+
+    let items = array_input("flatten", input)?;
+    let mut flattened = Vec::new();
+    flatten_into(&items, depth, &mut flattened);
+    Ok(flattened.to_value())
+}
+
+/// Pairs every item of an array with the outputs of `key` for it, sorted by those keys.
+fn sort_by(input: &Value, key: &Node, env: &Env) -> Result<Vec<(Value, Value)>, Fault> {
+    let mut keyed = array_input("sort_by", input)?
+        .into_iter()
+        .map(|item| Ok((collect(key, &item, env)?.to_value(), item)))
+        .collect::<Result<Vec<_>, Fault>>()?;
+    keyed.sort_by(|(lhs, _), (rhs, _)| compare(lhs, rhs));
+    Ok(keyed)
+}
+
+/// Returns the item with the smallest or the largest key, or null when there are none.
+fn extreme<I>(largest: bool, items: I) -> Value
+where
+    I: Iterator<Item = (Value, Value)>,
+{
+    items
+        .reduce(|best, item| {
+            let ordering = compare(&item.0, &best.0);
+            let replace = match largest {
+                true => ordering != Ordering::Less,
+                false => ordering == Ordering::Less,
+            };
+            if replace {
+                item
+            } else {
+                best
+            }
+        })
+        .map(|(_, item)| item)
+        .unwrap_or(Value::Null)
+}
+
+fn recurse_with(step: &Node, input: &Value, env: &Env, out: Output) -> Result<(), Fault> {
+    out(input.clone())?;
+    eval(step, input, env, &mut |value| recurse_with(step, &value, env, out))
+}
+
+fn walk(update: &Node, input: &Value, env: &Env, out: Output) -> Result<(), Fault> {
+    let walked = match input {
+        Value::Array(array) => {
+            let mut values = Vec::new();
+            for item in array.values.iter() {
+                walk(update, item, env, &mut |value| {
+                    values.push(value);
+                    Ok(())
+                })?;
+            }
+            values.to_value()
+        }
+        Value::Object(object) => {
+            let mut walked = Object::default();
+            for (key, value) in object.iter() {
+                let mut last = None;
+                walk(update, value, env, &mut |value| {
+                    last.get_or_insert(value);
+                    Ok(())
+                })?;
+                if let Some(value) = last {
+                    walked.insert(key, value);
+                }
+            }
+            walked.to_value()
+        }
+        other => other.clone(),
+    };
+
+    eval(update, &walked, env, out)
+}
+
+fn range(from: f64, upto: f64, out: Output) -> Result<(), Fault> {
+    let mut current = from;
+    while current < upto {
+        out(number_value(current))?;
+        current += 1.0;
+    }
+    Ok(())
+}
+
+fn parse_number(string: &str) -> Result<Value, Fault> {
+    let trimmed = string.trim();
+    if let Ok(number) = trimmed.parse::<i64>() {
+        return Ok(number.to_value());
+    }
+    match trimmed.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number.to_value()),
+        _ => Err(Fault::Error(JqError::Runtime(format!(
+            "Cannot parse {:?} as a number",
+            string
+        )))),
+    }
+}
+
+fn string_input(name: &str, input: &Value) -> Result<String, Fault> {
+    match input {
+        Value::String(string) => Ok(string.as_string()),
+        Value::DateTime(datetime) => Ok(datetime.to_string()),
+        _ => Err(type_error(format!("{} input must be a string", name))),
+    }
+}
+
+fn array_input(name: &str, input: &Value) -> Result<Vec<Value>, Fault> {
+    match input {
+        Value::Array(array) => Ok(array.values.clone()),
+        other => Err(type_error(format!(
+            "{} cannot be used with {}, as it is not an array",
+            describe(other),
+            name
+        ))),
+    }
+}
+
+fn number_argument(name: &str, value: &Value) -> Result<f64, Fault> {
+    as_f64(value)
+        .ok_or_else(|| type_error(format!("{}() requires numbers, found {}", name, describe(value))))
+}
+
+fn path_argument(path: &Value) -> Result<Vec<Value>, Fault> {
+    match path {
+        Value::Array(path) => Ok(path.values.clone()),
+        other => Err(type_error(format!(
+            "Path must be specified as an array, found {}",
+            describe(other)
+        ))),
+    }
+}
@@ -0,0 +1,1037 @@
+use super::ast::{Assignment, Comparator, Node, Operator, Part};
+use super::builtins;
+use super::JqError;
+use crate::prelude::*;
+use std::cmp::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+/// Why an evaluation stopped early. Besides the errors of `JqError`, programs can raise any
+/// value with `error`, and `Stop` unwinds a generator once enough of its outputs were taken.
+pub(crate) enum Fault {
+    Error(JqError),
+    Raised(Box<Value>),
+    Stop(usize),
+}
+
+impl From<JqError> for Fault {
+    fn from(error: JqError) -> Self {
+        Fault::Error(error)
+    }
+}
+
+impl Fault {
+    /// The value a `catch` handler receives.
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            Fault::Error(JqError::Type(message)) | Fault::Error(JqError::Runtime(message)) => {
+                message.to_value()
+            }
+            Fault::Error(error) => error.to_string().to_value(),
+            Fault::Raised(value) => *value,
+            Fault::Stop(_) => Value::Null,
+        }
+    }
+
+    pub(crate) fn into_error(self) -> JqError {
+        match self {
+            Fault::Error(error) => error,
+            Fault::Raised(value) => match *value {
+                Value::String(message) => JqError::Runtime(message.as_string()),
+                value => JqError::Runtime(format!(
+                    "{} (not a string)",
+                    value.to_json(JsonMode::Inline)
+                )),
+            },
+            Fault::Stop(_) => JqError::Runtime("break".to_string()),
+        }
+    }
+}
+
+/// Receives every output of an expression.
+pub(crate) type Output<'a> = &'a mut dyn FnMut(Value) -> Result<(), Fault>;
+
+/// Receives every output of a path expression, along with the path that leads to it.
+pub(crate) type PathOutput<'a> = &'a mut dyn FnMut(Vec<Value>, Value) -> Result<(), Fault>;
+
+/// The variables in scope.
+pub(crate) enum Env<'a> {
+    Root(&'a [(String, Value)]),
+    Bind(&'a str, &'a Value, &'a Env<'a>),
+}
+
+impl Env<'_> {
+    fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Env::Root(variables) => variables
+                .iter()
+                .rev()
+                .find(|(variable, _)| variable == name)
+                .map(|(_, value)| value),
+            Env::Bind(variable, value, parent) => {
+                if *variable == name {
+                    Some(value)
+                } else {
+                    parent.get(name)
+                }
+            }
+        }
+    }
+}
+
+/// Returns a token that identifies a `Fault::Stop` raised by the caller.
+pub(crate) fn stop_token() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(1);
+    NEXT.fetch_add(1, AtomicOrdering::Relaxed)
+}
+
+pub(crate) fn eval(node: &Node, input: &Value, env: &Env, out: Output) -> Result<(), Fault> {
+    match node {
+        Node::Identity => out(input.clone()),
+        Node::Recurse => recurse(input, out),
+        Node::Literal(value) => out(value.clone()),
+        Node::Format(parts) => format(parts, String::new(), input, env, out),
+        Node::Index(target, key) => eval(target, input, env, &mut |target| {
+            eval(key, input, env, &mut |key| out(index(&target, &key)?))
+        }),
+        Node::Slice(target, from, to) => eval(target, input, env, &mut |target| {
+            bound(from.as_deref(), input, env, &mut |from| {
+                bound(to.as_deref(), input, env, &mut |to| {
+                    out(slice(&target, &from, &to)?)
+                })
+            })
+        }),
+        Node::Iterate(target) => eval(target, input, env, &mut |target| {
+            for item in iterate(&target)? {
+                out(item)?;
+            }
+            Ok(())
+        }),
+        Node::Pipe(lhs, rhs) => eval(lhs, input, env, &mut |value| eval(rhs, &value, env, out)),
+        Node::Comma(lhs, rhs) => {
+            eval(lhs, input, env, out)?;
+            eval(rhs, input, env, out)
+        }
+        Node::Neg(node) => eval(node, input, env, &mut |value| match as_f64(&value) {
+            Some(number) => out(number_value(-number)),
+            None => Err(type_error(format!("{} cannot be negated", describe(&value)))),
+        }),
+        Node::Arithmetic(operator, lhs, rhs) => eval(rhs, input, env, &mut |rhs| {
+            eval(lhs, input, env, &mut |lhs| {
+                out(arithmetic(*operator, &lhs, &rhs)?)
+            })
+        }),
+        Node::Comparison(comparator, lhs, rhs) => eval(rhs, input, env, &mut |rhs| {
+            eval(lhs, input, env, &mut |lhs| {
+                let ordering = compare(&lhs, &rhs);
+                out(Value::Boolean(match comparator {
+                    Comparator::Equal => ordering == Ordering::Equal,
+                    Comparator::NotEqual => ordering != Ordering::Equal,
+                    Comparator::LessThan => ordering == Ordering::Less,
+                    Comparator::LessThanOrEqual => ordering != Ordering::Greater,
+                    Comparator::GreaterThan => ordering == Ordering::Greater,
+                    Comparator::GreaterThanOrEqual => ordering != Ordering::Less,
+                }))
+            })
+        }),
+        Node::And(lhs, rhs) => eval(lhs, input, env, &mut |lhs| {
+            if !is_truthy(&lhs) {
+                return out(Value::Boolean(false));
+            }
+            eval(rhs, input, env, &mut |rhs| out(Value::Boolean(is_truthy(&rhs))))
+        }),
+        Node::Or(lhs, rhs) => eval(lhs, input, env, &mut |lhs| {
+            if is_truthy(&lhs) {
+                return out(Value::Boolean(true));
+            }
+            eval(rhs, input, env, &mut |rhs| out(Value::Boolean(is_truthy(&rhs))))
+        }),
+        Node::Alternative(lhs, rhs) => {
+            let mut found = false;
+            guard(
+                |out| eval(lhs, input, env, out),
+                &mut |value| {
+                    if is_truthy(&value) {
+                        found = true;
+                        out(value)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            if found {
+                Ok(())
+            } else {
+                eval(rhs, input, env, out)
+            }
+        }
+        Node::Assign(assignment, lhs, rhs) => assign(*assignment, lhs, rhs, input, env, out),
+        Node::Array(None) => out(Array::new().to_value()),
+        Node::Array(Some(node)) => out(collect(node, input, env)?.to_value()),
+        Node::Object(entries) => object(entries, Object::default(), input, env, out),
+        Node::Variable(name) => match env.get(name) {
+            Some(value) => out(value.clone()),
+            None => Err(Fault::Error(JqError::UndefinedVariable(name.clone()))),
+        },
+        Node::Bind(source, name, body) => eval(source, input, env, &mut |value| {
+            eval(body, input, &Env::Bind(name, &value, env), out)
+        }),
+        Node::Reduce(source, name, init, update) => eval(init, input, env, &mut |init| {
+            let mut state = init;
+            eval(source, input, env, &mut |item| {
+                let env = Env::Bind(name, &item, env);
+                let mut last = None;
+                eval(update, &state, &env, &mut |value| {
+                    last = Some(value);
+                    Ok(())
+                })?;
+                state = last.unwrap_or(Value::Null);
+                Ok(())
+            })?;
+            out(state)
+        }),
+        Node::Foreach(source, name, init, update, extract) => eval(init, input, env, &mut |init| {
+            let mut state = init;
+            eval(source, input, env, &mut |item| {
+                let env = Env::Bind(name, &item, env);
+                for value in collect(update, &state, &env)? {
+                    state = value.clone();
+                    match extract {
+                        Some(extract) => eval(extract, &value, &env, out)?,
+                        None => out(value)?,
+                    }
+                }
+                Ok(())
+            })
+        }),
+        Node::If(branches, otherwise) => branch(branches, otherwise.as_deref(), input, env, out),
+        Node::Try(body, handler) => match guard(|out| eval(body, input, env, out), out)? {
+            Some(fault) => match handler {
+                Some(handler) => eval(handler, &fault.into_value(), env, out),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        },
+        Node::Call(name, args) => builtins::call(name, args, input, env, out),
+    }
+}
+
+/// Evaluates a path expression, producing the path of every output along with its value.
+pub(crate) fn eval_paths(
+    node: &Node,
+    path: &[Value],
+    input: &Value,
+    env: &Env,
+    out: PathOutput,
+) -> Result<(), Fault> {
+    match node {
+        Node::Identity => out(path.to_vec(), input.clone()),
+        Node::Recurse => recurse_paths(path.to_vec(), input, out),
+        Node::Index(target, key) => eval_paths(target, path, input, env, &mut |path, value| {
+            eval(key, input, env, &mut |key| {
+                let child = index(&value, &key)?;
+                let mut path = path.clone();
+                path.push(key);
+                out(path, child)
+            })
+        }),
+        Node::Slice(target, from, to) => eval_paths(target, path, input, env, &mut |path, value| {
+            bound(from.as_deref(), input, env, &mut |from| {
+                bound(to.as_deref(), input, env, &mut |to| {
+                    let child = slice(&value, &from, &to)?;
+                    let mut bounds = Object::default();
+                    bounds.insert("start", from.clone());
+                    bounds.insert("end", to);
+                    let mut path = path.clone();
+                    path.push(bounds.to_value());
+                    out(path, child)
+                })
+            })
+        }),
+        Node::Iterate(target) => eval_paths(target, path, input, env, &mut |path, value| {
+            for (key, child) in children(&value)? {
+                let mut path = path.clone();
+                path.push(key);
+                out(path, child)?;
+            }
+            Ok(())
+        }),
+        Node::Pipe(lhs, rhs) => eval_paths(lhs, path, input, env, &mut |path, value| {
+            eval_paths(rhs, &path, &value, env, out)
+        }),
+        Node::Comma(lhs, rhs) => {
+            eval_paths(lhs, path, input, env, out)?;
+            eval_paths(rhs, path, input, env, out)
+        }
+        Node::Alternative(lhs, rhs) => {
+            let mut found = false;
+            let mut downstream = None;
+            let result = eval_paths(lhs, path, input, env, &mut |path, value| {
+                if is_truthy(&value) {
+                    found = true;
+                    if let Err(fault) = out(path, value) {
+                        downstream = Some(fault);
+                        return Err(Fault::Stop(0));
+                    }
+                }
+                Ok(())
+            });
+            match (downstream, result) {
+                (Some(fault), _) => Err(fault),
+                _ if found => Ok(()),
+                _ => eval_paths(rhs, path, input, env, out),
+            }
+        }
+        Node::If(branches, otherwise) => branch_paths(branches, otherwise.as_deref(), path, input, env, out),
+        Node::Try(body, _) => {
+            let mut downstream = None;
+            let _ = eval_paths(body, path, input, env, &mut |path, value| {
+                out(path, value).map_err(|fault| {
+                    downstream = Some(fault);
+                    Fault::Stop(0)
+                })
+            });
+            match downstream {
+                Some(fault) => Err(fault),
+                None => Ok(()),
+            }
+        }
+        Node::Bind(source, name, body) => eval(source, input, env, &mut |value| {
+            eval_paths(body, path, input, &Env::Bind(name, &value, env), out)
+        }),
+        Node::Call(name, args) => builtins::call_paths(name, args, path, input, env, out),
+        _ => Err(invalid_path()),
+    }
+}
+
+/// Runs an expression, passing its outputs to `out`. Faults raised by `out`, that is by the
+/// rest of the pipeline, are returned as errors, while faults raised by the expression itself
+/// are handed back so the caller can decide whether to suppress them.
+fn guard<F>(body: F, out: Output) -> Result<Option<Fault>, Fault>
+where
+    F: FnOnce(Output) -> Result<(), Fault>,
+{
+    let mut downstream = None;
+    let result = body(&mut |value| {
+        out(value).map_err(|fault| {
+            downstream = Some(fault);
+            Fault::Stop(0)
+        })
+    });
+
+    match downstream {
+        Some(fault) => Err(fault),
+        None => Ok(result.err()),
+    }
+}
+
+pub(crate) fn collect(node: &Node, input: &Value, env: &Env) -> Result<Vec<Value>, Fault> {
+    let mut values = Vec::new();
+    eval(node, input, env, &mut |value| {
+        values.push(value);
+        Ok(())
+    })?;
+    Ok(values)
+}
+
+/// Returns the first output of an expression without evaluating the rest.
+pub(crate) fn first(node: &Node, input: &Value, env: &Env) -> Result<Option<Value>, Fault> {
+    let token = stop_token();
+    let mut found = None;
+    let result = eval(node, input, env, &mut |value| {
+        found = Some(value);
+        Err(Fault::Stop(token))
+    });
+
+    match result {
+        Err(Fault::Stop(stop)) if stop == token => Ok(found),
+        Err(fault) => Err(fault),
+        Ok(()) => Ok(found),
+    }
+}
+
+pub(crate) fn collect_paths(
+    node: &Node,
+    input: &Value,
+    env: &Env,
+) -> Result<Vec<Vec<Value>>, Fault> {
+    let mut paths = Vec::new();
+    eval_paths(node, &[], input, env, &mut |path, _| {
+        paths.push(path);
+        Ok(())
+    })?;
+    Ok(paths)
+}
+
+fn bound(node: Option<&Node>, input: &Value, env: &Env, out: Output) -> Result<(), Fault> {
+    match node {
+        Some(node) => eval(node, input, env, out),
+        None => out(Value::Null),
+    }
+}
+
+fn format(
+    parts: &[Part],
+    prefix: String,
+    input: &Value,
+    env: &Env,
+    out: Output,
+) -> Result<(), Fault> {
+    match parts.split_first() {
+        None => out(prefix.to_value()),
+        Some((Part::Text(text), rest)) => format(rest, prefix + text, input, env, out),
+        Some((Part::Interpolation(node), rest)) => eval(node, input, env, &mut |value| {
+            format(rest, format!("{}{}", prefix, to_text(&value)), input, env, out)
+        }),
+    }
+}
+
+fn object(
+    entries: &[(Node, Node)],
+    object: Object,
+    input: &Value,
+    env: &Env,
+    out: Output,
+) -> Result<(), Fault> {
+    let ((key, value), rest) = match entries.split_first() {
+        Some(entry) => entry,
+        None => return out(object.to_value()),
+    };
+
+    eval(key, input, env, &mut |key| {
+        let key = match key {
+            Value::String(key) => key.as_string(),
+            other => {
+                return Err(type_error(format!(
+                    "Object keys must be strings, found {}",
+                    describe(&other)
+                )))
+            }
+        };
+        eval(value, input, env, &mut |value| {
+            let mut object = object.clone();
+            object.insert(key.as_str(), value);
+            self::object(rest, object, input, env, out)
+        })
+    })
+}
+
+fn branch(
+    branches: &[(Node, Node)],
+    otherwise: Option<&Node>,
+    input: &Value,
+    env: &Env,
+    out: Output,
+) -> Result<(), Fault> {
+    match branches.split_first() {
+        Some(((condition, then), rest)) => eval(condition, input, env, &mut |condition| {
+            if is_truthy(&condition) {
+                eval(then, input, env, out)
+            } else {
+                branch(rest, otherwise, input, env, out)
+            }
+        }),
+        None => match otherwise {
+            Some(otherwise) => eval(otherwise, input, env, out),
+            None => out(input.clone()),
+        },
+    }
+}
+
+fn branch_paths(
+    branches: &[(Node, Node)],
+    otherwise: Option<&Node>,
+    path: &[Value],
+    input: &Value,
+    env: &Env,
+    out: PathOutput,
+) -> Result<(), Fault> {
+    match branches.split_first() {
+        Some(((condition, then), rest)) => eval(condition, input, env, &mut |condition| {
+            if is_truthy(&condition) {
+                eval_paths(then, path, input, env, out)
+            } else {
+                branch_paths(rest, otherwise, path, input, env, out)
+            }
+        }),
+        None => match otherwise {
+            Some(otherwise) => eval_paths(otherwise, path, input, env, out),
+            None => out(path.to_vec(), input.clone()),
+        },
+    }
+}
+
+fn assign(
+    assignment: Assignment,
+    lhs: &Node,
+    rhs: &Node,
+    input: &Value,
+    env: &Env,
+    out: Output,
+) -> Result<(), Fault> {
+    let paths = collect_paths(lhs, input, env)?;
+
+    match assignment {
+        Assignment::Update => {
+            let mut result = input.clone();
+            let mut deleted = Vec::new();
+            for path in paths {
+                let current = get_path(&result, &path)?;
+                match first(rhs, &current, env)? {
+                    Some(value) => set_path(&mut result, &path, value)?,
+                    None => deleted.push(path),
+                }
+            }
+            delete_paths(&mut result, deleted)?;
+            out(result)
+        }
+        _ => eval(rhs, input, env, &mut |value| {
+            let mut result = input.clone();
+            for path in paths.iter() {
+                let value = match assignment {
+                    Assignment::Arithmetic(operator) => {
+                        arithmetic(operator, &get_path(&result, path)?, &value)?
+                    }
+                    Assignment::Alternative => {
+                        let current = get_path(&result, path)?;
+                        if is_truthy(&current) {
+                            current
+                        } else {
+                            value.clone()
+                        }
+                    }
+                    _ => value.clone(),
+                };
+                set_path(&mut result, path, value)?;
+            }
+            out(result)
+        }),
+    }
+}
+
+fn recurse(value: &Value, out: Output) -> Result<(), Fault> {
+    out(value.clone())?;
+    if matches!(value, Value::Array(_) | Value::Object(_)) {
+        for item in iterate(value)? {
+            recurse(&item, out)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn recurse_paths(path: Vec<Value>, value: &Value, out: PathOutput) -> Result<(), Fault> {
+    out(path.clone(), value.clone())?;
+    if matches!(value, Value::Array(_) | Value::Object(_)) {
+        for (key, child) in children(value)? {
+            let mut path = path.clone();
+            path.push(key);
+            recurse_paths(path, &child, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the items of an array or the values of an object, the latter ordered by key.
+pub(crate) fn iterate(value: &Value) -> Result<Vec<Value>, Fault> {
+    Ok(children(value)?.into_iter().map(|(_, value)| value).collect())
+}
+
+/// Returns the keys and values of an array or an object, the latter ordered by key.
+pub(crate) fn children(value: &Value) -> Result<Vec<(Value, Value)>, Fault> {
+    match value {
+        Value::Array(array) => Ok(array
+            .values
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index.to_value(), item.clone()))
+            .collect()),
+        Value::Object(object) => Ok(entries(object)
+            .into_iter()
+            .map(|(key, value)| (key.to_value(), value.clone()))
+            .collect()),
+        other => Err(type_error(format!(
+            "Cannot iterate over {}",
+            describe(other)
+        ))),
+    }
+}
+
+/// Returns the entries of an object ordered by key.
+pub(crate) fn entries(object: &Object) -> Vec<(String, &Value)> {
+    let mut entries = object
+        .iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect::<Vec<_>>();
+    entries.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+    entries
+}
+
+pub(crate) fn index(target: &Value, key: &Value) -> Result<Value, Fault> {
+    match (target, key) {
+        (Value::Object(object), Value::String(key)) => {
+            Ok(object.get(key.as_str()).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Array(array), Value::Number(_)) => Ok(array_index(array, key)
+            .and_then(|index| array.get(index).cloned())
+            .unwrap_or(Value::Null)),
+        (Value::Array(_) | Value::String(_), Value::Object(bounds)) => slice(
+            target,
+            bounds.get("start").unwrap_or(&Value::Null),
+            bounds.get("end").unwrap_or(&Value::Null),
+        ),
+        (Value::Null | Value::Undefined, Value::String(_) | Value::Number(_) | Value::Object(_)) => {
+            Ok(Value::Null)
+        }
+        _ => Err(type_error(format!(
+            "Cannot index {} with {}",
+            type_name(target),
+            describe_key(key)
+        ))),
+    }
+}
+
+/// Resolves an index, counting negative ones from the end of the array.
+fn array_index(array: &Array, key: &Value) -> Option<usize> {
+    let index = as_f64(key)?.floor() as i64;
+    let index = if index < 0 {
+        array.len() as i64 + index
+    } else {
+        index
+    };
+    usize::try_from(index).ok()
+}
+
+pub(crate) fn slice(target: &Value, from: &Value, to: &Value) -> Result<Value, Fault> {
+    let len = match target {
+        Value::Null | Value::Undefined => return Ok(Value::Null),
+        Value::Array(array) => array.len(),
+        Value::String(string) => string.as_str().chars().count(),
+        other => {
+            return Err(type_error(format!(
+                "Cannot index {} with object",
+                type_name(other)
+            )))
+        }
+    };
+    let (from, to) = slice_bounds(len, from, to)?;
+
+    match target {
+        Value::Array(array) => Ok(array.values[from..to].to_vec().to_value()),
+        Value::String(string) => Ok(string
+            .as_str()
+            .chars()
+            .skip(from)
+            .take(to - from)
+            .collect::<String>()
+            .to_value()),
+        _ => Ok(Value::Null),
+    }
+}
+
+/// Resolves the bounds of a slice, clamping them to the length of the sliced value.
+pub(crate) fn slice_bounds(len: usize, from: &Value, to: &Value) -> Result<(usize, usize), Fault> {
+    let resolve = |bound: &Value, default: usize| match bound {
+        Value::Null | Value::Undefined => Ok(default),
+        Value::Number(number) => {
+            let bound = number.to_f64().unwrap_or(0.0).floor() as i64;
+            let bound = if bound < 0 { len as i64 + bound } else { bound };
+            Ok(bound.clamp(0, len as i64) as usize)
+        }
+        other => Err(type_error(format!(
+            "Start and end indices of an array slice must be numbers, found {}",
+            describe(other)
+        ))),
+    };
+
+    let from = resolve(from, 0)?;
+    let to = resolve(to, len)?;
+    Ok((from, to.max(from)))
+}
+
+pub(crate) fn get_path(value: &Value, path: &[Value]) -> Result<Value, Fault> {
+    path.iter()
+        .try_fold(value.clone(), |value, key| index(&value, key))
+}
+
+pub(crate) fn set_path(value: &mut Value, path: &[Value], new: Value) -> Result<(), Fault> {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = new;
+            return Ok(());
+        }
+    };
+
+    if is_null(value) {
+        *value = match key {
+            Value::String(_) => Object::default().to_value(),
+            _ => Array::new().to_value(),
+        };
+    }
+
+    match (value, key) {
+        (Value::Object(object), Value::String(key)) => {
+            let mut child = object.remove(&key.as_str()).unwrap_or(Value::Null);
+            set_path(&mut child, rest, new)?;
+            object.insert(key.as_str(), child);
+            Ok(())
+        }
+        (Value::Array(array), Value::Number(_)) => {
+            let index = as_f64(key).unwrap_or(0.0).floor() as i64;
+            let index = if index < 0 {
+                array.len() as i64 + index
+            } else {
+                index
+            };
+            let index = usize::try_from(index)
+                .map_err(|_| Fault::Error(JqError::Runtime("Out of bounds negative array index".to_string())))?;
+            while array.len() <= index {
+                array.push(Value::Null);
+            }
+            set_path(&mut array.values[index], rest, new)
+        }
+        (Value::Array(array), Value::Object(bounds)) => {
+            let (from, to) = slice_bounds(
+                array.len(),
+                bounds.get("start").unwrap_or(&Value::Null),
+                bounds.get("end").unwrap_or(&Value::Null),
+            )?;
+            let mut part = array.values[from..to].to_vec().to_value();
+            set_path(&mut part, rest, new)?;
+            match part {
+                Value::Array(part) => {
+                    array.values.splice(from..to, part.values);
+                    Ok(())
+                }
+                _ => Err(type_error(
+                    "A slice of an array can only be assigned another array".to_string(),
+                )),
+            }
+        }
+        (value, key) => Err(type_error(format!(
+            "Cannot index {} with {}",
+            type_name(value),
+            describe_key(key)
+        ))),
+    }
+}
+
+pub(crate) fn delete_paths(value: &mut Value, mut paths: Vec<Vec<Value>>) -> Result<(), Fault> {
+    // Deleting the last paths first keeps the indexes of the remaining ones valid.
+    paths.sort_by(|lhs, rhs| compare_slices(rhs, lhs));
+    paths.dedup_by(|lhs, rhs| compare_slices(lhs, rhs) == Ordering::Equal);
+
+    for path in paths {
+        delete_path(value, &path)?;
+    }
+
+    Ok(())
+}
+
+fn delete_path(value: &mut Value, path: &[Value]) -> Result<(), Fault> {
+    let (key, parents) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            *value = Value::Null;
+            return Ok(());
+        }
+    };
+
+    let mut parent = value;
+    for step in parents {
+        let child = match (parent, step) {
+            (Value::Object(object), Value::String(step)) => object.get_mut(step.as_str()),
+            (Value::Array(array), Value::Number(_)) => match array_index(array, step) {
+                Some(index) => array.get_mut(index),
+                None => None,
+            },
+            _ => None,
+        };
+        match child {
+            Some(child) => parent = child,
+            None => return Ok(()),
+        }
+    }
+
+    match (parent, key) {
+        (Value::Object(object), Value::String(key)) => {
+            object.remove(&key.as_str());
+            Ok(())
+        }
+        (Value::Array(array), Value::Number(_)) => {
+            if let Some(index) = array_index(array, key) {
+                if index < array.len() {
+                    array.values.remove(index);
+                }
+            }
+            Ok(())
+        }
+        (Value::Array(array), Value::Object(bounds)) => {
+            let (from, to) = slice_bounds(
+                array.len(),
+                bounds.get("start").unwrap_or(&Value::Null),
+                bounds.get("end").unwrap_or(&Value::Null),
+            )?;
+            array.values.drain(from..to);
+            Ok(())
+        }
+        (Value::Null | Value::Undefined, _) => Ok(()),
+        (parent, key) => Err(type_error(format!(
+            "Cannot delete field at {} of {}",
+            describe_key(key),
+            type_name(parent)
+        ))),
+    }
+}
+
+pub(crate) fn arithmetic(operator: Operator, lhs: &Value, rhs: &Value) -> Result<Value, Fault> {
+    let numbers = (as_f64(lhs), as_f64(rhs));
+
+    let result = match (operator, lhs, rhs) {
+        (Operator::Add, Value::Null | Value::Undefined, other)
+        | (Operator::Add, other, Value::Null | Value::Undefined) => Some(other.clone()),
+        (_, Value::Number(_), Value::Number(_)) => {
+            let (lhs_number, rhs_number) = (numbers.0.unwrap_or(0.0), numbers.1.unwrap_or(0.0));
+            let result = match operator {
+                Operator::Add => lhs_number + rhs_number,
+                Operator::Subtract => lhs_number - rhs_number,
+                Operator::Multiply => lhs_number * rhs_number,
+                Operator::Divide | Operator::Modulo => {
+                    // The modulo works on integers, so a divisor such as 0.5 is zero as well.
+                    let divisor = match operator {
+                        Operator::Modulo => rhs_number.trunc(),
+                        _ => rhs_number,
+                    };
+                    if divisor == 0.0 {
+                        return Err(Fault::Error(JqError::Runtime(format!(
+                            "{} and {} cannot be divided because the divisor is zero",
+                            describe(lhs),
+                            describe(rhs)
+                        ))));
+                    }
+                    match operator {
+                        Operator::Modulo => ((lhs_number as i64) % (divisor as i64).abs()) as f64,
+                        _ => lhs_number / divisor,
+                    }
+                }
+            };
+            Some(number_value(result))
+        }
+        (Operator::Add, Value::String(lhs), Value::String(rhs)) => {
+            Some(format!("{}{}", lhs.as_str(), rhs.as_str()).to_value())
+        }
+        (Operator::Add, Value::Array(lhs), Value::Array(rhs)) => {
+            let mut values = lhs.values.clone();
+            values.extend(rhs.values.iter().cloned());
+            Some(values.to_value())
+        }
+        (Operator::Add, Value::Object(lhs), Value::Object(rhs)) => {
+            let mut merged = lhs.clone();
+            for (key, value) in rhs.iter() {
+                merged.insert(key, value.clone());
+            }
+            Some(merged.to_value())
+        }
+        (Operator::Subtract, Value::Array(lhs), Value::Array(rhs)) => Some(
+            lhs.values
+                .iter()
+                .filter(|item| !rhs.values.iter().any(|other| equals(item, other)))
+                .cloned()
+                .collect::<Vec<_>>()
+                .to_value(),
+        ),
+        (Operator::Multiply, Value::String(string), Value::Number(_))
+        | (Operator::Multiply, Value::Number(_), Value::String(string)) => {
+            let times = numbers.0.or(numbers.1).unwrap_or(0.0);
+            if times <= 0.0 {
+                Some(Value::Null)
+            } else {
+                Some(string.as_str().repeat((times as usize).max(1)).to_value())
+            }
+        }
+        (Operator::Multiply, Value::Object(lhs), Value::Object(rhs)) => {
+            Some(deep_merge(lhs, rhs).to_value())
+        }
+        (Operator::Divide, Value::String(lhs), Value::String(rhs)) => {
+            Some(split(lhs.as_str(), rhs.as_str()))
+        }
+        _ => None,
+    };
+
+    result.ok_or_else(|| {
+        let verb = match operator {
+            Operator::Add => "added",
+            Operator::Subtract => "subtracted",
+            Operator::Multiply => "multiplied",
+            Operator::Divide | Operator::Modulo => "divided",
+        };
+        type_error(format!(
+            "{} and {} cannot be {}",
+            describe(lhs),
+            describe(rhs),
+            verb
+        ))
+    })
+}
+
+fn deep_merge(lhs: &Object, rhs: &Object) -> Object {
+    let mut merged = lhs.clone();
+    for (key, value) in rhs.iter() {
+        let value = match (merged.get(key), value) {
+            (Some(Value::Object(lhs)), Value::Object(rhs)) => deep_merge(lhs, rhs).to_value(),
+            _ => value.clone(),
+        };
+        merged.insert(key, value);
+    }
+    merged
+}
+
+pub(crate) fn split(string: &str, separator: &str) -> Value {
+    if string.is_empty() {
+        return Array::new().to_value();
+    }
+
+    if separator.is_empty() {
+        return string
+            .chars()
+            .map(|char| char.to_string())
+            .collect::<Vec<_>>()
+            .to_value();
+    }
+
+    string.split(separator).collect::<Vec<_>>().to_value()
+}
+
+/// Orders values the way jq does: null, false, true, numbers, strings, arrays and then objects.
+pub(crate) fn compare(lhs: &Value, rhs: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null | Value::Undefined => 0,
+            Value::Boolean(false) => 1,
+            Value::Boolean(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) | Value::DateTime(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match rank(lhs).cmp(&rank(rhs)) {
+        Ordering::Equal => {}
+        ordering => return ordering,
+    }
+
+    match (lhs, rhs) {
+        (Value::Number(_), Value::Number(_)) => as_f64(lhs)
+            .partial_cmp(&as_f64(rhs))
+            .unwrap_or(Ordering::Equal),
+        (Value::Array(lhs), Value::Array(rhs)) => compare_slices(&lhs.values, &rhs.values),
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            let lhs = entries(lhs);
+            let rhs = entries(rhs);
+            let keys = lhs
+                .iter()
+                .map(|(key, _)| key)
+                .cmp(rhs.iter().map(|(key, _)| key));
+            match keys {
+                Ordering::Equal => lhs
+                    .iter()
+                    .zip(rhs.iter())
+                    .map(|((_, lhs), (_, rhs))| compare(lhs, rhs))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal),
+                ordering => ordering,
+            }
+        }
+        (lhs, rhs) if rank(lhs) == 4 => to_text(lhs).cmp(&to_text(rhs)),
+        _ => Ordering::Equal,
+    }
+}
+
+pub(crate) fn compare_slices(lhs: &[Value], rhs: &[Value]) -> Ordering {
+    lhs.iter()
+        .zip(rhs.iter())
+        .map(|(lhs, rhs)| compare(lhs, rhs))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or_else(|| lhs.len().cmp(&rhs.len()))
+}
+
+pub(crate) fn equals(lhs: &Value, rhs: &Value) -> bool {
+    compare(lhs, rhs) == Ordering::Equal
+}
+
+/// Only `false` and `null` are false.
+pub(crate) fn is_truthy(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Boolean(false) | Value::Null | Value::Undefined
+    )
+}
+
+pub(crate) fn is_null(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Undefined)
+}
+
+pub(crate) fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.to_f64(),
+        _ => None,
+    }
+}
+
+/// Returns a computed float as a value, an integer when it has no fractional part.
+pub(crate) fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        (number as i64).to_value()
+    } else {
+        number.to_value()
+    }
+}
+
+/// Returns strings as they are and every other value as JSON.
+pub(crate) fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.as_string(),
+        Value::DateTime(datetime) => datetime.to_string(),
+        other => other.to_json(JsonMode::Inline),
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null | Value::Undefined => "null",
+        Value::Boolean(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) | Value::DateTime(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Describes a value in error messages, such as `number (42)`.
+pub(crate) fn describe(value: &Value) -> String {
+    let json = match value {
+        Value::Undefined => "null".to_string(),
+        Value::String(_) | Value::DateTime(_) => format!("{:?}", to_text(value)),
+        other => other.to_json(JsonMode::Inline),
+    };
+    let json = if json.chars().count() > 11 {
+        format!("{}...", json.chars().take(10).collect::<String>())
+    } else {
+        json
+    };
+    format!("{} ({})", type_name(value), json)
+}
+
+fn describe_key(key: &Value) -> String {
+    match key {
+        Value::String(key) => format!("{:?}", key.as_str()),
+        other => type_name(other).to_string(),
+    }
+}
+
+pub(crate) fn type_error(message: String) -> Fault {
+    Fault::Error(JqError::Type(message))
+}
+
+pub(crate) fn invalid_path() -> Fault {
+    type_error("Invalid path expression".to_string())
+}
@@ -0,0 +1,383 @@
+//! A jq filter language for `Value`.
+//!
+//! Programs are compiled once into a `Jq` and then run against any number of inputs. Like jq,
+//! a program produces a stream of values rather than a single one, so `run` returns a `Vec`.
+//!
+//! The supported subset covers what most shell pipelines use: path expressions (`.a.b`,
+//! `.[0]`, `.[2:4]`, `.[]`, `..`, `?`), pipes and commas, arithmetic and comparisons, `and`,
+//! `or` and the alternative operator `//`, array and object construction, string
+//! interpolation, variables bound with `as`, `reduce`, `foreach`, `if`/`elif`/`else`,
+//! `try`/`catch`, the assignment operators (`=`, `|=`, `+=`, `//=`, ...) and a library of
+//! builtins such as `map`, `select`, `to_entries`, `from_entries`, `with_entries`,
+//! `sort_by`, `group_by`, `del`, `paths` and `test`. Function definitions, modules and format
+//! strings such as `@csv` are not supported.
+//!
+//! Objects carry no key order, so iterating over an object visits its keys in sorted order.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let value = Value::json_to_value(r#"{"users": [{"name": "a", "active": true}, {"name": "b", "active": false}]}"#).unwrap();
+//! let program = Jq::compile(r#"[.users[] | select(.active) | "user \(.name)"]"#).unwrap();
+//!
+//! assert_eq!(program.run(&value).unwrap(), vec![vec!["user a"].to_value()]);
+//! ```
+mod ast;
+mod builtins;
+mod interpreter;
+
+use crate::prelude::*;
+use ast::{Node, Part};
+use interpreter::Env;
+use std::fmt::{Display, Formatter};
+
+/// The errors raised while compiling or running a jq program.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum JqError {
+    /// The program is not valid.
+    Syntax(String),
+    /// The program calls a function that does not exist, named as `name/arity`.
+    UnknownFunction(String),
+    /// The program reads a variable that was never bound.
+    UndefinedVariable(String),
+    /// A value had the wrong type for an operation, such as iterating over a number.
+    Type(String),
+    /// Raised by `error`, or by an operation such as a division by zero.
+    Runtime(String),
+}
+
+impl Display for JqError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JqError::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            JqError::UnknownFunction(name) => write!(f, "{} is not defined", name),
+            JqError::UndefinedVariable(name) => write!(f, "${} is not defined", name),
+            JqError::Type(msg) => write!(f, "{}", msg),
+            JqError::Runtime(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<JqError> for Error {
+    fn from(error: JqError) -> Self {
+        Error::Jq(error)
+    }
+}
+
+/// A compiled jq program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jq {
+    program: String,
+    ast: Node,
+}
+
+impl Jq {
+    /// Compiles a program. Syntax errors and calls to unknown functions are reported here
+    /// rather than on every run.
+    pub fn compile(program: &str) -> Result<Self, Error> {
+        let ast = ast::parse(program)?;
+        Self::validate(&ast)?;
+
+        Ok(Self {
+            program: program.to_string(),
+            ast,
+        })
+    }
+
+    /// Runs the program against a value, returning every output.
+    pub fn run(&self, input: &Value) -> Result<Vec<Value>, Error> {
+        self.run_with(input, Vec::<(String, Value)>::new())
+    }
+
+    /// Runs the program with predefined variables, the equivalent of `jq --arg` and
+    /// `--argjson`. The names are given without the leading `$`.
+    pub fn run_with<I, K>(&self, input: &Value, variables: I) -> Result<Vec<Value>, Error>
+    where
+        I: IntoIterator<Item = (K, Value)>,
+        K: Into<String>,
+    {
+        let variables = variables
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect::<Vec<_>>();
+        let mut outputs = Vec::new();
+
+        interpreter::eval(&self.ast, input, &Env::Root(&variables), &mut |value| {
+            outputs.push(value);
+            Ok(())
+        })
+        .map_err(|fault| Error::Jq(fault.into_error()))?;
+
+        Ok(outputs)
+    }
+
+    /// Returns the source of the program.
+    pub fn as_str(&self) -> &str {
+        &self.program
+    }
+
+    fn validate(node: &Node) -> Result<(), JqError> {
+        match node {
+            Node::Call(name, args) => {
+                if !builtins::exists(name, args.len()) {
+                    return Err(JqError::UnknownFunction(format!("{}/{}", name, args.len())));
+                }
+                args.iter().try_for_each(Self::validate)
+            }
+            Node::Format(parts) => parts.iter().try_for_each(|part| match part {
+                Part::Interpolation(node) => Self::validate(node),
+                Part::Text(_) => Ok(()),
+            }),
+            Node::Index(lhs, rhs)
+            | Node::Pipe(lhs, rhs)
+            | Node::Comma(lhs, rhs)
+            | Node::Arithmetic(_, lhs, rhs)
+            | Node::Comparison(_, lhs, rhs)
+            | Node::And(lhs, rhs)
+            | Node::Or(lhs, rhs)
+            | Node::Alternative(lhs, rhs)
+            | Node::Assign(_, lhs, rhs)
+            | Node::Bind(lhs, _, rhs) => {
+                Self::validate(lhs)?;
+                Self::validate(rhs)
+            }
+            Node::Slice(target, from, to) => {
+                Self::validate(target)?;
+                from.iter().chain(to.iter()).try_for_each(|node| Self::validate(node))
+            }
+            Node::Iterate(node) | Node::Neg(node) | Node::Array(Some(node)) => Self::validate(node),
+            Node::Object(entries) => entries.iter().try_for_each(|(key, value)| {
+                Self::validate(key)?;
+                Self::validate(value)
+            }),
+            Node::Reduce(source, _, init, update) => {
+                Self::validate(source)?;
+                Self::validate(init)?;
+                Self::validate(update)
+            }
+            Node::Foreach(source, _, init, update, extract) => {
+                Self::validate(source)?;
+                Self::validate(init)?;
+                Self::validate(update)?;
+                extract.iter().try_for_each(|node| Self::validate(node))
+            }
+            Node::If(branches, otherwise) => {
+                branches.iter().try_for_each(|(condition, then)| {
+                    Self::validate(condition)?;
+                    Self::validate(then)
+                })?;
+                otherwise.iter().try_for_each(|node| Self::validate(node))
+            }
+            Node::Try(body, handler) => {
+                Self::validate(body)?;
+                handler.iter().try_for_each(|node| Self::validate(node))
+            }
+            Node::Identity
+            | Node::Recurse
+            | Node::Literal(_)
+            | Node::Array(None)
+            | Node::Variable(_) => Ok(()),
+        }
+    }
+}
+
+impl Display for Jq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)
+    }
+}
+
+impl TryFrom<&str> for Jq {
+    type Error = Error;
+
+    fn try_from(program: &str) -> Result<Self, Self::Error> {
+        Self::compile(program)
+    }
+}
+
+impl Value {
+    /// Compiles and runs a jq program against the value, returning every output. Prefer
+    /// `Jq::compile` when the same program runs more than once.
+    pub fn jq(&self, program: &str) -> Result<Vec<Value>, Error> {
+        Jq::compile(program)?.run(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpreter::equals;
+    use crate::prelude::*;
+
+    const DATA: &str = r#"{
+        "users": [
+            {"name": "alice", "age": 31, "roles": ["admin", "dev"], "active": true},
+            {"name": "bob", "age": 25, "roles": ["dev"], "active": false},
+            {"name": "carol", "age": 42, "roles": [], "active": true}
+        ],
+        "settings": {"theme": "dark", "retries": 3}
+    }"#;
+
+    /// Runs a program and compares its outputs, with jq equality, to a JSON array.
+    fn check(input: &str, program: &str, expected: &str) {
+        let input = Value::json_to_value(input).unwrap();
+        let outputs = input.jq(program).unwrap().to_value();
+        let expected = Value::json_to_value(expected).unwrap();
+
+        assert!(
+            equals(&outputs, &expected),
+            "`{}` returned {}, expected {}",
+            program,
+            outputs.to_json(JsonMode::Inline),
+            expected.to_json(JsonMode::Inline)
+        );
+    }
+
+    #[test]
+    fn test_paths_and_pipes() {
+        check(DATA, ".settings.theme", r#"["dark"]"#);
+        check(DATA, ".users[-1].name", r#"["carol"]"#);
+        check(DATA, ".users[].name", r#"["alice", "bob", "carol"]"#);
+        check(DATA, ".users[1:] | length", "[2]");
+        check(DATA, ".missing.deeper", "[null]");
+        check(DATA, ".settings | .theme, .retries", r#"["dark", 3]"#);
+        check(DATA, "[.. | numbers] | add", "[101]");
+        check(DATA, ".settings.theme[0]?", "[]");
+        check(DATA, r#"."settings"["theme"]"#, r#"["dark"]"#);
+    }
+
+    #[test]
+    fn test_map_select_and_entries() {
+        check(DATA, "[.users[] | select(.active) | .name]", r#"[["alice", "carol"]]"#);
+        check(DATA, ".users | map(.age) | max", "[42]");
+        check(DATA, ".settings | to_entries | map(.key)", r#"[["retries", "theme"]]"#);
+        check(
+            DATA,
+            ".settings | with_entries(.value |= tostring)",
+            r#"[{"theme": "dark", "retries": "3"}]"#,
+        );
+        check("null", r#"[{"key": "a", "value": 1}] | from_entries"#, r#"[{"a": 1}]"#);
+        check(DATA, ".users | sort_by(-.age) | map(.name)", r#"[["carol", "alice", "bob"]]"#);
+        check(
+            DATA,
+            "[.users[] | .roles[]] | group_by(.) | map({(.[0]): length}) | add",
+            r#"[{"admin": 1, "dev": 2}]"#,
+        );
+    }
+
+    #[test]
+    fn test_construction_and_interpolation() {
+        check(
+            DATA,
+            r#"{who: .users[0].name, (.settings.theme): true, "n": (.users | length)}"#,
+            r#"[{"who": "alice", "dark": true, "n": 3}]"#,
+        );
+        check(
+            DATA,
+            ".settings | .retries as $n | {theme, $n}",
+            r#"[{"theme": "dark", "n": 3}]"#,
+        );
+        check("null", "{a: (1, 2)}", r#"[{"a": 1}, {"a": 2}]"#);
+        check(DATA, r#".users[0] | "\(.name) is \(.age)""#, r#"["alice is 31"]"#);
+        check("null", "[1, 2] + [3] - [1]", "[[2, 3]]");
+        check("null", r#"{"a": {"b": 1}} * {"a": {"c": 2}}"#, r#"[{"a": {"b": 1, "c": 2}}]"#);
+    }
+
+    #[test]
+    fn test_tojson_and_fromjson() {
+        check(r#""line\nbreak""#, "tojson", r#"["\"line\\nbreak\""]"#);
+        check(
+            r#"{"a\tb": ["q\"", "\\", "\u0001"]}"#,
+            "tojson",
+            r#"["{\"a\\tb\": [\"q\\\"\",\"\\\\\",\"\\u0001\"]}"]"#,
+        );
+        for input in [r#""line\nbreak""#, r#"{"a\tb": ["q\"", "\\", "\u0001"]}"#] {
+            check(input, "tojson | fromjson", &format!("[{}]", input));
+        }
+    }
+
+    #[test]
+    fn test_reduce_foreach_and_variables() {
+        check(DATA, "reduce .users[] as $user (0; . + $user.age)", "[98]");
+        check("null", "[foreach (1, 2, 3) as $n (0; . + $n)]", "[[1, 3, 6]]");
+        check(DATA, ".settings.retries as $n | [range($n)]", "[[0, 1, 2]]");
+
+        let program = Jq::compile(".users[] | select(.name == $name) | .age").unwrap();
+        let outputs = program
+            .run_with(&Value::json_to_value(DATA).unwrap(), vec![("name", "bob".to_value())])
+            .unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].to_i64(), Some(25));
+    }
+
+    #[test]
+    fn test_alternative_conditionals_and_errors() {
+        check(DATA, ".missing // .settings.theme", r#"["dark"]"#);
+        check("null", "(false, null) // 1", "[1]");
+        check(
+            DATA,
+            r#".users[] | if .age > 40 then "old" elif .age > 30 then "mid" else "young" end"#,
+            r#"["mid", "young", "old"]"#,
+        );
+        check("null", r#"try error("boom") catch ."#, r#"["boom"]"#);
+        check(r#"["1", "x"]"#, r#"[.[] | try tonumber catch "nan"]"#, r#"[[1, "nan"]]"#);
+        check("[1, 2, 3]", "[.[] | (1 / .)?]", "[[1, 0.5, 0.3333333333333333]]");
+        check(DATA, "[limit(2; .users[])] | length", "[2]");
+        check(DATA, "first(.users[] | .name)", r#"["alice"]"#);
+    }
+
+    #[test]
+    fn test_assignment_and_deletion() {
+        check(r#"{"a": {"b": 1}}"#, ".a.b += 1 | .a.c = 3", r#"[{"a": {"b": 2, "c": 3}}]"#);
+        check("[1, 2, 3, 4]", "(.[] | select(. % 2 == 0)) |= . * 10", "[[1, 20, 3, 40]]");
+        check(r#"{"a": null}"#, ".a //= 5 | .b //= 6", r#"[{"a": 5, "b": 6}]"#);
+        check(r#"{"a": 1, "b": 2, "c": null}"#, "del(.a, .c)", r#"[{"b": 2}]"#);
+        check("[1, 2, 3]", "del(.[0, 2])", "[[2]]");
+        check("[1, 2, 3, 4]", "del(.[1:3])", "[[1, 4]]");
+        check(
+            r#"{"a": [{"b": 1}]}"#,
+            "[paths]",
+            r#"[[["a"], ["a", 0], ["a", 0, "b"]]]"#,
+        );
+        check("null", r#"setpath(["a", 1]; true)"#, r#"[{"a": [null, true]}]"#);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Jq::compile(".a |"),
+            Err(Error::Jq(JqError::Syntax(_)))
+        ));
+        assert_eq!(
+            Jq::compile("nope(1)"),
+            Err(Error::Jq(JqError::UnknownFunction("nope/1".to_string())))
+        );
+
+        let data = Value::json_to_value(DATA).unwrap();
+        assert_eq!(
+            data.jq("$missing"),
+            Err(Error::Jq(JqError::UndefinedVariable("missing".to_string())))
+        );
+        assert_eq!(
+            data.jq(".settings.retries[]"),
+            Err(Error::Jq(JqError::Type(
+                "Cannot iterate over number (3)".to_string()
+            )))
+        );
+        assert_eq!(
+            Value::from(vec![5]).jq("from_entries"),
+            Err(Error::Jq(JqError::Type(
+                "Cannot index number (5) with \"key\"".to_string()
+            )))
+        );
+        assert!(matches!(
+            Value::json_to_value(r#"{"n": 5}"#).unwrap().jq("from_entries"),
+            Err(Error::Jq(JqError::Type(_)))
+        ));
+        assert_eq!(
+            data.jq(r#"error("custom")"#),
+            Err(Error::Jq(JqError::Runtime("custom".to_string())))
+        );
+    }
+}
@@ -24,17 +24,29 @@
 //! assert!(undefined_value, Value::Undefined);
 //! datetime_value.add_days(1);
 //! ```
+pub mod codegen;
+pub mod conversion;
+pub mod diff;
+pub mod flatten;
 pub mod impls;
 pub mod macros;
+pub mod merge;
+pub mod patch;
+pub mod pattern;
 pub mod prelude;
 pub mod primitives;
+pub mod schema;
 #[cfg(feature = "serde")]
 pub mod serde_value;
 pub mod to;
 pub mod to_value;
 pub mod traits;
+pub mod transform;
 pub mod types;
+pub mod typescript;
+pub mod validate;
 pub mod value;
+pub mod visitor;
 
 #[cfg(feature = "parser")]
 #[macro_use]
@@ -43,6 +55,12 @@ extern crate pest_derive;
 #[cfg(feature = "parser")]
 pub mod parser;
 
+#[cfg(feature = "jmespath")]
+pub mod jmespath;
+
+#[cfg(feature = "jq")]
+pub mod jq;
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum Error {
     #[cfg(feature = "parser")]
@@ -50,6 +68,15 @@ pub enum Error {
     #[cfg(feature = "parser")]
     NonParseble,
     NotNumber,
+    MergeConflict(String),
+    Patch(patch::PatchError),
+    Schema(schema::SchemaError),
+    Conversion(conversion::ConversionError),
+    Validation(validate::Violations),
+    #[cfg(feature = "jmespath")]
+    JmesPath(jmespath::JmesPathError),
+    #[cfg(feature = "jq")]
+    Jq(jq::JqError),
 }
 
 #[cfg(test)]
@@ -30,6 +30,172 @@ macro_rules! json {
     };
 }
 
+/// Asserts that two values are equivalent, panicking with a structural diff report when they
+/// are not. Numbers compare by value, so `1u8` and `1i32` are equal.
+#[macro_export]
+macro_rules! assert_value_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let diff = $crate::value::Value::diff(&$left, &$right);
+        if !diff.is_empty() {
+            panic!("assertion failed: values differ\n{}", diff.report(false));
+        }
+    }};
+    ($left:expr, $right:expr, $($arg:tt)+) => {{
+        let diff = $crate::value::Value::diff(&$left, &$right);
+        if !diff.is_empty() {
+            panic!(
+                "assertion failed: values differ: {}\n{}",
+                format_args!($($arg)+),
+                diff.report(false)
+            );
+        }
+    }};
+}
+
+/// Builds a `Pattern` from a `json!`-like syntax. See the `pattern` module for the syntax.
+#[macro_export]
+macro_rules! pattern {
+    (@pattern _) => {
+        $crate::pattern::Pattern::Any
+    };
+    (@pattern $name:ident @ $($pattern:tt)+) => {
+        $crate::pattern::Pattern::Capture(
+            stringify!($name).to_string(),
+            Box::new($crate::pattern!(@pattern $($pattern)+)),
+        )
+    };
+    (@pattern null) => {
+        $crate::pattern::Pattern::Literal($crate::value::Value::Null)
+    };
+    (@pattern string) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::String)
+    };
+    (@pattern number) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::Number)
+    };
+    (@pattern integer) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::Integer)
+    };
+    (@pattern bool) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::Bool)
+    };
+    (@pattern array) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::Array)
+    };
+    (@pattern object) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::Object)
+    };
+    (@pattern datetime) => {
+        $crate::pattern::Pattern::Type($crate::pattern::TypePattern::DateTime)
+    };
+    (@pattern [ $($elements:tt)* ]) => {
+        $crate::pattern!(@array [] [] $($elements)*)
+    };
+    (@pattern { $($entries:tt)* }) => {
+        $crate::pattern!(@object [] [] $($entries)*)
+    };
+    (@pattern ..= $($max:tt)+) => {
+        $crate::pattern!(@range None, Some(($($max)+) as f64), true)
+    };
+    (@pattern .. $($max:tt)+) => {
+        $crate::pattern!(@range None, Some(($($max)+) as f64), false)
+    };
+    (@pattern - $min:tt ..= $($max:tt)+) => {
+        $crate::pattern!(@range Some(-($min as f64)), Some(($($max)+) as f64), true)
+    };
+    (@pattern - $min:tt .. $($max:tt)+) => {
+        $crate::pattern!(@range Some(-($min as f64)), Some(($($max)+) as f64), false)
+    };
+    (@pattern - $min:tt ..) => {
+        $crate::pattern!(@range Some(-($min as f64)), None, false)
+    };
+    (@pattern $min:tt ..= $($max:tt)+) => {
+        $crate::pattern!(@range Some($min as f64), Some(($($max)+) as f64), true)
+    };
+    (@pattern $min:tt .. $($max:tt)+) => {
+        $crate::pattern!(@range Some($min as f64), Some(($($max)+) as f64), false)
+    };
+    (@pattern $min:tt ..) => {
+        $crate::pattern!(@range Some($min as f64), None, false)
+    };
+    (@pattern - $value:literal) => {
+        $crate::pattern::Pattern::Literal($crate::traits::ToValueBehavior::to_value(&-$value))
+    };
+    (@pattern $value:literal) => {
+        $crate::pattern::Pattern::Literal($crate::traits::ToValueBehavior::to_value(&$value))
+    };
+    (@pattern ($value:expr)) => {
+        $crate::pattern::Pattern::Literal($crate::traits::ToValueBehavior::to_value(&$value))
+    };
+    (@range $min:expr, $max:expr, $inclusive:expr) => {
+        $crate::pattern::Pattern::Range {
+            min: $min,
+            max: $max,
+            inclusive: $inclusive,
+        }
+    };
+
+    // Splits the elements of an array pattern on commas.
+    (@array [$($out:expr,)*] []) => {
+        $crate::pattern::Pattern::array(vec![$($out,)*])
+    };
+    (@array [$($out:expr,)*] [$($current:tt)+]) => {
+        $crate::pattern::Pattern::array(vec![$($out,)* $crate::pattern!(@element $($current)+),])
+    };
+    (@array [$($out:expr,)*] [$($current:tt)+] , $($rest:tt)*) => {
+        $crate::pattern!(@array [$($out,)* $crate::pattern!(@element $($current)+),] [] $($rest)*)
+    };
+    (@array [$($out:expr,)*] [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::pattern!(@array [$($out,)*] [$($current)* $next] $($rest)*)
+    };
+    (@element ..) => {
+        $crate::pattern::Element::Rest(None)
+    };
+    (@element $name:ident @ ..) => {
+        $crate::pattern::Element::Rest(Some(stringify!($name).to_string()))
+    };
+    (@element $($pattern:tt)+) => {
+        $crate::pattern::Element::Item($crate::pattern!(@pattern $($pattern)+))
+    };
+
+    // Splits the entries of an object pattern on commas.
+    (@object [$($out:expr,)*] []) => {
+        $crate::pattern::Pattern::object(vec![$($out,)*])
+    };
+    (@object [$($out:expr,)*] [$($current:tt)+]) => {
+        $crate::pattern::Pattern::object(vec![$($out,)* $crate::pattern!(@entry $($current)+),])
+    };
+    (@object [$($out:expr,)*] [$($current:tt)+] , $($rest:tt)*) => {
+        $crate::pattern!(@object [$($out,)* $crate::pattern!(@entry $($current)+),] [] $($rest)*)
+    };
+    (@object [$($out:expr,)*] [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::pattern!(@object [$($out,)*] [$($current)* $next] $($rest)*)
+    };
+    (@entry ..) => {
+        $crate::pattern::Entry::Rest(None)
+    };
+    (@entry $name:ident @ ..) => {
+        $crate::pattern::Entry::Rest(Some(stringify!($name).to_string()))
+    };
+    (@entry $key:literal ? : $($pattern:tt)+) => {
+        $crate::pattern!(@field $key, true, $($pattern)+)
+    };
+    (@entry $key:literal : $($pattern:tt)+) => {
+        $crate::pattern!(@field $key, false, $($pattern)+)
+    };
+    (@field $key:expr, $optional:expr, $($pattern:tt)+) => {
+        $crate::pattern::Entry::Field($crate::pattern::Field {
+            key: $key.to_string(),
+            pattern: $crate::pattern!(@pattern $($pattern)+),
+            optional: $optional,
+        })
+    };
+
+    ($($pattern:tt)+) => {
+        $crate::pattern!(@pattern $($pattern)+)
+    };
+}
+
 #[cfg(test)]
 mod test {
     use crate::traits::ToValueBehavior;
@@ -63,4 +229,16 @@ mod test {
 
         assert_eq!(json, map.to_value());
     }
+
+    #[test]
+    fn test_assert_value_eq() {
+        assert_value_eq!(json!({"a": [1, 2]}), json!({"a": [1u8, 2u64]}));
+
+        let result = std::panic::catch_unwind(|| {
+            assert_value_eq!(json!({"a": 1}), json!({"a": 2}), "case {}", 1);
+        });
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("case 1"));
+        assert!(message.contains("@@ /a @@ changed\n- 1\n+ 2"));
+    }
 }
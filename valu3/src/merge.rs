@@ -0,0 +1,399 @@
+//! Configurable deep merge of `Value`s, for layering configuration and similar documents.
+//!
+//! `Value::deep_merge` merges objects key by key and recursively. Everything else is decided by
+//! `MergeOptions`: how arrays combine, which can change per path; what happens when the two
+//! sides disagree on the type of a node; and whether `Null` and `Undefined` members delete a
+//! key, are ignored, or are merged like any other value.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let mut config = Value::json_to_value(r#"{"plugins": ["a"], "server": {"port": 80, "tls": true}}"#).unwrap();
+//! let overrides = Value::json_to_value(r#"{"plugins": ["b"], "server": {"port": 8080, "tls": null}}"#).unwrap();
+//!
+//! let options = MergeOptions::new()
+//!     .arrays(ArrayStrategy::Append)
+//!     .nulls(NullStrategy::Delete);
+//! config.deep_merge(&overrides, &options).unwrap();
+//!
+//! assert_eq!(config.pointer("/plugins/1"), Some(&"b".to_value()));
+//! assert_eq!(config.pointer("/server/tls"), None);
+//! ```
+use crate::patch::equivalent;
+use crate::prelude::*;
+use std::mem::discriminant;
+
+/// How two arrays merge.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ArrayStrategy {
+    /// The incoming array replaces the existing one.
+    #[default]
+    Replace,
+    /// The incoming elements are appended to the existing ones.
+    Append,
+    /// The incoming elements are appended unless an equivalent element is already present.
+    Union,
+    /// Incoming objects are deep merged into the existing object with an equivalent value in
+    /// the given field; the other elements are appended.
+    MergeByKey(String),
+}
+
+/// What happens when the two sides of a merge hold values of different types, such as an
+/// object and a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// The incoming value wins.
+    #[default]
+    Overwrite,
+    /// The existing value is kept.
+    Keep,
+    /// The merge fails with `Error::MergeConflict`.
+    Error,
+}
+
+/// How incoming `Null` and `Undefined` object members are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullStrategy {
+    /// They replace the existing value like any other value.
+    #[default]
+    Replace,
+    /// They remove the key from the object.
+    Delete,
+    /// They are ignored and the existing value is kept.
+    Keep,
+}
+
+/// The options of `Value::deep_merge`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MergeOptions {
+    arrays: ArrayStrategy,
+    paths: Vec<(Vec<String>, ArrayStrategy)>,
+    conflicts: ConflictStrategy,
+    nulls: NullStrategy,
+}
+
+impl MergeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the strategy for arrays without a strategy of their own.
+    pub fn arrays(mut self, strategy: ArrayStrategy) -> Self {
+        self.arrays = strategy;
+        self
+    }
+
+    /// Sets the strategy for the arrays at `path`, a JSON Pointer in which a `*` token matches
+    /// any key or index. When several paths match, the last one set wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is not a valid JSON Pointer.
+    pub fn array_at(mut self, path: &str, strategy: ArrayStrategy) -> Self {
+        let pointer = JsonPointer::parse(path)
+            .unwrap_or_else(|_| panic!("invalid JSON Pointer: \"{}\"", path));
+        self.paths.push((pointer.tokens().to_vec(), strategy));
+        self
+    }
+
+    /// Sets how type mismatches are handled.
+    pub fn conflicts(mut self, strategy: ConflictStrategy) -> Self {
+        self.conflicts = strategy;
+        self
+    }
+
+    /// Sets how incoming `Null` and `Undefined` object members are treated.
+    pub fn nulls(mut self, strategy: NullStrategy) -> Self {
+        self.nulls = strategy;
+        self
+    }
+
+    fn array_strategy(&self, path: &[String]) -> &ArrayStrategy {
+        self.paths
+            .iter()
+            .rev()
+            .find(|(pattern, _)| {
+                pattern.len() == path.len()
+                    && pattern
+                        .iter()
+                        .zip(path)
+                        .all(|(pattern, token)| pattern == "*" || pattern == token)
+            })
+            .map(|(_, strategy)| strategy)
+            .unwrap_or(&self.arrays)
+    }
+}
+
+fn is_absent(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Undefined)
+}
+
+fn field_of<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(object) => object.get(field),
+        _ => None,
+    }
+}
+
+fn pointer(path: &[String]) -> JsonPointer {
+    path.iter()
+        .fold(JsonPointer::root(), |pointer, token| pointer.join(token))
+}
+
+fn merge(
+    base: &mut Value,
+    other: &Value,
+    path: &mut Vec<String>,
+    options: &MergeOptions,
+) -> Result<(), Error> {
+    match (&mut *base, other) {
+        (Value::Object(base), Value::Object(other)) => {
+            let mut keys = other.keys();
+            keys.sort();
+            for key in keys {
+                let value = other.get(key).unwrap();
+                if is_absent(value) {
+                    match options.nulls {
+                        NullStrategy::Delete => {
+                            base.remove(&key);
+                            continue;
+                        }
+                        NullStrategy::Keep => continue,
+                        NullStrategy::Replace => {}
+                    }
+                }
+
+                path.push(key.to_string());
+                match base.get_mut(key) {
+                    Some(current) => merge(current, value, path, options)?,
+                    None => {
+                        let mut current = Value::Undefined;
+                        merge(&mut current, value, path, options)?;
+                        base.insert(key, current);
+                    }
+                }
+                path.pop();
+            }
+            Ok(())
+        }
+        (Value::Array(base), Value::Array(other)) => {
+            match options.array_strategy(path) {
+                ArrayStrategy::Replace => base.values = other.values.clone(),
+                ArrayStrategy::Append => base.values.extend(other.values.iter().cloned()),
+                ArrayStrategy::Union => {
+                    for value in &other.values {
+                        if !base.values.iter().any(|current| equivalent(current, value)) {
+                            base.values.push(value.clone());
+                        }
+                    }
+                }
+                ArrayStrategy::MergeByKey(field) => {
+                    for value in &other.values {
+                        let position = field_of(value, field).and_then(|key| {
+                            base.values.iter().position(|current| {
+                                field_of(current, field)
+                                    .is_some_and(|current| equivalent(current, key))
+                            })
+                        });
+                        match position {
+                            Some(position) => {
+                                path.push(position.to_string());
+                                merge(&mut base.values[position], value, path, options)?;
+                                path.pop();
+                            }
+                            _ => base.values.push(value.clone()),
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        (current, Value::Object(_)) if is_absent(current) => {
+            *base = Value::Object(Object::default());
+            merge(base, other, path, options)
+        }
+        (current, other)
+            if !is_absent(current)
+                && !is_absent(other)
+                && discriminant(&*current) != discriminant(other) =>
+        {
+            match options.conflicts {
+                ConflictStrategy::Overwrite => *current = other.clone(),
+                ConflictStrategy::Keep => {}
+                ConflictStrategy::Error => {
+                    return Err(Error::MergeConflict(format!(
+                        "\"{}\" holds {} and cannot be merged with {}",
+                        pointer(path),
+                        current.to_json(JsonMode::Inline),
+                        other.to_json(JsonMode::Inline)
+                    )))
+                }
+            }
+            Ok(())
+        }
+        (current, other) => {
+            *current = other.clone();
+            Ok(())
+        }
+    }
+}
+
+impl Value {
+    /// Deep merges `other` into this value according to `options`. The merge is atomic: when
+    /// it fails on a conflict the value is left untouched.
+    pub fn deep_merge(&mut self, other: &Value, options: &MergeOptions) -> Result<(), Error> {
+        let mut merged = self.clone();
+        merge(&mut merged, other, &mut Vec::new(), options)?;
+        *self = merged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn merged(base: &str, other: &str, options: &MergeOptions) -> Result<Value, Error> {
+        let mut value = json(base);
+        value.deep_merge(&json(other), options).map(|_| value)
+    }
+
+    #[test]
+    fn test_deep_merge_objects() {
+        let value = merged(
+            r#"{"a": 1, "b": {"c": 2, "d": [1]}, "e": "x"}"#,
+            r#"{"a": 2, "b": {"d": [2], "f": {"g": true}}, "h": null}"#,
+            &MergeOptions::new(),
+        )
+        .unwrap();
+
+        assert_value_eq!(
+            value,
+            json(r#"{"a": 2, "b": {"c": 2, "d": [2], "f": {"g": true}}, "e": "x", "h": null}"#)
+        );
+    }
+
+    #[test]
+    fn test_array_strategies() {
+        let base = r#"{"list": [1, 2], "users": [{"id": 1, "name": "a", "tags": ["x"]}, {"id": 2, "name": "b"}]}"#;
+        let other =
+            r#"{"list": [2, 3], "users": [{"id": 2, "name": "c"}, {"id": 3, "name": "d"}, 4]}"#;
+
+        let cases = [
+            (ArrayStrategy::Replace, r#"[2, 3]"#),
+            (ArrayStrategy::Append, r#"[1, 2, 2, 3]"#),
+            (ArrayStrategy::Union, r#"[1, 2, 3]"#),
+        ];
+        for (strategy, expected) in cases {
+            let value = merged(base, other, &MergeOptions::new().arrays(strategy)).unwrap();
+            assert_value_eq!(value.get("list").unwrap(), json(expected));
+        }
+
+        let options = MergeOptions::new()
+            .arrays(ArrayStrategy::Union)
+            .array_at("/users", ArrayStrategy::MergeByKey("id".to_string()));
+        let value = merged(base, other, &options).unwrap();
+        assert_value_eq!(value.get("list").unwrap(), json(r#"[1, 2, 3]"#));
+        assert_value_eq!(
+            value.get("users").unwrap(),
+            json(
+                r#"[{"id": 1, "name": "a", "tags": ["x"]}, {"id": 2, "name": "c"}, {"id": 3, "name": "d"}, 4]"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_array_strategy_wildcards() {
+        let options = MergeOptions::new()
+            .array_at("/groups", ArrayStrategy::MergeByKey("name".to_string()))
+            .array_at("/groups/*/members", ArrayStrategy::Union);
+        let value = merged(
+            r#"{"groups": [{"name": "a", "members": [1, 2]}]}"#,
+            r#"{"groups": [{"name": "a", "members": [2, 3]}]}"#,
+            &options,
+        )
+        .unwrap();
+
+        assert_value_eq!(
+            value,
+            json(r#"{"groups": [{"name": "a", "members": [1, 2, 3]}]}"#)
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let (base, other) = (r#"{"a": {"b": 1}, "c": 1}"#, r#"{"a": "text", "c": 2}"#);
+
+        let value = merged(base, other, &MergeOptions::new()).unwrap();
+        assert_value_eq!(value, json(r#"{"a": "text", "c": 2}"#));
+
+        let options = MergeOptions::new().conflicts(ConflictStrategy::Keep);
+        let value = merged(base, other, &options).unwrap();
+        assert_value_eq!(value, json(r#"{"a": {"b": 1}, "c": 2}"#));
+
+        let mut value = json(base);
+        let options = MergeOptions::new().conflicts(ConflictStrategy::Error);
+        assert!(matches!(
+            value.deep_merge(&json(other), &options),
+            Err(Error::MergeConflict(_))
+        ));
+        assert_value_eq!(value, json(base));
+    }
+
+    #[test]
+    fn test_nulls() {
+        let (base, other) = (
+            r#"{"a": 1, "b": {"c": 2}}"#,
+            r#"{"a": null, "b": {"c": null, "d": null}}"#,
+        );
+
+        let value = merged(base, other, &MergeOptions::new()).unwrap();
+        assert_value_eq!(value, json(r#"{"a": null, "b": {"c": null, "d": null}}"#));
+
+        let value = merged(
+            base,
+            other,
+            &MergeOptions::new().nulls(NullStrategy::Delete),
+        )
+        .unwrap();
+        assert_value_eq!(value, json(r#"{"b": {}}"#));
+
+        let value = merged(base, other, &MergeOptions::new().nulls(NullStrategy::Keep)).unwrap();
+        assert_value_eq!(value, json(base));
+
+        let mut value = json(base);
+        let mut other = Object::default();
+        other.insert("a", Value::Undefined);
+        value
+            .deep_merge(
+                &Value::Object(other),
+                &MergeOptions::new().nulls(NullStrategy::Delete),
+            )
+            .unwrap();
+        assert_value_eq!(value, json(r#"{"b": {"c": 2}}"#));
+    }
+
+    #[test]
+    fn test_layering() {
+        let layers = [
+            r#"{"log": {"level": "info"}, "ports": [80]}"#,
+            r#"{"log": {"file": "app.log"}, "ports": [443]}"#,
+            r#"{"log": {"level": "debug"}}"#,
+        ];
+        let options = MergeOptions::new().arrays(ArrayStrategy::Append);
+
+        let mut config = Value::Object(Object::default());
+        for layer in layers {
+            config.deep_merge(&json(layer), &options).unwrap();
+        }
+
+        assert_value_eq!(
+            config,
+            json(r#"{"log": {"level": "debug", "file": "app.log"}, "ports": [80, 443]}"#)
+        );
+    }
+}
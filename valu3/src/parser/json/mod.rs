@@ -12,7 +12,7 @@ impl Value {
     pub fn json_to_value(str: &str) -> Result<Value, Error> {
         let value = match JSONParser::parse(Rule::json, str.trim()) {
             Ok(mut pairs) => match pairs.next() {
-                Some(pair) => Self::json_parse_value_inner(pair),
+                Some(pair) => Self::json_parse_value_inner(pair)?,
                 None => return Err(Error::NonParseble),
             },
             Err(msg) => return Err(Error::NonParsebleMsg(msg.to_string())),
@@ -20,8 +20,8 @@ impl Value {
         Ok(value)
     }
 
-    fn json_parse_value_inner(pair: Pair<Rule>) -> Self {
-        match pair.as_rule() {
+    fn json_parse_value_inner(pair: Pair<Rule>) -> Result<Self, Error> {
+        let value = match pair.as_rule() {
             Rule::object => {
                 let map = pair
                     .into_inner()
@@ -33,21 +33,23 @@ impl Value {
                             .into_inner()
                             .next()
                             .unwrap()
-                            .as_str()
-                            .to_string();
-                        let value = Self::json_parse_value_inner(inner_rules.next().unwrap());
-                        (name, value)
+                            .as_str();
+                        let name = string(name)?;
+                        let value = Self::json_parse_value_inner(inner_rules.next().unwrap())?;
+                        Ok((name, value))
                     })
-                    .collect::<HashMap<String, Value>>();
+                    .collect::<Result<HashMap<String, Value>, Error>>()?;
 
                 Self::from(map)
             }
             Rule::array => Self::from(
                 pair.into_inner()
                     .map(Self::json_parse_value_inner)
-                    .collect::<Vec<_>>(),
+                    .collect::<Result<Vec<_>, Error>>()?,
             ),
-            Rule::string => Self::from(StringB::from(pair.into_inner().next().unwrap().as_str())),
+            Rule::string => Self::from(StringB::from(string(
+                pair.into_inner().next().unwrap().as_str(),
+            )?)),
             Rule::number => Self::from(Number::try_from(pair.as_str()).unwrap()),
             Rule::boolean => Self::Boolean(pair.as_str().parse().unwrap()),
             Rule::null => Self::Null,
@@ -58,8 +60,86 @@ impl Value {
             | Rule::inner
             | Rule::char
             | Rule::WHITESPACE => Self::Undefined,
+        };
+        Ok(value)
+    }
+}
+
+/// Decodes a JSON string body. With the `cstring` feature strings are C strings, which cannot
+/// hold the NUL character `\u0000` decodes to.
+fn string(raw: &str) -> Result<String, Error> {
+    let string = unescape(raw);
+    if cfg!(feature = "cstring") && string.contains('\0') {
+        return Err(Error::NonParsebleMsg(
+            "strings cannot contain NUL characters with the `cstring` feature".to_string(),
+        ));
+    }
+    Ok(string)
+}
+
+/// Decodes the escape sequences of a JSON string body, including UTF-16 surrogate pairs.
+/// Invalid sequences are kept as they are.
+pub(crate) fn unescape(raw: &str) -> String {
+    if !raw.contains('\\') {
+        return raw.to_string();
+    }
+
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('"') => result.push('"'),
+            Some('\\') => result.push('\\'),
+            Some('/') => result.push('/'),
+            Some('b') => result.push('\u{8}'),
+            Some('f') => result.push('\u{c}'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('u') => {
+                let high = read_hex(&mut chars);
+                let code = match high {
+                    Some(high) if (0xD800..0xDC00).contains(&high) => {
+                        let mut lookahead = chars.clone();
+                        match (lookahead.next(), lookahead.next(), read_hex(&mut lookahead)) {
+                            (Some('\\'), Some('u'), Some(low)) if (0xDC00..0xE000).contains(&low) => {
+                                chars = lookahead;
+                                Some(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                            }
+                            _ => None,
+                        }
+                    }
+                    other => other,
+                };
+
+                match code.and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    None => result.push(char::REPLACEMENT_CHARACTER),
+                }
+            }
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
         }
     }
+
+    result
+}
+
+fn read_hex(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        code = code * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(code)
 }
 
 #[cfg(test)]
@@ -90,14 +170,12 @@ mod tests {
     fn array() {
         let raw = "[0, true, null, \"ok\"]";
 
-        let compare = {
-            let mut list = Vec::new();
-            list.push(Value::Number(Number::from(0)));
-            list.push(Value::Boolean(true));
-            list.push(Value::Null);
-            list.push(Value::String(StringB::from("ok")));
-            Value::from(list)
-        };
+        let compare = Value::from(vec![
+            Value::Number(Number::from(0)),
+            Value::Boolean(true),
+            Value::Null,
+            Value::String(StringB::from("ok")),
+        ]);
 
         assert_eq!(Value::json_to_value(raw), Ok(compare));
     }
@@ -127,6 +205,29 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "cstring")]
+    fn string_with_nul() {
+        assert!(matches!(
+            Value::json_to_value(r#""a\u0000b""#),
+            Err(Error::NonParsebleMsg(_))
+        ));
+        assert!(matches!(
+            Value::json_to_value(r#"{"a\u0000": 1}"#),
+            Err(Error::NonParsebleMsg(_))
+        ));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let string = r#""line\n\"quoted\" \u03a6 \ud834\udd1e""#;
+
+        assert_eq!(
+            Value::json_to_value(string),
+            Ok(Value::String(StringB::from("line\n\"quoted\" \u{3a6} \u{1d11e}")))
+        );
+    }
+
     #[test]
     fn null() {
         let null = "null";
@@ -141,6 +242,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn all() {
         let boolean = Value::json_to_value("true").unwrap();
         let float = Value::json_to_value("3.14").unwrap();
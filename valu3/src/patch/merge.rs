@@ -0,0 +1,152 @@
+use super::equivalent;
+use crate::prelude::*;
+
+fn merge(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Object::default());
+    }
+    let object = match target {
+        Value::Object(object) => object,
+        _ => unreachable!(),
+    };
+
+    for (key, value) in patch.iter() {
+        if value.is_null() {
+            object.remove(&key);
+            continue;
+        }
+
+        match object.get_mut(key) {
+            Some(current) => merge(current, value),
+            None => {
+                let mut current = Value::Null;
+                merge(&mut current, value);
+                object.insert(key, current);
+            }
+        }
+    }
+}
+
+fn diff(source: &Value, target: &Value) -> Value {
+    let (source, target) = match (source, target) {
+        (Value::Object(source), Value::Object(target)) => (source, target),
+        (_, target) => return target.clone(),
+    };
+
+    let mut patch = Object::default();
+    for key in source.keys() {
+        if !target.contains_key(&key) {
+            patch.insert(key, Value::Null);
+        }
+    }
+    for (key, value) in target.iter() {
+        match source.get(key) {
+            Some(current) if equivalent(current, value) => {}
+            Some(current) => {
+                patch.insert(key, diff(current, value));
+            }
+            None => {
+                patch.insert(key, value.clone());
+            }
+        }
+    }
+
+    Value::Object(patch)
+}
+
+impl Value {
+    /// Applies a JSON Merge Patch (RFC 7386): members of an object patch are merged
+    /// recursively, `null` members delete the matching key, and any other patch replaces the
+    /// value as a whole.
+    pub fn merge_patch(&mut self, patch: &Value) {
+        merge(self, patch)
+    }
+
+    /// Computes the JSON Merge Patch that turns `source` into `target`.
+    ///
+    /// Merge patches cannot set a member to `null`, since `null` means removal, and always
+    /// replace arrays as a whole; `null` members of `target` therefore end up removed once the
+    /// patch is applied.
+    pub fn merge_patch_diff(source: &Value, target: &Value) -> Value {
+        diff(source, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::patch::equivalent;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_merge_patch() {
+        // The examples of RFC 7386, appendix A.
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (
+                r#"{"a":{"b":"c"}}"#,
+                r#"{"a":{"b":"d","c":null}}"#,
+                r#"{"a":{"b":"d"}}"#,
+            ),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (
+                r#"{}"#,
+                r#"{"a":{"bb":{"ccc":null}}}"#,
+                r#"{"a":{"bb":{}}}"#,
+            ),
+        ];
+
+        for (document, patch, expected) in cases {
+            let mut value = json(document);
+            value.merge_patch(&json(patch));
+            assert!(
+                equivalent(&value, &json(expected)),
+                "{} -> {}",
+                patch,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_patch_diff() {
+        let cases = [
+            (
+                r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+                r#"{"title": "Hello!", "author": {"givenName": "John"}, "tags": ["example"], "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890"}"#,
+                r#"{"title": "Hello!", "author": {"familyName": null}, "tags": ["example"], "phoneNumber": "+01-123-456-7890"}"#,
+            ),
+            (r#"{"a": 1}"#, r#"{"a": 1}"#, r#"{}"#),
+            (r#"{"a": 1}"#, r#"[1]"#, r#"[1]"#),
+            (r#"3"#, r#"3"#, r#"3"#),
+        ];
+
+        for (source, target, expected) in cases {
+            let (mut value, target) = (json(source), json(target));
+            let patch = Value::merge_patch_diff(&value, &target);
+
+            assert!(equivalent(&patch, &json(expected)), "{}", patch);
+            value.merge_patch(&patch);
+            assert!(equivalent(&value, &target), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,562 @@
+//! JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7386) support for `Value`.
+//!
+//! `Value::apply_patch` applies a patch document — an array of `add`, `remove`, `replace`,
+//! `move`, `copy` and `test` operations — and `Value::diff_to_patch` produces the patch that
+//! turns one value into another. Locations are addressed with JSON Pointers (RFC 6901), also
+//! available on their own through `JsonPointer` and `Value::pointer`.
+//!
+//! `Value::merge_patch` applies the simpler merge patches, which mirror the shape of the
+//! document they change, and `Value::merge_patch_diff` computes them.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let mut value = Value::json_to_value(r#"{"name": "a", "tags": ["x"]}"#).unwrap();
+//! let patch = Value::json_to_value(r#"[
+//!     {"op": "replace", "path": "/name", "value": "b"},
+//!     {"op": "add", "path": "/tags/-", "value": "y"}
+//! ]"#).unwrap();
+//!
+//! value.apply_patch(&patch).unwrap();
+//! assert_eq!(value.pointer("/tags/1"), Some(&"y".to_value()));
+//! ```
+mod merge;
+mod pointer;
+
+pub use pointer::JsonPointer;
+
+use crate::diff::{align, Step};
+use crate::prelude::*;
+use pointer::{array_index, object_key};
+use std::fmt::{Display, Formatter};
+
+/// The errors raised while applying a JSON Patch.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PatchError {
+    /// A JSON Pointer is not well formed.
+    InvalidPointer(String),
+    /// The patch document or one of its operations is malformed.
+    InvalidOperation(String),
+    /// An operation refers to a location that does not exist.
+    PathNotFound(String),
+    /// A `test` operation found a value different from the expected one.
+    TestFailed(String),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::InvalidPointer(msg) => write!(f, "invalid pointer: {}", msg),
+            PatchError::InvalidOperation(msg) => write!(f, "invalid operation: {}", msg),
+            PatchError::PathNotFound(msg) => write!(f, "path not found: {}", msg),
+            PatchError::TestFailed(msg) => write!(f, "test failed: {}", msg),
+        }
+    }
+}
+
+impl From<PatchError> for Error {
+    fn from(error: PatchError) -> Self {
+        Error::Patch(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operation {
+    Add(JsonPointer, Value),
+    Remove(JsonPointer),
+    Replace(JsonPointer, Value),
+    Move(JsonPointer, JsonPointer),
+    Copy(JsonPointer, JsonPointer),
+    Test(JsonPointer, Value),
+}
+
+impl Operation {
+    fn parse(index: usize, operation: &Value) -> Result<Self, PatchError> {
+        let invalid = |msg: &str| PatchError::InvalidOperation(format!("#{}: {}", index, msg));
+
+        let object = match operation {
+            Value::Object(object) => object,
+            _ => return Err(invalid("an operation must be an object")),
+        };
+        let member = |name: &str| object.get(name);
+        let pointer = |name: &str| match member(name) {
+            Some(Value::String(pointer)) => {
+                JsonPointer::parse(pointer.as_str()).map_err(|error| match error {
+                    Error::Patch(PatchError::InvalidPointer(msg)) => {
+                        PatchError::InvalidPointer(format!("#{}: {}", index, msg))
+                    }
+                    _ => invalid(&format!("invalid \"{}\"", name)),
+                })
+            }
+            Some(_) => Err(invalid(&format!("\"{}\" must be a string", name))),
+            None => Err(invalid(&format!("missing \"{}\"", name))),
+        };
+        let value = || match member("value") {
+            Some(value) => Ok(value.clone()),
+            None => Err(invalid("missing \"value\"")),
+        };
+
+        let op = match member("op") {
+            Some(Value::String(op)) => op.as_str(),
+            Some(_) => return Err(invalid("\"op\" must be a string")),
+            None => return Err(invalid("missing \"op\"")),
+        };
+
+        match op {
+            "add" => Ok(Operation::Add(pointer("path")?, value()?)),
+            "remove" => Ok(Operation::Remove(pointer("path")?)),
+            "replace" => Ok(Operation::Replace(pointer("path")?, value()?)),
+            "move" => Ok(Operation::Move(pointer("from")?, pointer("path")?)),
+            "copy" => Ok(Operation::Copy(pointer("from")?, pointer("path")?)),
+            "test" => Ok(Operation::Test(pointer("path")?, value()?)),
+            op => Err(invalid(&format!("unknown op \"{}\"", op))),
+        }
+    }
+
+    fn apply(&self, index: usize, target: &mut Value) -> Result<(), PatchError> {
+        match self {
+            Operation::Add(path, value) => add(index, target, path, value.clone()),
+            Operation::Remove(path) => remove(index, target, path).map(|_| ()),
+            Operation::Replace(path, value) => match path.get_mut(target) {
+                Some(current) => {
+                    *current = value.clone();
+                    Ok(())
+                }
+                None => Err(not_found(index, path)),
+            },
+            Operation::Move(from, path) => {
+                if from == path {
+                    return match from.get(target) {
+                        Some(_) => Ok(()),
+                        None => Err(not_found(index, from)),
+                    };
+                }
+                if from.is_proper_prefix_of(path) {
+                    return Err(PatchError::InvalidOperation(format!(
+                        "#{}: cannot move \"{}\" into one of its children",
+                        index, from
+                    )));
+                }
+                let value = remove(index, target, from)?;
+                add(index, target, path, value)
+            }
+            Operation::Copy(from, path) => match from.get(target) {
+                Some(value) => add(index, target, path, value.clone()),
+                None => Err(not_found(index, from)),
+            },
+            Operation::Test(path, expected) => match path.get(target) {
+                Some(value) if equivalent(value, expected) => Ok(()),
+                Some(value) => Err(PatchError::TestFailed(format!(
+                    "#{}: \"{}\" is {}, expected {}",
+                    index,
+                    path,
+                    value.to_json(JsonMode::Inline),
+                    expected.to_json(JsonMode::Inline)
+                ))),
+                None => Err(not_found(index, path)),
+            },
+        }
+    }
+}
+
+fn not_found(index: usize, path: &JsonPointer) -> PatchError {
+    PatchError::PathNotFound(format!("#{}: \"{}\"", index, path))
+}
+
+fn add(
+    index: usize,
+    target: &mut Value,
+    path: &JsonPointer,
+    value: Value,
+) -> Result<(), PatchError> {
+    let (parent, token) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            *target = value;
+            return Ok(());
+        }
+    };
+
+    match parent.get_mut(target) {
+        Some(Value::Object(object)) => {
+            let key = object_key(object, token);
+            object.insert(&key, value);
+            Ok(())
+        }
+        Some(Value::Array(array)) => {
+            let position = if token == "-" {
+                Some(array.len())
+            } else {
+                array_index(token).filter(|position| *position <= array.len())
+            };
+            match position {
+                Some(position) => {
+                    array.values.insert(position, value);
+                    Ok(())
+                }
+                None => Err(not_found(index, path)),
+            }
+        }
+        _ => Err(not_found(index, path)),
+    }
+}
+
+fn remove(index: usize, target: &mut Value, path: &JsonPointer) -> Result<Value, PatchError> {
+    let (parent, token) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            return Err(PatchError::InvalidOperation(format!(
+                "#{}: cannot remove the whole document",
+                index
+            )))
+        }
+    };
+
+    let removed = match parent.get_mut(target) {
+        Some(Value::Object(object)) => {
+            let key = object_key(object, token);
+            object.remove(&&key)
+        }
+        Some(Value::Array(array)) => match array_index(token) {
+            Some(position) if position < array.len() => Some(array.values.remove(position)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    removed.ok_or_else(|| not_found(index, path))
+}
+
+/// Compares two values the way JSON does: numbers by their numeric value and objects regardless
+/// of the map that backs them.
+pub(crate) fn equivalent(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => {
+            match (lhs.to_i64(), rhs.to_i64(), lhs.to_u64(), rhs.to_u64()) {
+                (Some(lhs), Some(rhs), _, _) => lhs == rhs,
+                (_, _, Some(lhs), Some(rhs)) => lhs == rhs,
+                _ => lhs.to_f64() == rhs.to_f64(),
+            }
+        }
+        (Value::Array(lhs), Value::Array(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .values
+                    .iter()
+                    .zip(rhs.values.iter())
+                    .all(|(lhs, rhs)| equivalent(lhs, rhs))
+        }
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(key, lhs)| match rhs.get(key) {
+                    Some(rhs) => equivalent(lhs, rhs),
+                    None => false,
+                })
+        }
+        (lhs, rhs) => lhs == rhs,
+    }
+}
+
+fn operation(op: &str, path: &JsonPointer, value: Option<Value>) -> Value {
+    let mut object = Object::default();
+    object.insert("op", op.to_value());
+    object.insert("path", path.to_string().to_value());
+    if let Some(value) = value {
+        object.insert("value", value);
+    }
+    Value::Object(object)
+}
+
+fn sorted_keys(object: &Object) -> Vec<&ValueKey> {
+    let mut keys = object.keys();
+    keys.sort();
+    keys
+}
+
+fn diff(source: &Value, target: &Value, path: &JsonPointer, patch: &mut Vec<Value>) {
+    match (source, target) {
+        (Value::Object(source), Value::Object(target)) => {
+            for key in sorted_keys(source) {
+                if !target.contains_key(&key) {
+                    patch.push(operation("remove", &path.join(key), None));
+                }
+            }
+            for key in sorted_keys(target) {
+                let value = target.get(key).unwrap();
+                match source.get(key) {
+                    Some(current) => diff(current, value, &path.join(key), patch),
+                    None => patch.push(operation("add", &path.join(key), Some(value.clone()))),
+                }
+            }
+        }
+        (Value::Array(source), Value::Array(target)) => {
+            diff_arrays(&source.values, &target.values, path, patch)
+        }
+        (source, target) if equivalent(source, target) => {}
+        (_, target) => patch.push(operation("replace", path, Some(target.clone()))),
+    }
+}
+
+/// Diffs two arrays through their alignment, so that an element that changed in place is
+/// patched rather than removed and added again. `position` tracks the index each step refers
+/// to in the array as the operations emitted so far leave it.
+fn diff_arrays(source: &[Value], target: &[Value], path: &JsonPointer, patch: &mut Vec<Value>) {
+    let mut position = 0;
+    for step in align(source, target) {
+        match step {
+            Step::Same(..) => position += 1,
+            Step::Changed(i, j) => {
+                diff(&source[i], &target[j], &path.join(position), patch);
+                position += 1;
+            }
+            Step::Removed(_) => patch.push(operation("remove", &path.join(position), None)),
+            Step::Added(j) => {
+                patch.push(operation(
+                    "add",
+                    &path.join(position),
+                    Some(target[j].clone()),
+                ));
+                position += 1;
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Applies a JSON Patch document. The operations are applied in order and atomically: when
+    /// one of them fails the value is left untouched and the error is returned.
+    pub fn apply_patch(&mut self, patch: &Value) -> Result<(), Error> {
+        let operations = match patch {
+            Value::Array(operations) => operations
+                .values
+                .iter()
+                .enumerate()
+                .map(|(index, operation)| Operation::parse(index, operation))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                return Err(PatchError::InvalidOperation(
+                    "a patch must be an array of operations".to_string(),
+                )
+                .into())
+            }
+        };
+
+        let mut target = self.clone();
+        for (index, operation) in operations.iter().enumerate() {
+            operation.apply(index, &mut target)?;
+        }
+        *self = target;
+
+        Ok(())
+    }
+
+    /// Produces the JSON Patch that turns this value into `other`. Objects are compared member
+    /// by member and arrays element by element, so only the locations that changed are
+    /// patched.
+    pub fn diff_to_patch(&self, other: &Value) -> Value {
+        let mut patch = Vec::new();
+        diff(self, other, &JsonPointer::root(), &mut patch);
+        Value::Array(Array::from(patch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::equivalent;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn patched(document: &str, patch: &str) -> Result<Value, Error> {
+        let mut value = json(document);
+        value.apply_patch(&json(patch)).map(|_| value)
+    }
+
+    #[test]
+    fn test_apply_operations() {
+        let cases = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+                r#"{"foo": "bar", "baz": "qux"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "baz"]}"#,
+                r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+            ),
+            (
+                r#"{"foo": [1]}"#,
+                r#"[{"op": "add", "path": "/foo/-", "value": 2}]"#,
+                r#"{"foo": [1, 2]}"#,
+            ),
+            (
+                r#"{"foo": 1}"#,
+                r#"[{"op": "add", "path": "", "value": [1]}]"#,
+                r#"[1]"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "remove", "path": "/baz"}]"#,
+                r#"{"foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+                r#"[{"op": "remove", "path": "/foo/1"}]"#,
+                r#"{"foo": ["bar", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux"}"#,
+                r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+                r#"{"baz": "boo"}"#,
+            ),
+            (
+                r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+            ),
+            (
+                r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+                r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+                r#"{"foo": ["all", "cows", "eat", "grass"]}"#,
+            ),
+            (
+                r#"{"foo": {"a": 1}}"#,
+                r#"[{"op": "copy", "from": "/foo", "path": "/bar"}]"#,
+                r#"{"foo": {"a": 1}, "bar": {"a": 1}}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+                r#"[{"op": "test", "path": "/baz", "value": "qux"}, {"op": "test", "path": "/foo/1", "value": 2.0}]"#,
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            ),
+            (
+                r#"{"/": 9, "~1": 10}"#,
+                r#"[{"op": "test", "path": "/~01", "value": 10}, {"op": "remove", "path": "/~1"}]"#,
+                r#"{"~1": 10}"#,
+            ),
+        ];
+
+        for (document, patch, expected) in cases {
+            let value = patched(document, patch).unwrap();
+            assert!(
+                equivalent(&value, &json(expected)),
+                "{} -> {}",
+                patch,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let cases = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz/bat", "value": "qux"}]"#,
+            ),
+            (
+                r#"{"foo": [1]}"#,
+                r#"[{"op": "add", "path": "/foo/2", "value": 2}]"#,
+            ),
+            (
+                r#"{"foo": [1]}"#,
+                r#"[{"op": "add", "path": "/foo/01", "value": 2}]"#,
+            ),
+            (r#"{"foo": 1}"#, r#"[{"op": "remove", "path": "/bar"}]"#),
+            (
+                r#"{"foo": 1}"#,
+                r#"[{"op": "replace", "path": "/bar", "value": 2}]"#,
+            ),
+            (
+                r#"{"foo": {"a": 1}}"#,
+                r#"[{"op": "move", "from": "/foo", "path": "/foo/a/b"}]"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "test", "path": "/foo", "value": "baz"}]"#,
+            ),
+            (r#"{"foo": "bar"}"#, r#"[{"op": "launch", "path": "/foo"}]"#),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "foo", "value": 1}]"#,
+            ),
+            (r#"{"foo": "bar"}"#, r#"[{"op": "add", "path": "/foo"}]"#),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"{"op": "add", "path": "/foo", "value": 1}"#,
+            ),
+        ];
+
+        for (document, patch) in cases {
+            assert!(patched(document, patch).is_err(), "{}", patch);
+        }
+
+        assert!(matches!(
+            patched(
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "test", "path": "/foo", "value": 1}]"#
+            ),
+            Err(Error::Patch(PatchError::TestFailed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_apply_is_atomic() {
+        let mut value = json(r#"{"foo": "bar", "list": [1, 2]}"#);
+        let original = value.clone();
+        let patch = json(
+            r#"[
+                {"op": "remove", "path": "/foo"},
+                {"op": "add", "path": "/list/0", "value": 0},
+                {"op": "test", "path": "/list/0", "value": 1}
+            ]"#,
+        );
+
+        assert!(value.apply_patch(&patch).is_err());
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn test_diff_to_patch() {
+        let cases = [
+            (
+                r#"{"a": 1, "b": [1, 2, 3], "c": {"d": "e"}}"#,
+                r#"{"a": 2, "b": [1, 3, 4], "c": {"d": "e", "f": null}}"#,
+                4,
+            ),
+            (r#"[1, 2, 3, 4, 5]"#, r#"[0, 1, 2, 3, 4, 5]"#, 1),
+            (r#"[1, 2, 3, 4, 5]"#, r#"[1, 2, 4, 5]"#, 1),
+            (
+                r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]"#,
+                r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "c"}]"#,
+                1,
+            ),
+            (r#"["a", "b", "c"]"#, r#"["x", "y"]"#, 3),
+            (r#"{"a": [1]}"#, r#"{"a": {"0": 1}}"#, 1),
+            (r#"{"a": 1}"#, r#"{"a": 1.0}"#, 0),
+            (r#"1"#, r#""one""#, 1),
+        ];
+
+        for (source, target, operations) in cases {
+            let (mut value, target) = (json(source), json(target));
+            let patch = value.diff_to_patch(&target);
+
+            assert_eq!(patch.len(), operations, "{}", patch);
+            value.apply_patch(&patch).unwrap();
+            assert!(equivalent(&value, &target), "{} -> {}", patch, value);
+        }
+    }
+
+    #[test]
+    fn test_diff_to_patch_operations() {
+        let patch = json(r#"{"a": [1, 2], "b": 1}"#).diff_to_patch(&json(r#"{"a": [1, 2, 3]}"#));
+
+        assert_eq!(patch.len(), 2);
+        assert_eq!(patch.pointer("/0/op"), Some(&"remove".to_value()));
+        assert_eq!(patch.pointer("/0/path"), Some(&"/b".to_value()));
+        assert_eq!(patch.pointer("/1/op"), Some(&"add".to_value()));
+        assert_eq!(patch.pointer("/1/path"), Some(&"/a/2".to_value()));
+    }
+}
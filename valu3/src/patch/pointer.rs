@@ -0,0 +1,252 @@
+use super::PatchError;
+use crate::prelude::*;
+use std::fmt::{Display, Formatter};
+
+/// A JSON Pointer (RFC 6901) addressing a location inside a `Value`.
+///
+/// The empty pointer refers to the whole document; every other pointer is a list of reference
+/// tokens separated by `/`, where `~1` escapes `/` and `~0` escapes `~`. Tokens address object
+/// members by key and array elements by index, and the token `-` refers to the position past the
+/// end of an array.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+impl JsonPointer {
+    /// Returns the pointer to the whole document.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parses the string representation of a pointer.
+    pub fn parse(pointer: &str) -> Result<Self, Error> {
+        if pointer.is_empty() {
+            return Ok(Self::root());
+        }
+
+        let rest = match pointer.strip_prefix('/') {
+            Some(rest) => rest,
+            None => {
+                return Err(PatchError::InvalidPointer(format!(
+                    "\"{}\" does not start with \"/\"",
+                    pointer
+                ))
+                .into())
+            }
+        };
+
+        let tokens = rest
+            .split('/')
+            .map(|token| unescape(token, pointer))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { tokens })
+    }
+
+    /// Returns the unescaped reference tokens.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Returns `true` when the pointer refers to the whole document.
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Appends a reference token.
+    pub fn push<T: ToString>(&mut self, token: T) {
+        self.tokens.push(token.to_string());
+    }
+
+    /// Returns a new pointer with a reference token appended.
+    pub fn join<T: ToString>(&self, token: T) -> Self {
+        let mut pointer = self.clone();
+        pointer.push(token);
+        pointer
+    }
+
+    /// Splits the pointer into the pointer of its parent and its last token, or `None` for the
+    /// root pointer.
+    pub fn split_last(&self) -> Option<(JsonPointer, &str)> {
+        let (last, parent) = self.tokens.split_last()?;
+        Some((
+            JsonPointer {
+                tokens: parent.to_vec(),
+            },
+            last,
+        ))
+    }
+
+    /// Returns `true` when `other` is a location strictly inside the one this pointer refers to.
+    pub fn is_proper_prefix_of(&self, other: &JsonPointer) -> bool {
+        self.tokens.len() < other.tokens.len() && other.tokens.starts_with(&self.tokens)
+    }
+
+    /// Returns `true` when the pointer designates `path`, a stack of keys such as the ones
+    /// visitors and `Value::transform` receive. A `*` token matches any key or index.
+    pub fn matches(&self, path: &[ValueKey]) -> bool {
+        self.tokens.len() == path.len()
+            && self
+                .tokens
+                .iter()
+                .zip(path)
+                .all(|(token, key)| token == "*" || *token == key.to_string())
+    }
+
+    /// Resolves the pointer against a value.
+    pub fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.tokens
+            .iter()
+            .try_fold(value, |value, token| match value {
+                Value::Object(object) => object_get(object, token),
+                Value::Array(array) => array.get(array_index(token)?),
+                _ => None,
+            })
+    }
+
+    /// Resolves the pointer against a value, returning a mutable reference.
+    pub fn get_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        self.tokens
+            .iter()
+            .try_fold(value, |value, token| match value {
+                Value::Object(object) => {
+                    let key = object_key(object, token);
+                    object.get_mut(&key)
+                }
+                Value::Array(array) => array.get_mut(array_index(token)?),
+                _ => None,
+            })
+    }
+}
+
+impl Display for JsonPointer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&str> for JsonPointer {
+    type Error = Error;
+
+    fn try_from(pointer: &str) -> Result<Self, Self::Error> {
+        Self::parse(pointer)
+    }
+}
+
+impl Value {
+    /// Looks up a value by its JSON Pointer, returning `None` when the pointer is invalid or does
+    /// not resolve.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        JsonPointer::parse(pointer).ok()?.get(self)
+    }
+
+    /// Looks up a value by its JSON Pointer, returning a mutable reference.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        JsonPointer::parse(pointer).ok()?.get_mut(self)
+    }
+}
+
+fn unescape(token: &str, pointer: &str) -> Result<String, Error> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => {
+                    return Err(PatchError::InvalidPointer(format!(
+                        "\"{}\" contains an invalid escape sequence",
+                        pointer
+                    ))
+                    .into())
+                }
+            },
+            c => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Parses an array index token: a decimal number without leading zeros.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Returns the key of an object member, preferring an existing numeric key over the string key
+/// when the token is a number.
+pub(crate) fn object_key(object: &Object, token: &str) -> ValueKey {
+    if !object.contains_key(&token) {
+        if let Ok(index) = token.parse::<usize>() {
+            if object.contains_key(&index) {
+                return ValueKey::Number(index);
+            }
+        }
+    }
+    ValueKey::from(token)
+}
+
+fn object_get<'a>(object: &'a Object, token: &str) -> Option<&'a Value> {
+    object.get(&object_key(object, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let pointer = JsonPointer::parse("/a~1b/~0c/0").unwrap();
+        assert_eq!(pointer.tokens(), ["a/b", "~c", "0"]);
+        assert_eq!(pointer.to_string(), "/a~1b/~0c/0");
+        assert!(JsonPointer::parse("").unwrap().is_root());
+        assert!(JsonPointer::parse("a").is_err());
+        assert!(JsonPointer::parse("/a~2").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let path = [
+            ValueKey::from("users"),
+            ValueKey::Number(3),
+            ValueKey::from("email"),
+        ];
+
+        assert!(JsonPointer::parse("/users/3/email").unwrap().matches(&path));
+        assert!(JsonPointer::parse("/users/*/email").unwrap().matches(&path));
+        assert!(JsonPointer::parse("/*/*/*").unwrap().matches(&path));
+        assert!(!JsonPointer::parse("/users/*").unwrap().matches(&path));
+        assert!(!JsonPointer::parse("/users/*/name").unwrap().matches(&path));
+        assert!(JsonPointer::root().matches(&[]));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let mut value =
+            Value::json_to_value(r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "m~n": 8}"#).unwrap();
+
+        assert_eq!(value.pointer(""), Some(&value.clone()));
+        assert_eq!(value.pointer("/foo/1"), Some(&"baz".to_value()));
+        assert_eq!(value.pointer("/a~1b"), value.get("a/b"));
+        assert_eq!(value.pointer("/m~0n"), value.get("m~n"));
+        assert_eq!(value.pointer("/"), value.get(""));
+        assert_eq!(value.pointer("/foo/01"), None);
+        assert_eq!(value.pointer("/foo/-"), None);
+        assert_eq!(value.pointer("/missing"), None);
+
+        *value.pointer_mut("/foo/0").unwrap() = "qux".to_value();
+        assert_eq!(value.pointer("/foo/0"), Some(&"qux".to_value()));
+    }
+}
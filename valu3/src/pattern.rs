@@ -0,0 +1,452 @@
+//! Structural pattern matching against `Value`s.
+//!
+//! A `Pattern` describes the shape of a value: wildcards, literals, type predicates, numeric
+//! ranges, arrays with a rest, objects with optional keys, and named captures. Patterns are
+//! usually written with the `pattern!` macro, whose syntax follows `json!` and Rust patterns:
+//!
+//! | Syntax                         | Matches                                                   |
+//! |--------------------------------|-----------------------------------------------------------|
+//! | `_`                            | anything                                                  |
+//! | `"text"`, `1`, `-2.5`, `true`, `null` | an equivalent value                                |
+//! | `(expression)`                 | a value equivalent to the expression, via `to_value`      |
+//! | `string`, `number`, `integer`, `bool`, `array`, `object`, `datetime` | a value of that type |
+//! | `1..=5`, `0..10`, `1..`, `..=(-1)` | a number in the range                                 |
+//! | `[p, q, .., r]`                | an array, with at most one rest `..` for any elements     |
+//! | `{"key": p, "other"?: q}`      | an object holding those keys; `?` makes a key optional    |
+//! | `name @ p`                     | what `p` matches, captured as `name`                      |
+//! | `name @ ..`                    | the rest of an array or object, captured as `name`        |
+//!
+//! Objects may hold keys the pattern does not mention. `Value::matches` returns the captures of
+//! a successful match.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let message = Value::json_to_value(r#"{"type": "order", "id": 7, "items": ["a", "b"]}"#).unwrap();
+//! let order = pattern!({"type": "order", "id": id @ integer, "items": [first @ _, ..]});
+//!
+//! let captures = message.matches(&order).unwrap();
+//! assert_eq!(captures["first"], "a".to_value());
+//! ```
+use crate::patch::equivalent;
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::ops::Index;
+
+/// The type predicates of a `Pattern`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypePattern {
+    String,
+    Number,
+    /// A number without a fractional part.
+    Integer,
+    Bool,
+    Array,
+    Object,
+    Null,
+    DateTime,
+}
+
+/// A pattern to match `Value`s against. See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// Matches anything.
+    Any,
+    /// Matches a value equivalent to this one.
+    Literal(Value),
+    /// Matches a value of the given type.
+    Type(TypePattern),
+    /// Matches a number within the bounds.
+    Range {
+        min: Option<f64>,
+        max: Option<f64>,
+        inclusive: bool,
+    },
+    /// Matches an array whose first elements match `prefix` and last elements match `suffix`.
+    /// Without a rest the array must hold exactly these elements; with one, any number of
+    /// elements may sit in between, captured when the rest is named.
+    Array {
+        prefix: Vec<Pattern>,
+        rest: Option<Option<String>>,
+        suffix: Vec<Pattern>,
+    },
+    /// Matches an object holding the fields. The members not named by a field are captured
+    /// when `rest` is named.
+    Object {
+        fields: Vec<Field>,
+        rest: Option<String>,
+    },
+    /// Matches what the inner pattern matches, and captures the value under a name.
+    Capture(String, Box<Pattern>),
+}
+
+/// A field of an object pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub pattern: Pattern,
+    pub optional: bool,
+}
+
+/// An element of an array pattern, as written in `pattern!`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Item(Pattern),
+    Rest(Option<String>),
+}
+
+/// An entry of an object pattern, as written in `pattern!`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    Field(Field),
+    Rest(Option<String>),
+}
+
+impl Pattern {
+    /// Builds an array pattern from its elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the elements hold more than one rest.
+    pub fn array(elements: Vec<Element>) -> Self {
+        let mut prefix = Vec::new();
+        let mut rest = None;
+        let mut suffix = Vec::new();
+
+        for element in elements {
+            match element {
+                Element::Item(pattern) if rest.is_none() => prefix.push(pattern),
+                Element::Item(pattern) => suffix.push(pattern),
+                Element::Rest(_) if rest.is_some() => {
+                    panic!("an array pattern can hold a single rest")
+                }
+                Element::Rest(name) => rest = Some(name),
+            }
+        }
+
+        Pattern::Array {
+            prefix,
+            rest,
+            suffix,
+        }
+    }
+
+    /// Builds an object pattern from its entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entries hold more than one rest.
+    pub fn object(entries: Vec<Entry>) -> Self {
+        let mut fields = Vec::new();
+        let mut rest = None;
+        let mut has_rest = false;
+
+        for entry in entries {
+            match entry {
+                Entry::Field(field) => fields.push(field),
+                Entry::Rest(_) if has_rest => panic!("an object pattern can hold a single rest"),
+                Entry::Rest(name) => {
+                    has_rest = true;
+                    rest = name;
+                }
+            }
+        }
+
+        Pattern::Object { fields, rest }
+    }
+
+    /// Matches a value, recording captures. Captures made by a pattern that eventually fails
+    /// are left behind; `Value::matches` discards them.
+    fn matches(&self, value: &Value, captures: &mut Captures) -> bool {
+        match self {
+            Pattern::Any => true,
+            Pattern::Literal(literal) => equivalent(value, literal),
+            Pattern::Type(kind) => match (kind, value) {
+                (TypePattern::String, Value::String(_))
+                | (TypePattern::Number, Value::Number(_))
+                | (TypePattern::Bool, Value::Boolean(_))
+                | (TypePattern::Array, Value::Array(_))
+                | (TypePattern::Object, Value::Object(_))
+                | (TypePattern::Null, Value::Null)
+                | (TypePattern::DateTime, Value::DateTime(_)) => true,
+                (TypePattern::Integer, Value::Number(number)) => {
+                    number.is_integer() || number.to_f64().is_some_and(|n| n.fract() == 0.0)
+                }
+                _ => false,
+            },
+            Pattern::Range {
+                min,
+                max,
+                inclusive,
+            } => match value {
+                Value::Number(number) => match number.to_f64() {
+                    Some(number) => {
+                        min.is_none_or(|min| number >= min)
+                            && max.is_none_or(|max| {
+                                if *inclusive {
+                                    number <= max
+                                } else {
+                                    number < max
+                                }
+                            })
+                    }
+                    None => false,
+                },
+                _ => false,
+            },
+            Pattern::Array {
+                prefix,
+                rest,
+                suffix,
+            } => {
+                let values = match value {
+                    Value::Array(array) => &array.values,
+                    _ => return false,
+                };
+                let fixed = prefix.len() + suffix.len();
+                if values.len() < fixed || (rest.is_none() && values.len() != fixed) {
+                    return false;
+                }
+
+                let middle = prefix.len()..values.len() - suffix.len();
+                let matched = prefix
+                    .iter()
+                    .zip(&values[..middle.start])
+                    .chain(suffix.iter().zip(&values[middle.end..]))
+                    .all(|(pattern, value)| pattern.matches(value, captures));
+
+                if matched {
+                    if let Some(Some(name)) = rest {
+                        captures.insert(name, Value::Array(Array::from(values[middle].to_vec())));
+                    }
+                }
+                matched
+            }
+            Pattern::Object { fields, rest } => {
+                let object = match value {
+                    Value::Object(object) => object,
+                    _ => return false,
+                };
+
+                for field in fields {
+                    let matched = match object.get(field.key.as_str()) {
+                        Some(value) => field.pattern.matches(value, captures),
+                        None => field.optional,
+                    };
+                    if !matched {
+                        return false;
+                    }
+                }
+
+                if let Some(name) = rest {
+                    let mut remaining = Object::default();
+                    for (key, value) in object.iter() {
+                        let key_string = key.to_string();
+                        if !fields.iter().any(|field| field.key == key_string) {
+                            remaining.insert(key, value.clone());
+                        }
+                    }
+                    captures.insert(name, Value::Object(remaining));
+                }
+                true
+            }
+            Pattern::Capture(name, pattern) => {
+                let matched = pattern.matches(value, captures);
+                if matched {
+                    captures.insert(name, value.clone());
+                }
+                matched
+            }
+        }
+    }
+}
+
+impl From<Value> for Pattern {
+    fn from(value: Value) -> Self {
+        Pattern::Literal(value)
+    }
+}
+
+/// The values captured by a successful match, by name.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Captures {
+    values: HashMap<String, Value>,
+}
+
+impl Captures {
+    fn insert(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl Index<&str> for Captures {
+    type Output = Value;
+
+    /// Returns the value captured under `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if nothing was captured under `name`.
+    fn index(&self, name: &str) -> &Value {
+        match self.values.get(name) {
+            Some(value) => value,
+            None => panic!("no capture named \"{}\"", name),
+        }
+    }
+}
+
+impl Value {
+    /// Matches the value against a pattern, returning the captures on success.
+    pub fn matches(&self, pattern: &Pattern) -> Option<Captures> {
+        let mut captures = Captures::default();
+        if pattern.matches(self, &mut captures) {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::pattern;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_scalars() {
+        let cases = [
+            (pattern!(_), "null", true),
+            (pattern!("a"), r#""a""#, true),
+            (pattern!("a"), r#""b""#, false),
+            (pattern!(1), "1.0", true),
+            (pattern!(-2), "-2", true),
+            (pattern!(true), "true", true),
+            (pattern!(null), "null", true),
+            (pattern!(null), "false", false),
+            (pattern!(string), r#""a""#, true),
+            (pattern!(number), "1.5", true),
+            (pattern!(integer), "2", true),
+            (pattern!(integer), "2.5", false),
+            (pattern!(bool), "false", true),
+            (pattern!(array), "[]", true),
+            (pattern!(object), "{}", true),
+            (pattern!(object), "[]", false),
+            (pattern!(1..=5), "5", true),
+            (pattern!(1..5), "5", false),
+            (pattern!(1..5), "0.5", false),
+            (pattern!(-1.5..), "-1", true),
+            (pattern!(..=(-1)), "-1", true),
+            (pattern!(..0), "0", false),
+            (pattern!(0..10), r#""5""#, false),
+        ];
+
+        for (index, (pattern, value, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(
+                json(value).matches(&pattern).is_some(),
+                expected,
+                "case {}",
+                index
+            );
+        }
+
+        let limit = 3;
+        assert!(3.to_value().matches(&pattern!((limit))).is_some());
+        assert!(vec![1, 2]
+            .to_value()
+            .matches(&pattern!((vec![1, 2])))
+            .is_some());
+    }
+
+    #[test]
+    fn test_arrays() {
+        let value = json(r#"[1, 2, 3, 4]"#);
+
+        assert!(value.matches(&pattern!([_, _, _, _])).is_some());
+        assert!(value.matches(&pattern!([_, _, _])).is_none());
+        assert!(value.matches(&pattern!([1, ..])).is_some());
+        assert!(value.matches(&pattern!([.., 4])).is_some());
+        assert!(value.matches(&pattern!([1, .., 3])).is_none());
+        assert!(value.matches(&pattern!([1, 2, 3, 4, ..])).is_some());
+        assert!(value.matches(&pattern!([1, 2, 3, 4, 5, ..])).is_none());
+        assert!(json("[]").matches(&pattern!([])).is_some());
+
+        let captures = value
+            .matches(&pattern!([first @ _, middle @ .., last @ 3..]))
+            .unwrap();
+        assert_value_eq!(captures["first"], 1.to_value());
+        assert_value_eq!(captures["middle"], json("[2, 3]"));
+        assert_value_eq!(captures["last"], 4.to_value());
+    }
+
+    #[test]
+    fn test_objects() {
+        let message =
+            json(r#"{"type": "order", "id": 7, "items": [{"sku": "a", "qty": 2}], "note": null}"#);
+
+        let order = pattern!({
+            "type": "order",
+            "id": id @ integer,
+            "items": [{"sku": sku @ string, "qty": 1..}, ..],
+            "coupon"?: coupon @ string,
+        });
+        let captures = message.matches(&order).unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_value_eq!(captures["id"], 7.to_value());
+        assert_value_eq!(captures["sku"], "a".to_value());
+        assert!(captures.get("coupon").is_none());
+
+        assert!(message.matches(&pattern!({"type": "refund"})).is_none());
+        assert!(message.matches(&pattern!({"coupon": _})).is_none());
+        assert!(message.matches(&pattern!({"coupon"?: string})).is_some());
+        assert!(message.matches(&pattern!({"note"?: string})).is_none());
+
+        let captures = message
+            .matches(&pattern!({"type": _, "items": _, other @ ..}))
+            .unwrap();
+        assert_value_eq!(captures["other"], json(r#"{"id": 7, "note": null}"#));
+    }
+
+    #[test]
+    fn test_captures_of_failed_matches_are_dropped() {
+        let value = json(r#"{"a": 1, "b": 2}"#);
+        assert!(value.matches(&pattern!({"a": a @ _, "b": 3})).is_none());
+
+        let captures = value.matches(&pattern!(all @ {"a": 1})).unwrap();
+        assert_value_eq!(captures["all"], value);
+    }
+
+    #[test]
+    fn test_programmatic_patterns() {
+        let pattern = Pattern::array(vec![
+            Element::Item(Pattern::Type(TypePattern::String)),
+            Element::Rest(Some("tail".to_string())),
+        ]);
+        let captures = json(r#"["a", 1, 2]"#).matches(&pattern).unwrap();
+        assert_value_eq!(captures["tail"], json("[1, 2]"));
+
+        let pattern = Pattern::from(json(r#"{"a": [1]}"#));
+        assert!(json(r#"{"a": [1.0]}"#).matches(&pattern).is_some());
+    }
+}
@@ -12,9 +12,25 @@ pub use crate::to::yaml::*;
 pub use crate::value::*;
 pub use crate::Error;
 pub use crate::impls::*;
+pub use crate::patch::*;
+pub use crate::diff::*;
+pub use crate::merge::*;
+pub use crate::flatten::*;
+pub use crate::visitor::*;
+pub use crate::transform::*;
+pub use crate::pattern::*;
+pub use crate::schema::*;
+pub use crate::codegen::*;
+pub use crate::conversion::*;
+pub use crate::typescript::*;
+pub use crate::validate::*;
 #[cfg(feature = "cstring")]
 pub use std::ffi::CString;
 #[cfg(feature = "derive")]
 pub use valu3_derive::*;
 #[cfg(feature = "serde")]
 pub use crate::serde_value::*;
+#[cfg(feature = "jmespath")]
+pub use crate::jmespath::*;
+#[cfg(feature = "jq")]
+pub use crate::jq::*;
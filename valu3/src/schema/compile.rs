@@ -0,0 +1,826 @@
+use super::format::Format;
+use super::SchemaError;
+use crate::prelude::*;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// A compiled schema: a boolean schema or the keywords of an object schema.
+#[derive(Debug, Clone)]
+pub(super) enum Node {
+    Bool(bool),
+    Keywords(Vec<Keyword>),
+}
+
+/// The types of the `type` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum JsonType {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    String,
+    Integer,
+}
+
+impl JsonType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(JsonType::Null),
+            "boolean" => Some(JsonType::Boolean),
+            "object" => Some(JsonType::Object),
+            "array" => Some(JsonType::Array),
+            "number" => Some(JsonType::Number),
+            "string" => Some(JsonType::String),
+            "integer" => Some(JsonType::Integer),
+            _ => None,
+        }
+    }
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            JsonType::Null => "null",
+            JsonType::Boolean => "boolean",
+            JsonType::Object => "object",
+            JsonType::Array => "array",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Integer => "integer",
+        }
+    }
+}
+
+/// A compiled keyword. Subschemas are indexes into the nodes of the `Schema`.
+#[derive(Debug, Clone)]
+pub(super) enum Keyword {
+    Ref(usize),
+    Type(Vec<JsonType>),
+    Enum(Vec<Value>),
+    Const(Value),
+    MultipleOf(f64),
+    Maximum(f64),
+    ExclusiveMaximum(f64),
+    Minimum(f64),
+    ExclusiveMinimum(f64),
+    MaxLength(usize),
+    MinLength(usize),
+    Pattern(Regex),
+    Format(Format),
+    MaxItems(usize),
+    MinItems(usize),
+    UniqueItems,
+    PrefixItems(Vec<usize>),
+    /// `items`, which applies to the elements after the `prefixItems`.
+    Items {
+        schema: usize,
+        skip: usize,
+    },
+    /// `contains` with its `minContains` and `maxContains`.
+    Contains {
+        schema: usize,
+        min: usize,
+        max: Option<usize>,
+    },
+    MaxProperties(usize),
+    MinProperties(usize),
+    Required(Vec<String>),
+    DependentRequired(Vec<(String, Vec<String>)>),
+    Properties(Vec<(String, usize)>),
+    PatternProperties(Vec<(Regex, usize)>),
+    /// `additionalProperties`, which applies to the members matched by neither the
+    /// `properties` nor the `patternProperties` of the same schema.
+    AdditionalProperties {
+        schema: usize,
+        properties: Vec<String>,
+        patterns: Vec<Regex>,
+    },
+    PropertyNames(usize),
+    DependentSchemas(Vec<(String, usize)>),
+    AllOf(Vec<usize>),
+    AnyOf(Vec<usize>),
+    OneOf(Vec<usize>),
+    Not(usize),
+    If {
+        condition: usize,
+        then: Option<usize>,
+        otherwise: Option<usize>,
+    },
+    UnevaluatedItems(usize),
+    UnevaluatedProperties(usize),
+}
+
+impl Keyword {
+    /// The name of the keyword in the schema, which ends its schema path.
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Keyword::Ref(_) => "$ref",
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::MultipleOf(_) => "multipleOf",
+            Keyword::Maximum(_) => "maximum",
+            Keyword::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Keyword::Minimum(_) => "minimum",
+            Keyword::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Keyword::MaxLength(_) => "maxLength",
+            Keyword::MinLength(_) => "minLength",
+            Keyword::Pattern(_) => "pattern",
+            Keyword::Format(_) => "format",
+            Keyword::MaxItems(_) => "maxItems",
+            Keyword::MinItems(_) => "minItems",
+            Keyword::UniqueItems => "uniqueItems",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items { .. } => "items",
+            Keyword::Contains { .. } => "contains",
+            Keyword::MaxProperties(_) => "maxProperties",
+            Keyword::MinProperties(_) => "minProperties",
+            Keyword::Required(_) => "required",
+            Keyword::DependentRequired(_) => "dependentRequired",
+            Keyword::Properties(_) => "properties",
+            Keyword::PatternProperties(_) => "patternProperties",
+            Keyword::AdditionalProperties { .. } => "additionalProperties",
+            Keyword::PropertyNames(_) => "propertyNames",
+            Keyword::DependentSchemas(_) => "dependentSchemas",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+            Keyword::If { .. } => "if",
+            Keyword::UnevaluatedItems(_) => "unevaluatedItems",
+            Keyword::UnevaluatedProperties(_) => "unevaluatedProperties",
+        }
+    }
+}
+
+/// A `$ref` waiting for the whole document to be compiled.
+struct Reference {
+    node: usize,
+    keyword: usize,
+    reference: String,
+    base: String,
+    location: JsonPointer,
+}
+
+/// Compiles a schema document into nodes. References are resolved once the document has been
+/// walked, so that they may point forward and form cycles; a reference to a location that is
+/// not a subschema, such as `#/components/a`, compiles that location on demand.
+pub(super) struct Compiler<'a> {
+    document: &'a Value,
+    nodes: Vec<Node>,
+    locations: HashMap<String, usize>,
+    resources: HashMap<String, JsonPointer>,
+    anchors: HashMap<(String, String), JsonPointer>,
+    references: Vec<Reference>,
+}
+
+impl<'a> Compiler<'a> {
+    pub(super) fn new(document: &'a Value) -> Self {
+        let mut resources = HashMap::new();
+        resources.insert(String::new(), JsonPointer::root());
+
+        Self {
+            document,
+            nodes: Vec::new(),
+            locations: HashMap::new(),
+            resources,
+            anchors: HashMap::new(),
+            references: Vec::new(),
+        }
+    }
+
+    /// Compiles the document, returning its nodes and the index of the root node.
+    pub(super) fn compile(mut self) -> Result<(Vec<Node>, usize), Error> {
+        let root = self.node(self.document, &JsonPointer::root(), "")?;
+
+        while let Some(reference) = self.references.pop() {
+            let target = self.resolve(&reference)?;
+            if let Node::Keywords(keywords) = &mut self.nodes[reference.node] {
+                keywords[reference.keyword] = Keyword::Ref(target);
+            }
+        }
+        self.check_cycles()?;
+
+        Ok((self.nodes, root))
+    }
+
+    /// Rejects references that lead back to their own schema without descending into the
+    /// value, such as `{"$ref": "#"}`, which would otherwise recurse forever on every value.
+    fn check_cycles(&self) -> Result<(), Error> {
+        let mut locations = vec![String::new(); self.nodes.len()];
+        for (location, &index) in &self.locations {
+            locations[index] = location.clone();
+        }
+        // Nodes are numbered in the order the members of objects are walked, so they are
+        // visited by location for the cycle reported to be the same on every run.
+        let mut order = (0..self.nodes.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| locations[*a].cmp(&locations[*b]));
+
+        // 0 is unvisited, 1 is on the current path and 2 is done.
+        let mut states = vec![0u8; self.nodes.len()];
+        for start in order {
+            if states[start] != 0 {
+                continue;
+            }
+            states[start] = 1;
+            let mut stack = vec![(start, in_place(&self.nodes[start]))];
+            while let Some((index, edges)) = stack.last_mut() {
+                match edges.pop() {
+                    Some((keyword, target)) => match states[target] {
+                        0 => {
+                            states[target] = 1;
+                            stack.push((target, in_place(&self.nodes[target])));
+                        }
+                        1 => {
+                            return Err(SchemaError::InvalidSchema(format!(
+                                "a reference cycle does not descend into the value at #{}/{}",
+                                locations[*index], keyword
+                            ))
+                            .into())
+                        }
+                        _ => {}
+                    },
+                    None => {
+                        states[*index] = 2;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        schema: &'a Value,
+        location: &JsonPointer,
+        base: &str,
+    ) -> Result<usize, Error> {
+        let key = location.to_string();
+        if let Some(&index) = self.locations.get(&key) {
+            return Ok(index);
+        }
+
+        let object = match schema {
+            Value::Boolean(value) => {
+                self.nodes.push(Node::Bool(*value));
+                self.locations.insert(key, self.nodes.len() - 1);
+                return Ok(self.nodes.len() - 1);
+            }
+            Value::Object(object) => object,
+            _ => return Err(invalid(location, "a schema must be an object or a boolean")),
+        };
+
+        let index = self.nodes.len();
+        self.nodes.push(Node::Bool(true));
+        self.locations.insert(key, index);
+
+        let mut base = base.to_string();
+        if let Some(id) = object.get("$id") {
+            let id = string(id, &location.join("$id"))?;
+            base = join(&base, id.split('#').next().unwrap_or_default());
+            self.resources.insert(base.clone(), location.clone());
+        }
+        if let Some(anchor) = object.get("$anchor") {
+            let anchor = string(anchor, &location.join("$anchor"))?;
+            self.anchors
+                .insert((base.clone(), anchor.to_string()), location.clone());
+        }
+        // Definitions are compiled up front so that their `$id`s and `$anchor`s are known when
+        // references are resolved.
+        for definitions in ["$defs", "definitions"] {
+            self.schema_map(object, location, &base, definitions)?;
+        }
+
+        let keywords = self.keywords(index, object, location, &base)?;
+        self.nodes[index] = Node::Keywords(keywords);
+        Ok(index)
+    }
+
+    fn keywords(
+        &mut self,
+        index: usize,
+        object: &'a Object,
+        location: &JsonPointer,
+        base: &str,
+    ) -> Result<Vec<Keyword>, Error> {
+        let mut keywords = Vec::new();
+        let at = |name: &str| location.join(name);
+
+        if let Some(reference) = object.get("$ref") {
+            self.references.push(Reference {
+                node: index,
+                keyword: keywords.len(),
+                reference: string(reference, &at("$ref"))?.to_string(),
+                base: base.to_string(),
+                location: at("$ref"),
+            });
+            keywords.push(Keyword::Ref(index));
+        }
+
+        if let Some(types) = object.get("type") {
+            let names = match types {
+                Value::Array(array) => array.values.iter().collect(),
+                value => vec![value],
+            };
+            let types = names
+                .into_iter()
+                .map(|name| {
+                    let name = string(name, &at("type"))?;
+                    JsonType::parse(name)
+                        .ok_or_else(|| invalid(&at("type"), &format!("\"{}\" is not a type", name)))
+                })
+                .collect::<Result<_, _>>()?;
+            keywords.push(Keyword::Type(types));
+        }
+        if let Some(values) = object.get("enum") {
+            match values {
+                Value::Array(array) => keywords.push(Keyword::Enum(array.values.clone())),
+                _ => return Err(invalid(&at("enum"), "\"enum\" must be an array")),
+            }
+        }
+        if let Some(value) = object.get("const") {
+            keywords.push(Keyword::Const(value.clone()));
+        }
+
+        if let Some(value) = object.get("multipleOf") {
+            let divisor = number(value, &at("multipleOf"))?;
+            if divisor <= 0.0 {
+                return Err(invalid(
+                    &at("multipleOf"),
+                    "\"multipleOf\" must be positive",
+                ));
+            }
+            keywords.push(Keyword::MultipleOf(divisor));
+        }
+        for (name, keyword) in [
+            ("maximum", Keyword::Maximum as fn(f64) -> Keyword),
+            ("exclusiveMaximum", Keyword::ExclusiveMaximum),
+            ("minimum", Keyword::Minimum),
+            ("exclusiveMinimum", Keyword::ExclusiveMinimum),
+        ] {
+            if let Some(value) = object.get(name) {
+                keywords.push(keyword(number(value, &at(name))?));
+            }
+        }
+
+        for (name, keyword) in [
+            ("maxLength", Keyword::MaxLength as fn(usize) -> Keyword),
+            ("minLength", Keyword::MinLength),
+            ("maxItems", Keyword::MaxItems),
+            ("minItems", Keyword::MinItems),
+            ("maxProperties", Keyword::MaxProperties),
+            ("minProperties", Keyword::MinProperties),
+        ] {
+            if let Some(value) = object.get(name) {
+                keywords.push(keyword(count(value, &at(name))?));
+            }
+        }
+        if let Some(pattern) = object.get("pattern") {
+            keywords.push(Keyword::Pattern(regex(pattern, &at("pattern"))?));
+        }
+        if let Some(format) = object.get("format") {
+            if let Some(format) = Format::parse(string(format, &at("format"))?) {
+                keywords.push(Keyword::Format(format));
+            }
+        }
+
+        if let Some(unique) = object.get("uniqueItems") {
+            match unique {
+                Value::Boolean(true) => keywords.push(Keyword::UniqueItems),
+                Value::Boolean(false) => {}
+                _ => {
+                    return Err(invalid(
+                        &at("uniqueItems"),
+                        "\"uniqueItems\" must be a boolean",
+                    ))
+                }
+            }
+        }
+        let prefix_items = self.schema_list(object, location, base, "prefixItems")?;
+        let skip = prefix_items.as_ref().map_or(0, Vec::len);
+        if let Some(prefix_items) = prefix_items {
+            keywords.push(Keyword::PrefixItems(prefix_items));
+        }
+        if let Some(schema) = self.schema(object, location, base, "items")? {
+            keywords.push(Keyword::Items { schema, skip });
+        }
+        if let Some(schema) = self.schema(object, location, base, "contains")? {
+            let min = match object.get("minContains") {
+                Some(value) => count(value, &at("minContains"))?,
+                None => 1,
+            };
+            let max = match object.get("maxContains") {
+                Some(value) => Some(count(value, &at("maxContains"))?),
+                None => None,
+            };
+            keywords.push(Keyword::Contains { schema, min, max });
+        }
+
+        if let Some(required) = object.get("required") {
+            keywords.push(Keyword::Required(strings(required, &at("required"))?));
+        }
+        if let Some(dependent) = object.get("dependentRequired") {
+            let dependent = match dependent {
+                Value::Object(dependent) => dependent,
+                _ => {
+                    return Err(invalid(
+                        &at("dependentRequired"),
+                        "\"dependentRequired\" must be an object",
+                    ))
+                }
+            };
+            let mut requirements = Vec::new();
+            for (name, required) in dependent.iter() {
+                let name = name.to_string();
+                let required = strings(required, &at("dependentRequired").join(&name))?;
+                requirements.push((name, required));
+            }
+            requirements.sort();
+            keywords.push(Keyword::DependentRequired(requirements));
+        }
+
+        let properties = self.schema_map(object, location, base, "properties")?;
+        let pattern_properties =
+            match self.schema_map(object, location, base, "patternProperties")? {
+                Some(schemas) => Some(
+                    schemas
+                        .into_iter()
+                        .map(|(pattern, schema)| match Regex::new(&pattern) {
+                            Ok(regex) => Ok((regex, schema)),
+                            Err(err) => Err(invalid(
+                                &at("patternProperties").join(&pattern),
+                                &err.to_string(),
+                            )),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                None => None,
+            };
+        if let Some(schema) = self.schema(object, location, base, "additionalProperties")? {
+            keywords.push(Keyword::AdditionalProperties {
+                schema,
+                properties: properties
+                    .iter()
+                    .flatten()
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                patterns: pattern_properties
+                    .iter()
+                    .flatten()
+                    .map(|(regex, _)| regex.clone())
+                    .collect(),
+            });
+        }
+        if let Some(properties) = properties {
+            keywords.push(Keyword::Properties(properties));
+        }
+        if let Some(pattern_properties) = pattern_properties {
+            keywords.push(Keyword::PatternProperties(pattern_properties));
+        }
+        if let Some(schema) = self.schema(object, location, base, "propertyNames")? {
+            keywords.push(Keyword::PropertyNames(schema));
+        }
+        if let Some(dependent) = self.schema_map(object, location, base, "dependentSchemas")? {
+            keywords.push(Keyword::DependentSchemas(dependent));
+        }
+
+        for (name, keyword) in [
+            ("allOf", Keyword::AllOf as fn(Vec<usize>) -> Keyword),
+            ("anyOf", Keyword::AnyOf),
+            ("oneOf", Keyword::OneOf),
+        ] {
+            if let Some(schemas) = self.schema_list(object, location, base, name)? {
+                if schemas.is_empty() {
+                    return Err(invalid(
+                        &at(name),
+                        &format!("\"{}\" must not be empty", name),
+                    ));
+                }
+                keywords.push(keyword(schemas));
+            }
+        }
+        if let Some(schema) = self.schema(object, location, base, "not")? {
+            keywords.push(Keyword::Not(schema));
+        }
+        let then = self.schema(object, location, base, "then")?;
+        let otherwise = self.schema(object, location, base, "else")?;
+        if let Some(condition) = self.schema(object, location, base, "if")? {
+            keywords.push(Keyword::If {
+                condition,
+                then,
+                otherwise,
+            });
+        }
+
+        // The unevaluated keywords depend on the annotations of all the others, so they come
+        // last.
+        if let Some(schema) = self.schema(object, location, base, "unevaluatedItems")? {
+            keywords.push(Keyword::UnevaluatedItems(schema));
+        }
+        if let Some(schema) = self.schema(object, location, base, "unevaluatedProperties")? {
+            keywords.push(Keyword::UnevaluatedProperties(schema));
+        }
+
+        Ok(keywords)
+    }
+
+    /// Compiles the subschema held by a keyword.
+    fn schema(
+        &mut self,
+        object: &'a Object,
+        location: &JsonPointer,
+        base: &str,
+        name: &str,
+    ) -> Result<Option<usize>, Error> {
+        match object.get(name) {
+            Some(schema) => Ok(Some(self.node(schema, &location.join(name), base)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Compiles the array of subschemas held by a keyword.
+    fn schema_list(
+        &mut self,
+        object: &'a Object,
+        location: &JsonPointer,
+        base: &str,
+        name: &str,
+    ) -> Result<Option<Vec<usize>>, Error> {
+        let location = location.join(name);
+        match object.get(name) {
+            Some(Value::Array(array)) => array
+                .values
+                .iter()
+                .enumerate()
+                .map(|(index, schema)| self.node(schema, &location.join(index), base))
+                .collect::<Result<_, _>>()
+                .map(Some),
+            Some(_) => Err(invalid(
+                &location,
+                &format!("\"{}\" must be an array", name),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Compiles the object of subschemas held by a keyword, sorted by name.
+    fn schema_map(
+        &mut self,
+        object: &'a Object,
+        location: &JsonPointer,
+        base: &str,
+        name: &str,
+    ) -> Result<Option<Vec<(String, usize)>>, Error> {
+        let location = location.join(name);
+        let schemas = match object.get(name) {
+            Some(Value::Object(schemas)) => schemas,
+            Some(_) => {
+                return Err(invalid(
+                    &location,
+                    &format!("\"{}\" must be an object", name),
+                ))
+            }
+            None => return Ok(None),
+        };
+
+        let mut compiled = Vec::new();
+        for (key, schema) in schemas.iter() {
+            let key = key.to_string();
+            let index = self.node(schema, &location.join(&key), base)?;
+            compiled.push((key, index));
+        }
+        compiled.sort();
+        Ok(Some(compiled))
+    }
+
+    fn resolve(&mut self, reference: &Reference) -> Result<usize, Error> {
+        let unresolved = || {
+            Error::from(SchemaError::UnresolvedReference(format!(
+                "\"{}\" at #{}",
+                reference.reference, reference.location
+            )))
+        };
+
+        let (uri, fragment) = reference
+            .reference
+            .split_once('#')
+            .unwrap_or((reference.reference.as_str(), ""));
+        let base = if uri.is_empty() {
+            reference.base.clone()
+        } else {
+            join(&reference.base, uri)
+        };
+        let resource = self.resources.get(&base).ok_or_else(unresolved)?;
+
+        let target = if fragment.is_empty() || fragment.starts_with('/') {
+            let fragment =
+                JsonPointer::parse(&percent_decode(fragment)).map_err(|_| unresolved())?;
+            let mut target = resource.clone();
+            for token in fragment.tokens() {
+                target.push(token);
+            }
+            target
+        } else {
+            self.anchors
+                .get(&(base.clone(), fragment.to_string()))
+                .ok_or_else(unresolved)?
+                .clone()
+        };
+
+        if let Some(&index) = self.locations.get(&target.to_string()) {
+            return Ok(index);
+        }
+        let document = self.document;
+        let schema = target.get(document).ok_or_else(unresolved)?;
+        self.node(schema, &target, &base)
+    }
+}
+
+/// Returns the subschemas a node applies to the same value, with the keywords holding them.
+fn in_place(node: &Node) -> Vec<(&'static str, usize)> {
+    let keywords = match node {
+        Node::Bool(_) => return Vec::new(),
+        Node::Keywords(keywords) => keywords,
+    };
+    let mut edges = Vec::new();
+    for keyword in keywords {
+        let name = keyword.name();
+        match keyword {
+            Keyword::Ref(target) | Keyword::Not(target) => edges.push((name, *target)),
+            Keyword::AllOf(targets) | Keyword::AnyOf(targets) | Keyword::OneOf(targets) => {
+                edges.extend(targets.iter().map(|target| (name, *target)))
+            }
+            Keyword::DependentSchemas(targets) => {
+                edges.extend(targets.iter().map(|(_, target)| (name, *target)))
+            }
+            Keyword::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                edges.push((name, *condition));
+                edges.extend(then.iter().chain(otherwise).map(|target| (name, *target)));
+            }
+            _ => {}
+        }
+    }
+    edges
+}
+
+fn invalid(location: &JsonPointer, message: &str) -> Error {
+    SchemaError::InvalidSchema(format!("{} at #{}", message, location)).into()
+}
+
+fn keyword_name(location: &JsonPointer) -> String {
+    location
+        .tokens()
+        .last()
+        .map(|name| format!("\"{}\"", name))
+        .unwrap_or_default()
+}
+
+fn string<'v>(value: &'v Value, location: &JsonPointer) -> Result<&'v str, Error> {
+    match value {
+        Value::String(string) => Ok(string.as_str()),
+        _ => Err(invalid(
+            location,
+            &format!("{} must be a string", keyword_name(location)),
+        )),
+    }
+}
+
+fn strings(value: &Value, location: &JsonPointer) -> Result<Vec<String>, Error> {
+    match value {
+        Value::Array(array) => array
+            .values
+            .iter()
+            .map(|value| string(value, location).map(str::to_string))
+            .collect(),
+        _ => Err(invalid(
+            location,
+            &format!("{} must be an array of strings", keyword_name(location)),
+        )),
+    }
+}
+
+fn number(value: &Value, location: &JsonPointer) -> Result<f64, Error> {
+    match value {
+        Value::Number(number) => number.to_f64().ok_or_else(|| {
+            invalid(
+                location,
+                &format!("{} must be a number", keyword_name(location)),
+            )
+        }),
+        _ => Err(invalid(
+            location,
+            &format!("{} must be a number", keyword_name(location)),
+        )),
+    }
+}
+
+fn count(value: &Value, location: &JsonPointer) -> Result<usize, Error> {
+    let count = number(value, location)?;
+    if count < 0.0 || count.fract() != 0.0 {
+        return Err(invalid(
+            location,
+            &format!("{} must be a non-negative integer", keyword_name(location)),
+        ));
+    }
+    Ok(count as usize)
+}
+
+fn regex(value: &Value, location: &JsonPointer) -> Result<Regex, Error> {
+    Regex::new(string(value, location)?).map_err(|err| invalid(location, &err.to_string()))
+}
+
+/// Resolves a reference against a base URI. Dot segments are not normalized.
+fn join(base: &str, reference: &str) -> String {
+    let has_scheme = |uri: &str| {
+        uri.split_once(':').is_some_and(|(scheme, _)| {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        })
+    };
+
+    if base.is_empty() || has_scheme(reference) {
+        return reference.to_string();
+    }
+
+    let authority = base.find("://").map(|start| {
+        let rest = start + 3;
+        base[rest..].find('/').map_or(base.len(), |end| rest + end)
+    });
+    if let Some(path) = reference.strip_prefix("//") {
+        let scheme = base.split_once(':').map_or("", |(scheme, _)| scheme);
+        return format!("{}://{}", scheme, path);
+    }
+    if reference.starts_with('/') {
+        return match authority {
+            Some(end) => format!("{}{}", &base[..end], reference),
+            None => reference.to_string(),
+        };
+    }
+    match base.rfind('/') {
+        Some(end) if authority.is_none_or(|authority| end >= authority) => {
+            format!("{}{}", &base[..=end], reference)
+        }
+        _ => match authority {
+            Some(end) => format!("{}/{}", &base[..end], reference),
+            None => reference.to_string(),
+        },
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join, percent_decode};
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join("", "a.json"), "a.json");
+        assert_eq!(
+            join("https://x.com/a/b.json", "c.json"),
+            "https://x.com/a/c.json"
+        );
+        assert_eq!(
+            join("https://x.com/a/b.json", "/c.json"),
+            "https://x.com/c.json"
+        );
+        assert_eq!(join("https://x.com", "c.json"), "https://x.com/c.json");
+        assert_eq!(join("https://x.com/a", "//y.com/c"), "https://y.com/c");
+        assert_eq!(join("https://x.com/a", "urn:c"), "urn:c");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("/a%20b/%25"), "/a b/%");
+        assert_eq!(percent_decode("/a%2"), "/a%2");
+    }
+}
@@ -0,0 +1,115 @@
+use regex::Regex;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+/// The values of the `format` keyword that are asserted. Other formats are ignored, as the
+/// specification allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    DateTime,
+    Date,
+    Email,
+    Uri,
+    Uuid,
+    Ipv4,
+    Ipv6,
+    Regex,
+}
+
+impl Format {
+    pub(super) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "date-time" => Some(Format::DateTime),
+            "date" => Some(Format::Date),
+            "email" => Some(Format::Email),
+            "uri" => Some(Format::Uri),
+            "uuid" => Some(Format::Uuid),
+            "ipv4" => Some(Format::Ipv4),
+            "ipv6" => Some(Format::Ipv6),
+            "regex" => Some(Format::Regex),
+            _ => None,
+        }
+    }
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Format::DateTime => "date-time",
+            Format::Date => "date",
+            Format::Email => "email",
+            Format::Uri => "uri",
+            Format::Uuid => "uuid",
+            Format::Ipv4 => "ipv4",
+            Format::Ipv6 => "ipv6",
+            Format::Regex => "regex",
+        }
+    }
+
+    pub(crate) fn is_valid(&self, text: &str) -> bool {
+        match self {
+            Format::DateTime => chrono::DateTime::parse_from_rfc3339(text).is_ok(),
+            Format::Date => {
+                text.len() == 10 && chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok()
+            }
+            Format::Email => regex(&EMAIL, r"^[^\s@]+@[^\s@.]+(\.[^\s@.]+)*$").is_match(text),
+            Format::Uri => {
+                regex(&URI, r#"^[A-Za-z][A-Za-z0-9+.\-]*:[^\s"<>\\^`{|}]*$"#).is_match(text)
+            }
+            Format::Uuid => regex(
+                &UUID,
+                r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+            )
+            .is_match(text),
+            Format::Ipv4 => text.parse::<Ipv4Addr>().is_ok(),
+            Format::Ipv6 => text.parse::<Ipv6Addr>().is_ok(),
+            Format::Regex => Regex::new(text).is_ok(),
+        }
+    }
+}
+
+static EMAIL: OnceLock<Regex> = OnceLock::new();
+static URI: OnceLock<Regex> = OnceLock::new();
+static UUID: OnceLock<Regex> = OnceLock::new();
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Format;
+
+    #[test]
+    fn test_formats() {
+        let cases = [
+            (Format::DateTime, "2024-02-29T10:20:30Z", true),
+            (Format::DateTime, "2024-02-29T10:20:30.5+02:00", true),
+            (Format::DateTime, "2023-02-29T10:20:30Z", false),
+            (Format::DateTime, "2024-02-29 10:20", false),
+            (Format::Date, "2024-12-31", true),
+            (Format::Date, "2024-13-01", false),
+            (Format::Email, "someone@example.com", true),
+            (Format::Email, "someone@", false),
+            (Format::Email, "some one@example.com", false),
+            (Format::Uri, "https://example.com/a?b=c#d", true),
+            (Format::Uri, "urn:isbn:0451450523", true),
+            (Format::Uri, "/relative/path", false),
+            (Format::Uuid, "123e4567-e89b-12d3-a456-426614174000", true),
+            (Format::Uuid, "123e4567e89b12d3a456426614174000", false),
+            (Format::Ipv4, "192.168.0.1", true),
+            (Format::Ipv4, "256.0.0.1", false),
+            (Format::Ipv6, "::1", true),
+            (Format::Regex, "^a+$", true),
+            (Format::Regex, "(", false),
+        ];
+
+        for (format, text, expected) in cases {
+            assert_eq!(
+                format.is_valid(text),
+                expected,
+                "{} {}",
+                format.name(),
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use super::format::Format;
+use super::to_schema::DRAFT_2020_12;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The formats looked for in strings, by priority.
+const FORMATS: [Format; 5] = [
+    Format::DateTime,
+    Format::Date,
+    Format::Uuid,
+    Format::Email,
+    Format::Uri,
+];
+
+/// Infers a JSON Schema from sample values.
+///
+/// Every sample refines the shape of the values seen at each location: the types found there,
+/// which become a `type` union, the range of numbers, the format shared by all strings, the
+/// shape of array items, and which object members were present in every sample, which become
+/// `required`.
+///
+/// With [`SchemaInferrer::enums`], strings drawn from a small closed set of values, some of them
+/// repeated across samples, are also described with `enum`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use valu3::prelude::*;
+///
+/// let samples = [
+///     Value::json_to_value(r#"{"id": 1, "email": "a@x.com"}"#).unwrap(),
+///     Value::json_to_value(r#"{"id": 7, "email": null, "tags": ["a"]}"#).unwrap(),
+/// ];
+///
+/// let schema = SchemaInferrer::infer(&samples);
+/// // {"type": "object", "required": ["email", "id"], "properties": {
+/// //     "id": {"type": "integer", "minimum": 1, "maximum": 7},
+/// //     "email": {"type": ["string", "null"], "format": "email"},
+/// //     "tags": {"type": "array", "items": {"type": "string"}}}, ...}
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaInferrer {
+    shape: Shape,
+    enums: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    samples: usize,
+    null: bool,
+    boolean: bool,
+    number: Option<NumberShape>,
+    string: Option<StringShape>,
+    array: Option<Box<Shape>>,
+    object: Option<BTreeMap<String, Shape>>,
+    objects: usize,
+}
+
+#[derive(Debug, Clone)]
+struct NumberShape {
+    min: f64,
+    max: f64,
+    integer: bool,
+}
+
+#[derive(Debug, Clone)]
+struct StringShape {
+    /// The formats every string seen so far matches.
+    formats: Vec<Format>,
+    /// The distinct strings seen so far, while enums are inferred and there are few enough.
+    values: Option<BTreeSet<String>>,
+    count: usize,
+}
+
+impl SchemaInferrer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Infers the schema document of a set of samples.
+    pub fn infer<'a, I>(samples: I) -> Value
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        let mut inferrer = Self::new();
+        for sample in samples {
+            inferrer.add(sample);
+        }
+        inferrer.schema()
+    }
+
+    /// Describes strings with `enum` when they take at most `limit` distinct values and at
+    /// least one of them was seen more than once. Disabled by default.
+    pub fn enums(mut self, limit: usize) -> Self {
+        self.enums = Some(limit);
+        self
+    }
+
+    /// Refines the inferred schema with a sample.
+    pub fn add(&mut self, sample: &Value) {
+        self.shape.add(sample, self.enums);
+    }
+
+    /// Returns the number of samples added.
+    pub fn samples(&self) -> usize {
+        self.shape.samples
+    }
+
+    /// Returns the schema document inferred from the samples so far. Without samples, the
+    /// schema accepts anything.
+    pub fn schema(&self) -> Value {
+        let mut document = BTreeMap::new();
+        document.insert("$schema".to_string(), DRAFT_2020_12.to_value());
+        if let Value::Object(schema) = self.shape.schema() {
+            for (key, value) in schema.iter() {
+                document.insert(key.to_string(), value.clone());
+            }
+        }
+        Value::Object(Object::from(document))
+    }
+}
+
+impl Shape {
+    fn add(&mut self, value: &Value, enums: Option<usize>) {
+        self.samples += 1;
+
+        match value {
+            Value::Null | Value::Undefined => self.null = true,
+            Value::Boolean(_) => self.boolean = true,
+            Value::Number(number) => {
+                let (value, integer) = match number.to_f64() {
+                    Some(value) => (value, number.is_integer() || value.fract() == 0.0),
+                    None => return,
+                };
+                let shape = self.number.get_or_insert(NumberShape {
+                    min: value,
+                    max: value,
+                    integer,
+                });
+                shape.min = shape.min.min(value);
+                shape.max = shape.max.max(value);
+                shape.integer &= integer;
+            }
+            Value::String(string) => self.add_string(string.as_str(), enums),
+            Value::DateTime(date_time) => self.add_string(&date_time.to_string(), enums),
+            Value::Array(array) => {
+                let items = self.array.get_or_insert_with(Default::default);
+                for item in array.values.iter() {
+                    items.add(item, enums);
+                }
+            }
+            Value::Object(object) => {
+                self.objects += 1;
+                let properties = self.object.get_or_insert_with(BTreeMap::new);
+                for (key, member) in object.iter() {
+                    properties
+                        .entry(key.to_string())
+                        .or_default()
+                        .add(member, enums);
+                }
+            }
+        }
+    }
+
+    fn add_string(&mut self, text: &str, enums: Option<usize>) {
+        let shape = self.string.get_or_insert_with(|| StringShape {
+            formats: FORMATS.to_vec(),
+            values: enums.map(|_| BTreeSet::new()),
+            count: 0,
+        });
+        shape.formats.retain(|format| format.is_valid(text));
+        shape.count += 1;
+        if let Some(values) = &mut shape.values {
+            values.insert(text.to_string());
+            if values.len() > enums.unwrap_or_default() {
+                shape.values = None;
+            }
+        }
+    }
+
+    fn schema(&self) -> Value {
+        if self.samples == 0 {
+            return Value::Boolean(true);
+        }
+
+        let mut types = Vec::new();
+        let mut schema = BTreeMap::new();
+
+        if let Some(number) = &self.number {
+            types.push(if number.integer { "integer" } else { "number" });
+            schema.insert("minimum", bound(number.min, number.integer));
+            schema.insert("maximum", bound(number.max, number.integer));
+        }
+        if let Some(string) = &self.string {
+            types.push("string");
+            if let Some(format) = string.formats.first() {
+                schema.insert("format", format.name().to_value());
+            } else if let Some(values) = &string.values {
+                let only_strings = self.number.is_none()
+                    && !self.boolean
+                    && self.array.is_none()
+                    && self.object.is_none();
+                if only_strings && string.count > values.len() {
+                    let mut values = values
+                        .iter()
+                        .map(|value| value.to_value())
+                        .collect::<Vec<_>>();
+                    if self.null {
+                        values.push(Value::Null);
+                    }
+                    schema.insert("enum", values.to_value());
+                }
+            }
+        }
+        if self.boolean {
+            types.push("boolean");
+        }
+        if let Some(items) = &self.array {
+            types.push("array");
+            if items.samples > 0 {
+                schema.insert("items", items.schema());
+            }
+        }
+        if let Some(properties) = &self.object {
+            types.push("object");
+            let mut required = Vec::new();
+            let mut schemas = BTreeMap::new();
+            for (name, property) in properties {
+                if property.samples == self.objects {
+                    required.push(name.to_value());
+                }
+                schemas.insert(name.as_str(), property.schema());
+            }
+            schema.insert("properties", Value::Object(Object::from(schemas)));
+            if !required.is_empty() {
+                schema.insert("required", required.to_value());
+            }
+        }
+        if self.null {
+            types.push("null");
+        }
+
+        let kind = match types.as_slice() {
+            [kind] => kind.to_value(),
+            types => types.to_vec().to_value(),
+        };
+        schema.insert("type", kind);
+        Value::Object(Object::from(schema))
+    }
+}
+
+/// Returns a bound of a numeric range, as an integer when every number seen was one.
+fn bound(value: f64, integer: bool) -> Value {
+    if integer && value >= i64::MIN as f64 && value <= i64::MAX as f64 {
+        (value as i64).to_value()
+    } else {
+        value.to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_infer() {
+        let samples = [
+            json(
+                r#"{"id": 1, "email": "a@x.com", "created": "2024-01-02T03:04:05Z", "tags": ["a"], "score": 2}"#,
+            ),
+            json(
+                r#"{"id": 7, "email": null, "created": "2024-02-03T00:00:00+01:00", "tags": [], "score": 0.5}"#,
+            ),
+            json(
+                r#"{"id": 3, "email": "b@y.org", "created": "2024-03-04T05:06:07Z", "score": -1, "ref": "123e4567-e89b-12d3-a456-426614174000"}"#,
+            ),
+        ];
+
+        let schema = SchemaInferrer::infer(&samples);
+        assert_value_eq!(
+            schema,
+            json(
+                r#"{
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "type": "object",
+                    "required": ["created", "email", "id", "score"],
+                    "properties": {
+                        "id": {"type": "integer", "minimum": 1, "maximum": 7},
+                        "email": {"type": ["string", "null"], "format": "email"},
+                        "created": {"type": "string", "format": "date-time"},
+                        "tags": {"type": "array", "items": {"type": "string"}},
+                        "score": {"type": "number", "minimum": -1, "maximum": 2},
+                        "ref": {"type": "string", "format": "uuid"}
+                    }
+                }"#
+            )
+        );
+
+        let validator = Schema::compile(&schema).unwrap();
+        for sample in &samples {
+            assert!(validator.is_valid(sample));
+        }
+    }
+
+    #[test]
+    fn test_unions_and_nesting() {
+        let mut inferrer = SchemaInferrer::new();
+        for sample in [
+            r#"[1, "a", {"k": true}]"#,
+            r#"[{"k": false, "extra": [1.5]}, null]"#,
+        ] {
+            inferrer.add(&json(sample));
+        }
+
+        assert_eq!(inferrer.samples(), 2);
+        assert_value_eq!(
+            inferrer.schema(),
+            json(
+                r#"{
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "type": "array",
+                    "items": {
+                        "type": ["integer", "string", "object", "null"],
+                        "minimum": 1,
+                        "maximum": 1,
+                        "properties": {
+                            "k": {"type": "boolean"},
+                            "extra": {"type": "array", "items": {"type": "number", "minimum": 1.5, "maximum": 1.5}}
+                        },
+                        "required": ["k"]
+                    }
+                }"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        let mut inferrer = SchemaInferrer::new().enums(3);
+        for sample in [
+            r#"{"status": "open", "name": "a", "size": "S"}"#,
+            r#"{"status": "closed", "name": "b", "size": "M"}"#,
+            r#"{"status": "open", "name": "c", "size": "L"}"#,
+            r#"{"status": null, "name": "d", "size": "XL"}"#,
+        ] {
+            inferrer.add(&json(sample));
+        }
+
+        let schema = inferrer.schema();
+        let properties = schema.get("properties").unwrap();
+        assert_value_eq!(
+            properties.get("status").unwrap().clone(),
+            json(r#"{"type": ["string", "null"], "enum": ["closed", "open", null]}"#)
+        );
+        assert_value_eq!(
+            properties.get("name").unwrap().clone(),
+            json(r#"{"type": "string"}"#)
+        );
+        assert_value_eq!(
+            properties.get("size").unwrap().clone(),
+            json(r#"{"type": "string"}"#)
+        );
+        assert!(SchemaInferrer::infer(&[json(r#"["a", "a"]"#)])
+            .get("items")
+            .unwrap()
+            .get("enum")
+            .is_none());
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_value_eq!(
+            SchemaInferrer::new().schema(),
+            json(r#"{"$schema": "https://json-schema.org/draft/2020-12/schema"}"#)
+        );
+        assert_value_eq!(
+            SchemaInferrer::infer(&[json("[]")]),
+            json(r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": "array"}"#)
+        );
+    }
+}
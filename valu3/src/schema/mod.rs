@@ -0,0 +1,445 @@
+//! JSON Schema (draft 2020-12) validation for `Value`.
+//!
+//! `Schema::compile` checks a schema document once and turns it into a form that validates
+//! other values quickly. `Schema::validate` reports every error it finds, each with the JSON
+//! Pointer of the offending part of the instance and of the keyword that rejected it.
+//!
+//! The validation vocabularies are supported: `type`, `enum`, `const`, the numeric, string,
+//! array and object constraints, the applicators (`allOf`, `anyOf`, `oneOf`, `not`,
+//! `if`/`then`/`else`, `prefixItems`, `items`, `contains`, `properties`,
+//! `patternProperties`, `additionalProperties`, `propertyNames`, `dependentSchemas`,
+//! `unevaluatedItems`, `unevaluatedProperties`) and `dependentRequired`. References may
+//! target the document itself (`#/$defs/a`), an `$anchor`, or a subschema identified by its
+//! `$id`; remote documents and `$dynamicRef` are not supported. The `date-time`, `date`,
+//! `email`, `uri`, `uuid`, `ipv4`, `ipv6` and `regex` formats are asserted, other formats are
+//! ignored. Patterns use the syntax of the `regex` crate, which lacks look-around and
+//! backreferences.
+//!
+//! Schemas for Rust types come from `ToSchema`, which `#[derive(ToSchema)]` implements for
+//! structs and enums, and the schema of data without a type comes from `SchemaInferrer`,
+//! which learns it from samples.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let schema = Schema::compile(&Value::json_to_value(r##"{
+//!     "type": "object",
+//!     "properties": {
+//!         "email": {"type": "string", "format": "email"},
+//!         "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+//!     },
+//!     "required": ["email"],
+//!     "$defs": {"tag": {"type": "string", "pattern": "^[a-z]+$"}}
+//! }"##).unwrap()).unwrap();
+//!
+//! let payload = Value::json_to_value(r#"{"tags": ["ok", "Not OK"]}"#).unwrap();
+//! for error in schema.validate(&payload).unwrap_err() {
+//!     println!("{} ({})", error, error.schema_path());
+//! }
+//! ```
+mod compile;
+pub(crate) mod format;
+mod infer;
+mod to_schema;
+mod validate;
+
+pub use infer::SchemaInferrer;
+pub(crate) use to_schema::TypeNames;
+pub use to_schema::{SchemaDefinitions, ToSchema, DRAFT_2020_12};
+
+use crate::prelude::*;
+use compile::{Compiler, Node};
+use std::fmt::{Display, Formatter};
+
+/// The errors raised while compiling a schema.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SchemaError {
+    /// The schema document is not a valid schema.
+    InvalidSchema(String),
+    /// A `$ref` does not point to a schema of the document.
+    UnresolvedReference(String),
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::InvalidSchema(msg) => write!(f, "invalid schema: {}", msg),
+            SchemaError::UnresolvedReference(msg) => write!(f, "unresolved reference: {}", msg),
+        }
+    }
+}
+
+impl From<SchemaError> for Error {
+    fn from(error: SchemaError) -> Self {
+        Error::Schema(error)
+    }
+}
+
+/// A compiled JSON Schema.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    nodes: Vec<Node>,
+    root: usize,
+}
+
+impl Schema {
+    /// Compiles a schema document, reporting the first problem found in it.
+    pub fn compile(schema: &Value) -> Result<Self, Error> {
+        let (nodes, root) = Compiler::new(schema).compile()?;
+        Ok(Self { nodes, root })
+    }
+
+    /// Validates a value, returning every error found.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let root = JsonPointer::root();
+        self.check(self.root, instance, &root, &root, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns `true` when the value is valid.
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.validate(instance).is_ok()
+    }
+}
+
+impl TryFrom<&Value> for Schema {
+    type Error = Error;
+
+    fn try_from(schema: &Value) -> Result<Self, Self::Error> {
+        Self::compile(schema)
+    }
+}
+
+/// A value that a schema rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    instance_path: JsonPointer,
+    schema_path: JsonPointer,
+    message: String,
+}
+
+impl ValidationError {
+    /// The location of the rejected value in the instance.
+    pub fn instance_path(&self) -> &JsonPointer {
+        &self.instance_path
+    }
+
+    /// The location of the keyword that rejected the value, following `$ref`s as they were
+    /// traversed.
+    pub fn schema_path(&self) -> &JsonPointer {
+        &self.schema_path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.instance_path.is_root() {
+            write!(f, "(root): {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn schema(schema: &str) -> Schema {
+        Schema::compile(&json(schema)).unwrap()
+    }
+
+    /// Returns the instance and schema paths of the errors.
+    fn errors(schema: &Schema, instance: &str) -> Vec<(String, String)> {
+        match schema.validate(&json(instance)) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|error| {
+                    (
+                        error.instance_path().to_string(),
+                        error.schema_path().to_string(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_keywords() {
+        let cases = [
+            (r#"{"type": "integer"}"#, "1.0", true),
+            (r#"{"type": "integer"}"#, "1.5", false),
+            (r#"{"type": ["string", "null"]}"#, "null", true),
+            (r#"{"type": "number"}"#, r#""1""#, false),
+            (r#"{"enum": [1, "a"]}"#, "1.0", true),
+            (r#"{"enum": [1, "a"]}"#, r#""b""#, false),
+            (r#"{"const": {"a": [1]}}"#, r#"{"a": [1]}"#, true),
+            (r#"{"multipleOf": 0.1}"#, "0.3", true),
+            (r#"{"multipleOf": 2}"#, "3", false),
+            (r#"{"maximum": 3}"#, "3", true),
+            (r#"{"exclusiveMaximum": 3}"#, "3", false),
+            (r#"{"minimum": -1}"#, "-2", false),
+            (r#"{"exclusiveMinimum": -1}"#, "-0.5", true),
+            (r#"{"minLength": 2}"#, r#""é""#, false),
+            (r#"{"maxLength": 2}"#, r#""éé""#, true),
+            (r#"{"pattern": "^a\\d+$"}"#, r#""a12""#, true),
+            (r#"{"pattern": "\\d"}"#, r#""abc""#, false),
+            (r#"{"format": "uuid"}"#, r#""not-a-uuid""#, false),
+            (r#"{"format": "unknown"}"#, r#""anything""#, true),
+            (r#"{"minItems": 1, "maxItems": 2}"#, "[1, 2, 3]", false),
+            (r#"{"uniqueItems": true}"#, "[1, 1.0]", false),
+            (r#"{"uniqueItems": true}"#, r#"[{"a": 1}, {"a": 2}]"#, true),
+            (
+                r#"{"prefixItems": [{"type": "string"}], "items": false}"#,
+                r#"["a"]"#,
+                true,
+            ),
+            (
+                r#"{"prefixItems": [{"type": "string"}], "items": false}"#,
+                r#"["a", 1]"#,
+                false,
+            ),
+            (r#"{"contains": {"type": "string"}}"#, "[1, 2]", false),
+            (
+                r#"{"contains": {"type": "string"}, "minContains": 2}"#,
+                r#"[1, "a", "b"]"#,
+                true,
+            ),
+            (
+                r#"{"contains": {"type": "string"}, "maxContains": 1}"#,
+                r#"["a", "b"]"#,
+                false,
+            ),
+            (
+                r#"{"contains": {"type": "string"}, "minContains": 0}"#,
+                "[]",
+                true,
+            ),
+            (r#"{"minProperties": 1}"#, "{}", false),
+            (r#"{"required": ["a"]}"#, r#"{"b": 1}"#, false),
+            (r#"{"required": ["a"]}"#, "[]", true),
+            (
+                r#"{"dependentRequired": {"a": ["b"]}}"#,
+                r#"{"a": 1}"#,
+                false,
+            ),
+            (
+                r#"{"dependentRequired": {"a": ["b"]}}"#,
+                r#"{"c": 1}"#,
+                true,
+            ),
+            (
+                r#"{"propertyNames": {"maxLength": 2}}"#,
+                r#"{"abc": 1}"#,
+                false,
+            ),
+            (
+                r#"{"patternProperties": {"^x-": {"type": "string"}}}"#,
+                r#"{"x-a": 1}"#,
+                false,
+            ),
+            (
+                r#"{"properties": {"a": true}, "additionalProperties": false}"#,
+                r#"{"a": 1}"#,
+                true,
+            ),
+            (
+                r#"{"properties": {"a": true}, "additionalProperties": false}"#,
+                r#"{"b": 1}"#,
+                false,
+            ),
+            (
+                r#"{"dependentSchemas": {"a": {"required": ["b"]}}}"#,
+                r#"{"a": 1}"#,
+                false,
+            ),
+            (r#"{"allOf": [{"minimum": 1}, {"maximum": 3}]}"#, "4", false),
+            (
+                r#"{"anyOf": [{"type": "string"}, {"minimum": 1}]}"#,
+                "0",
+                false,
+            ),
+            (r#"{"oneOf": [{"minimum": 1}, {"maximum": 3}]}"#, "2", false),
+            (r#"{"oneOf": [{"minimum": 1}, {"maximum": 3}]}"#, "5", true),
+            (r#"{"not": {"type": "null"}}"#, "null", false),
+            (
+                r#"{"if": {"minimum": 10}, "then": {"multipleOf": 10}, "else": {"maximum": 5}}"#,
+                "20",
+                true,
+            ),
+            (
+                r#"{"if": {"minimum": 10}, "then": {"multipleOf": 10}, "else": {"maximum": 5}}"#,
+                "15",
+                false,
+            ),
+            (
+                r#"{"if": {"minimum": 10}, "then": {"multipleOf": 10}, "else": {"maximum": 5}}"#,
+                "7",
+                false,
+            ),
+            ("true", "1", true),
+            ("false", "1", false),
+        ];
+
+        for (source, instance, expected) in cases {
+            assert_eq!(
+                schema(source).is_valid(&json(instance)),
+                expected,
+                "{} against {}",
+                instance,
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_reports_every_error() {
+        let schema = schema(
+            r##"{
+                "type": "object",
+                "properties": {
+                    "email": {"type": "string", "format": "email"},
+                    "age": {"type": "integer", "minimum": 0},
+                    "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+                },
+                "required": ["email", "name"],
+                "$defs": {"tag": {"type": "string", "pattern": "^[a-z]+$"}}
+            }"##,
+        );
+
+        let report = errors(
+            &schema,
+            r#"{"email": "nobody", "age": -1, "tags": ["ok", "Not OK", 3]}"#,
+        );
+        assert_eq!(
+            report,
+            vec![
+                ("".to_string(), "/required".to_string()),
+                ("/age".to_string(), "/properties/age/minimum".to_string()),
+                ("/email".to_string(), "/properties/email/format".to_string()),
+                (
+                    "/tags/1".to_string(),
+                    "/properties/tags/items/$ref/pattern".to_string()
+                ),
+                (
+                    "/tags/2".to_string(),
+                    "/properties/tags/items/$ref/type".to_string()
+                ),
+            ]
+        );
+
+        let error = &schema.validate(&json(r#"{"email": 1}"#)).unwrap_err()[0];
+        assert_eq!(error.to_string(), "(root): \"name\" is a required property");
+        let error = &schema
+            .validate(&json(r#"{"email": 1, "name": "a"}"#))
+            .unwrap_err()[0];
+        assert_eq!(error.to_string(), "/email: 1 is not of type \"string\"");
+    }
+
+    #[test]
+    fn test_references() {
+        let tree = schema(
+            r##"{
+                "$id": "https://example.com/tree",
+                "type": "object",
+                "properties": {
+                    "value": {"$ref": "#number"},
+                    "children": {"type": "array", "items": {"$ref": "#"}},
+                    "meta": {"$ref": "meta"}
+                },
+                "$defs": {
+                    "number": {"$anchor": "number", "type": "number"},
+                    "meta": {"$id": "meta", "type": "string"}
+                }
+            }"##,
+        );
+
+        assert!(tree.is_valid(&json(
+            r#"{"value": 1, "children": [{"value": 2, "children": []}], "meta": "m"}"#
+        )));
+        assert_eq!(
+            errors(&tree, r#"{"children": [{"value": "a"}]}"#),
+            vec![(
+                "/children/0/value".to_string(),
+                "/properties/children/items/$ref/properties/value/$ref/type".to_string()
+            )]
+        );
+        assert!(!tree.is_valid(&json(r#"{"meta": 1}"#)));
+
+        let loose =
+            schema(r##"{"$ref": "#/components/id", "components": {"id": {"type": "integer"}}}"##);
+        assert!(loose.is_valid(&json("1")));
+        assert!(!loose.is_valid(&json("1.5")));
+
+        let escaped = schema(r##"{"$ref": "#/$defs/a~1b%20c", "$defs": {"a/b c": {"const": 1}}}"##);
+        assert!(escaped.is_valid(&json("1")));
+    }
+
+    #[test]
+    fn test_unevaluated() {
+        let closed = schema(
+            r#"{
+                "allOf": [{"properties": {"a": true}}],
+                "properties": {"b": true},
+                "unevaluatedProperties": false
+            }"#,
+        );
+        assert!(closed.is_valid(&json(r#"{"a": 1, "b": 2}"#)));
+        assert_eq!(
+            errors(&closed, r#"{"a": 1, "c": 3}"#),
+            vec![("/c".to_string(), "/unevaluatedProperties".to_string())]
+        );
+
+        let tuple = schema(
+            r#"{"prefixItems": [{"type": "string"}], "contains": {"type": "number"}, "unevaluatedItems": false}"#,
+        );
+        assert!(tuple.is_valid(&json(r#"["a", 1, 2]"#)));
+        assert!(!tuple.is_valid(&json(r#"["a", 1, true]"#)));
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        let cases = [
+            (r#"{"type": "text"}"#, "invalid schema: \"text\" is not a type at #/type"),
+            (r#"{"minimum": "1"}"#, "invalid schema: \"minimum\" must be a number at #/minimum"),
+            (
+                r#"{"properties": {"a": {"minLength": -1}}}"#,
+                "invalid schema: \"minLength\" must be a non-negative integer at #/properties/a/minLength",
+            ),
+            (r#"{"items": 1}"#, "invalid schema: a schema must be an object or a boolean at #/items"),
+            (r##"{"$ref": "#/$defs/missing"}"##, "unresolved reference: \"#/$defs/missing\" at #/$ref"),
+            (r#"{"$ref": "https://elsewhere.com/schema"}"#, "unresolved reference: \"https://elsewhere.com/schema\" at #/$ref"),
+            (r##"{"$ref": "#"}"##, "invalid schema: a reference cycle does not descend into the value at #/$ref"),
+            (
+                r##"{"$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"allOf": [{"$ref": "#/$defs/a"}]}}}"##,
+                "invalid schema: a reference cycle does not descend into the value at #/$defs/b/allOf/0/$ref",
+            ),
+        ];
+
+        for (source, expected) in cases {
+            match Schema::compile(&json(source)) {
+                Err(Error::Schema(error)) => assert_eq!(error.to_string(), expected),
+                other => panic!("{} compiled to {:?}", source, other),
+            }
+        }
+
+        assert!(matches!(
+            Schema::compile(&json(r#"{"pattern": "("}"#)),
+            Err(Error::Schema(SchemaError::InvalidSchema(_)))
+        ));
+    }
+}
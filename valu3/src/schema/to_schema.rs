@@ -0,0 +1,538 @@
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+/// The URI of the JSON Schema dialect the generated documents use.
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Types that can describe their `Value` representation with a JSON Schema.
+///
+/// Derived types are added to the `$defs` of the document under their name and referred to
+/// with `$ref`, so that nested and recursive types compose.
+///
+/// # Examples
+///
+/// ```no_run
+/// use valu3::prelude::*;
+///
+/// #[derive(ToValue, FromValue, ToSchema)]
+/// struct User {
+///     id: u32,
+///     tags: Vec<String>,
+///     manager: Option<Box<User>>,
+/// }
+///
+/// let schema = Schema::compile(&User::schema()).unwrap();
+/// ```
+pub trait ToSchema {
+    /// Returns the schema of the type, adding the definitions it refers to.
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value;
+
+    /// Returns `true` when the type may be left out of an object, as `Option` may.
+    fn is_optional() -> bool {
+        false
+    }
+
+    /// Returns a standalone schema document for the type.
+    fn schema() -> Value {
+        let mut definitions = SchemaDefinitions::new();
+        let schema = Self::schema_with(&mut definitions);
+        definitions.into_document(schema)
+    }
+}
+
+/// The named schemas a document refers to, which become its `$defs`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDefinitions {
+    definitions: BTreeMap<String, Value>,
+    types: TypeNames,
+}
+
+impl SchemaDefinitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the name of a type without module paths, as `short_type_name` does.
+    pub fn name_of<T: ?Sized>() -> String {
+        short_type_name::<T>()
+    }
+
+    /// Returns the name a type is defined under: `name_of` the type, followed by a number,
+    /// as in `User2`, when a different type of the same name from another module took it.
+    pub fn name_for<T: ?Sized>(&mut self) -> String {
+        let definitions = &self.definitions;
+        self.types
+            .name_for::<T>(Self::name_of::<T>(), |name| definitions.contains_key(name))
+    }
+
+    /// Defines a schema under a name, unless it already is, and returns a reference to it.
+    /// While `schema` runs the name refers to a placeholder, so that recursive types end.
+    pub fn define<F>(&mut self, name: &str, schema: F) -> Value
+    where
+        F: FnOnce(&mut Self) -> Value,
+    {
+        if !self.definitions.contains_key(name) {
+            self.definitions
+                .insert(name.to_string(), Value::Boolean(true));
+            let schema = schema(self);
+            self.definitions.insert(name.to_string(), schema);
+        }
+        object(vec![("$ref", reference(name).to_value())])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.definitions.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Builds a schema document around a schema. A root that only refers to a definition is
+    /// replaced by the definition, which stays in `$defs` only when something refers to it.
+    pub fn into_document(mut self, schema: Value) -> Value {
+        let schema = match referenced_name(&schema) {
+            Some(name) => {
+                let definition = self.definitions.get(&name).cloned().unwrap_or(schema);
+                let target = reference(&name);
+                let referenced = self.definitions.values().any(|definition| {
+                    definition.fold(false, |found, path, value| {
+                        found
+                            || (path.last() == Some(&ValueKey::from("$ref"))
+                                && *value == target.to_value())
+                    })
+                });
+                if !referenced {
+                    self.definitions.remove(&name);
+                }
+                definition
+            }
+            None => schema,
+        };
+
+        let members = match &schema {
+            Value::Object(object) => object,
+            _ => return schema,
+        };
+        let mut document = BTreeMap::new();
+        document.insert("$schema".to_string(), DRAFT_2020_12.to_value());
+        for (key, value) in members.iter() {
+            document.insert(key.to_string(), value.clone());
+        }
+        if !self.definitions.is_empty() {
+            document.insert(
+                "$defs".to_string(),
+                Value::Object(Object::from(self.definitions)),
+            );
+        }
+        Value::Object(Object::from(document))
+    }
+}
+
+/// The names given to types by the full path of the type, so that types of the same name from
+/// different modules get different names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TypeNames(BTreeMap<&'static str, String>);
+
+impl TypeNames {
+    /// Returns the name given to a type, or gives it `name`, or the first of `name2`,
+    /// `name3`... that is neither `taken` nor given to another type.
+    pub(crate) fn name_for<T: ?Sized>(
+        &mut self,
+        name: String,
+        taken: impl Fn(&str) -> bool,
+    ) -> String {
+        let path = std::any::type_name::<T>();
+        if let Some(name) = self.0.get(path) {
+            return name.clone();
+        }
+
+        let given = &self.0;
+        let taken = |name: &str| taken(name) || given.values().any(|given| given == name);
+        let name = if taken(&name) {
+            (2..)
+                .map(|number| format!("{}{}", name, number))
+                .find(|name| !taken(name))
+                .unwrap()
+        } else {
+            name
+        };
+        self.0.insert(path, name.clone());
+        name
+    }
+}
+
+fn reference(name: &str) -> String {
+    format!("#{}", JsonPointer::root().join("$defs").join(name))
+}
+
+/// Returns the name of the definition a schema only refers to.
+fn referenced_name(schema: &Value) -> Option<String> {
+    let object = match schema {
+        Value::Object(object) if object.len() == 1 => object,
+        _ => return None,
+    };
+    let target = match object.get("$ref")? {
+        Value::String(target) => target.as_str(),
+        _ => return None,
+    };
+    let pointer = JsonPointer::parse(target.strip_prefix('#')?).ok()?;
+    match pointer.tokens() {
+        [defs, name] if defs == "$defs" => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(Object::from(
+        members.into_iter().collect::<BTreeMap<_, _>>(),
+    ))
+}
+
+fn typed(name: &str) -> Value {
+    object(vec![("type", name.to_value())])
+}
+
+/// Makes a schema accept `null` as well, extending its `type` and `enum` when it has them.
+fn nullable(schema: Value) -> Value {
+    let mut members = match schema {
+        Value::Object(object) if object.get("$ref").is_none() => object,
+        Value::Boolean(true) => return Value::Boolean(true),
+        schema => return object(vec![("anyOf", vec![schema, typed("null")].to_value())]),
+    };
+
+    let kind = match members.get("type") {
+        Some(Value::String(kind)) => kind.as_string(),
+        _ => {
+            return object(vec![(
+                "anyOf",
+                vec![Value::Object(members), typed("null")].to_value(),
+            )])
+        }
+    };
+    if kind != "null" {
+        members.insert("type", vec![kind.to_value(), "null".to_value()].to_value());
+    }
+    if let Some(Value::Array(values)) = members.get_mut("enum") {
+        values.push(Value::Null);
+    }
+    Value::Object(members)
+}
+
+macro_rules! integer_schema {
+    ($($ty:ty => $format:literal),* $(,)?) => {
+        $(
+            impl ToSchema for $ty {
+                fn schema_with(_: &mut SchemaDefinitions) -> Value {
+                    object(vec![
+                        ("type", "integer".to_value()),
+                        ("format", $format.to_value()),
+                        ("minimum", <$ty>::MIN.to_value()),
+                        ("maximum", <$ty>::MAX.to_value()),
+                    ])
+                }
+            }
+        )*
+    };
+}
+
+integer_schema!(
+    u8 => "uint8",
+    u16 => "uint16",
+    u32 => "uint32",
+    u64 => "uint64",
+    u128 => "uint128",
+    usize => "uint64",
+    i8 => "int8",
+    i16 => "int16",
+    i32 => "int32",
+    i64 => "int64",
+    i128 => "int128",
+    isize => "int64",
+);
+
+impl ToSchema for f32 {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        object(vec![
+            ("type", "number".to_value()),
+            ("format", "float".to_value()),
+            ("minimum", (f32::MIN as f64).to_value()),
+            ("maximum", (f32::MAX as f64).to_value()),
+        ])
+    }
+}
+
+impl ToSchema for f64 {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        object(vec![
+            ("type", "number".to_value()),
+            ("format", "double".to_value()),
+        ])
+    }
+}
+
+impl ToSchema for Number {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("number")
+    }
+}
+
+impl ToSchema for bool {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("boolean")
+    }
+}
+
+impl ToSchema for String {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("string")
+    }
+}
+
+impl ToSchema for &str {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("string")
+    }
+}
+
+impl ToSchema for StringB {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("string")
+    }
+}
+
+impl ToSchema for char {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        object(vec![
+            ("type", "string".to_value()),
+            ("minLength", 1.to_value()),
+            ("maxLength", 1.to_value()),
+        ])
+    }
+}
+
+impl ToSchema for DateTime {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        object(vec![
+            ("type", "string".to_value()),
+            ("format", "date-time".to_value()),
+        ])
+    }
+}
+
+impl ToSchema for Value {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        Value::Boolean(true)
+    }
+}
+
+impl ToSchema for Object {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("object")
+    }
+}
+
+impl ToSchema for Array {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("array")
+    }
+}
+
+impl ToSchema for () {
+    fn schema_with(_: &mut SchemaDefinitions) -> Value {
+        typed("null")
+    }
+}
+
+impl<T: ToSchema> ToSchema for Option<T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        nullable(T::schema_with(definitions))
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl<T: ToSchema + ?Sized> ToSchema for Box<T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        T::schema_with(definitions)
+    }
+
+    fn is_optional() -> bool {
+        T::is_optional()
+    }
+}
+
+fn array<T: ToSchema>(definitions: &mut SchemaDefinitions, unique: bool) -> Value {
+    let mut members = vec![
+        ("type", "array".to_value()),
+        ("items", T::schema_with(definitions)),
+    ];
+    if unique {
+        members.push(("uniqueItems", true.to_value()));
+    }
+    object(members)
+}
+
+impl<T: ToSchema> ToSchema for Vec<T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        array::<T>(definitions, false)
+    }
+}
+
+impl<T: ToSchema> ToSchema for VecDeque<T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        array::<T>(definitions, false)
+    }
+}
+
+impl<T: ToSchema, S> ToSchema for HashSet<T, S> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        array::<T>(definitions, true)
+    }
+}
+
+impl<T: ToSchema> ToSchema for BTreeSet<T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        array::<T>(definitions, true)
+    }
+}
+
+impl<T: ToSchema, const N: usize> ToSchema for [T; N] {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        object(vec![
+            ("type", "array".to_value()),
+            ("items", T::schema_with(definitions)),
+            ("minItems", N.to_value()),
+            ("maxItems", N.to_value()),
+        ])
+    }
+}
+
+fn map<T: ToSchema>(definitions: &mut SchemaDefinitions) -> Value {
+    object(vec![
+        ("type", "object".to_value()),
+        ("additionalProperties", T::schema_with(definitions)),
+    ])
+}
+
+impl<T: ToSchema, S> ToSchema for HashMap<String, T, S> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        map::<T>(definitions)
+    }
+}
+
+impl<T: ToSchema> ToSchema for BTreeMap<String, T> {
+    fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+        map::<T>(definitions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    struct Node;
+
+    impl ToSchema for Node {
+        fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+            definitions.define("Node", |definitions| {
+                Value::from(vec![(
+                    "properties",
+                    Value::from(vec![("children", Vec::<Node>::schema_with(definitions))]),
+                )])
+            })
+        }
+    }
+
+    #[test]
+    fn test_std_types() {
+        assert_value_eq!(
+            <u8 as ToSchema>::schema(),
+            json(
+                r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": "integer", "format": "uint8", "minimum": 0, "maximum": 255}"#
+            )
+        );
+        assert_value_eq!(
+            <Option<Vec<String>> as ToSchema>::schema_with(&mut SchemaDefinitions::new()),
+            json(r#"{"type": ["array", "null"], "items": {"type": "string"}}"#)
+        );
+        assert_value_eq!(
+            <HashMap<String, Option<bool>> as ToSchema>::schema_with(&mut SchemaDefinitions::new()),
+            json(r#"{"type": "object", "additionalProperties": {"type": ["boolean", "null"]}}"#)
+        );
+        assert_value_eq!(
+            <HashSet<char> as ToSchema>::schema_with(&mut SchemaDefinitions::new()),
+            json(
+                r#"{"type": "array", "uniqueItems": true, "items": {"type": "string", "minLength": 1, "maxLength": 1}}"#
+            )
+        );
+        assert!(<Option<i8> as ToSchema>::is_optional());
+        assert!(!<Vec<i8> as ToSchema>::is_optional());
+    }
+
+    #[test]
+    fn test_ranges_validate() {
+        let schema = Schema::compile(&<BTreeMap<String, i8> as ToSchema>::schema()).unwrap();
+        assert!(schema.is_valid(&json(r#"{"a": -128, "b": 127}"#)));
+        assert!(!schema.is_valid(&json(r#"{"a": 128}"#)));
+        assert!(!schema.is_valid(&json(r#"{"a": 1.5}"#)));
+    }
+
+    #[test]
+    fn test_recursive_definitions() {
+        let document = Node::schema();
+        assert_value_eq!(
+            document,
+            json(
+                r##"{
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/Node"}}},
+                    "$defs": {"Node": {"properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/Node"}}}}}
+                }"##
+            )
+        );
+
+        let schema = Schema::compile(&document).unwrap();
+        assert!(schema.is_valid(&json(r#"{"children": [{"children": []}]}"#)));
+        assert!(!schema.is_valid(&json(r#"{"children": [{"children": 1}]}"#)));
+
+        let mut definitions = SchemaDefinitions::new();
+        let nullable = <Option<Node> as ToSchema>::schema_with(&mut definitions);
+        assert_value_eq!(
+            nullable,
+            json(r##"{"anyOf": [{"$ref": "#/$defs/Node"}, {"type": "null"}]}"##)
+        );
+        assert_eq!(definitions.len(), 1);
+    }
+
+    #[test]
+    fn test_name_of() {
+        assert_eq!(SchemaDefinitions::name_of::<Node>(), "Node");
+        assert_eq!(
+            SchemaDefinitions::name_of::<HashMap<String, Vec<Node>>>(),
+            "HashMap<String, Vec<Node>>"
+        );
+    }
+
+    mod other {
+        pub struct Node;
+    }
+
+    #[test]
+    fn test_name_for() {
+        let mut definitions = SchemaDefinitions::new();
+        assert_eq!(definitions.name_for::<Node>(), "Node");
+        assert_eq!(definitions.name_for::<other::Node>(), "Node2");
+        assert_eq!(definitions.name_for::<Node>(), "Node");
+        assert_eq!(definitions.name_for::<Vec<other::Node>>(), "Vec<Node>");
+    }
+}
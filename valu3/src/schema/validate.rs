@@ -0,0 +1,545 @@
+use super::compile::{JsonType, Keyword, Node};
+use super::{Schema, ValidationError};
+use crate::patch::equivalent;
+use crate::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// The object members and array elements a schema evaluated, which `unevaluatedProperties`
+/// and `unevaluatedItems` leave out.
+#[derive(Debug, Default)]
+pub(super) struct Evaluated {
+    properties: HashSet<String>,
+    items: HashSet<usize>,
+}
+
+impl Evaluated {
+    fn extend(&mut self, other: Evaluated) {
+        self.properties.extend(other.properties);
+        self.items.extend(other.items);
+    }
+}
+
+impl Schema {
+    /// Validates a value against a node, pushing the errors found. The value is valid when no
+    /// error was pushed.
+    pub(super) fn check(
+        &self,
+        node: usize,
+        value: &Value,
+        instance: &JsonPointer,
+        schema: &JsonPointer,
+        errors: &mut Vec<ValidationError>,
+    ) -> Evaluated {
+        let mut evaluated = Evaluated::default();
+        let keywords = match &self.nodes[node] {
+            Node::Bool(true) => return evaluated,
+            Node::Bool(false) => {
+                errors.push(error(
+                    instance,
+                    schema,
+                    format!("false schema does not allow {}", json(value)),
+                ));
+                return evaluated;
+            }
+            Node::Keywords(keywords) => keywords,
+        };
+
+        for keyword in keywords {
+            let at = schema.join(keyword.name());
+            let fail = |errors: &mut Vec<ValidationError>, message: String| {
+                errors.push(error(instance, &at, message))
+            };
+
+            match keyword {
+                Keyword::Type(types) => {
+                    if !types.iter().any(|kind| is_type(value, *kind)) {
+                        let names = types
+                            .iter()
+                            .map(|kind| format!("\"{}\"", kind.name()))
+                            .collect::<Vec<_>>();
+                        fail(
+                            errors,
+                            format!("{} is not of type {}", json(value), names.join(", ")),
+                        );
+                    }
+                }
+                Keyword::Enum(values) => {
+                    if !values.iter().any(|option| equivalent(value, option)) {
+                        let options = Value::Array(Array::from(values.clone()));
+                        fail(
+                            errors,
+                            format!("{} is not one of {}", json(value), json(&options)),
+                        );
+                    }
+                }
+                Keyword::Const(constant) => {
+                    if !equivalent(value, constant) {
+                        fail(
+                            errors,
+                            format!("{} is not equal to {}", json(value), json(constant)),
+                        );
+                    }
+                }
+                Keyword::MultipleOf(divisor) => {
+                    if let Some(number) = number(value) {
+                        let quotient = number / divisor;
+                        if !quotient.is_finite() || (quotient - quotient.round()).abs() > 1e-9 {
+                            fail(
+                                errors,
+                                format!("{} is not a multiple of {}", json(value), divisor),
+                            );
+                        }
+                    }
+                }
+                Keyword::Maximum(maximum) => {
+                    if number(value).is_some_and(|number| number > *maximum) {
+                        fail(
+                            errors,
+                            format!("{} is greater than the maximum of {}", json(value), maximum),
+                        );
+                    }
+                }
+                Keyword::ExclusiveMaximum(maximum) => {
+                    if number(value).is_some_and(|number| number >= *maximum) {
+                        fail(
+                            errors,
+                            format!(
+                                "{} is greater than or equal to the exclusive maximum of {}",
+                                json(value),
+                                maximum
+                            ),
+                        );
+                    }
+                }
+                Keyword::Minimum(minimum) => {
+                    if number(value).is_some_and(|number| number < *minimum) {
+                        fail(
+                            errors,
+                            format!("{} is less than the minimum of {}", json(value), minimum),
+                        );
+                    }
+                }
+                Keyword::ExclusiveMinimum(minimum) => {
+                    if number(value).is_some_and(|number| number <= *minimum) {
+                        fail(
+                            errors,
+                            format!(
+                                "{} is less than or equal to the exclusive minimum of {}",
+                                json(value),
+                                minimum
+                            ),
+                        );
+                    }
+                }
+                Keyword::MaxLength(length) => {
+                    if text(value).is_some_and(|text| text.chars().count() > *length) {
+                        fail(
+                            errors,
+                            format!("{} is longer than {} characters", json(value), length),
+                        );
+                    }
+                }
+                Keyword::MinLength(length) => {
+                    if text(value).is_some_and(|text| text.chars().count() < *length) {
+                        fail(
+                            errors,
+                            format!("{} is shorter than {} characters", json(value), length),
+                        );
+                    }
+                }
+                Keyword::Pattern(regex) => {
+                    if text(value).is_some_and(|text| !regex.is_match(&text)) {
+                        fail(
+                            errors,
+                            format!("{} does not match \"{}\"", json(value), regex.as_str()),
+                        );
+                    }
+                }
+                Keyword::Format(format) => {
+                    if text(value).is_some_and(|text| !format.is_valid(&text)) {
+                        fail(
+                            errors,
+                            format!("{} is not a valid \"{}\"", json(value), format.name()),
+                        );
+                    }
+                }
+                Keyword::MaxItems(count) => {
+                    if items(value).is_some_and(|items| items.len() > *count) {
+                        fail(
+                            errors,
+                            format!("{} has more than {} items", json(value), count),
+                        );
+                    }
+                }
+                Keyword::MinItems(count) => {
+                    if items(value).is_some_and(|items| items.len() < *count) {
+                        fail(
+                            errors,
+                            format!("{} has fewer than {} items", json(value), count),
+                        );
+                    }
+                }
+                Keyword::UniqueItems => {
+                    if let Some(items) = items(value) {
+                        let duplicated = items.iter().enumerate().any(|(index, item)| {
+                            items[index + 1..]
+                                .iter()
+                                .any(|other| equivalent(item, other))
+                        });
+                        if duplicated {
+                            fail(errors, format!("{} has non-unique items", json(value)));
+                        }
+                    }
+                }
+                Keyword::PrefixItems(schemas) => {
+                    if let Some(items) = items(value) {
+                        for (index, (schema, item)) in schemas.iter().zip(items).enumerate() {
+                            self.check(
+                                *schema,
+                                item,
+                                &instance.join(index),
+                                &at.join(index),
+                                errors,
+                            );
+                            evaluated.items.insert(index);
+                        }
+                    }
+                }
+                Keyword::Items { schema, skip } => {
+                    if let Some(items) = items(value) {
+                        for (index, item) in items.iter().enumerate().skip(*skip) {
+                            self.check(*schema, item, &instance.join(index), &at, errors);
+                            evaluated.items.insert(index);
+                        }
+                    }
+                }
+                Keyword::Contains {
+                    schema: contains,
+                    min,
+                    max,
+                } => {
+                    if let Some(items) = items(value) {
+                        let mut matched = 0;
+                        for (index, item) in items.iter().enumerate() {
+                            if self.is_valid_at(*contains, item, &instance.join(index), &at) {
+                                matched += 1;
+                                evaluated.items.insert(index);
+                            }
+                        }
+
+                        if matched == 0 && *min > 0 {
+                            fail(
+                                errors,
+                                format!(
+                                    "{} does not contain items matching the \"contains\" schema",
+                                    json(value)
+                                ),
+                            );
+                        } else if matched < *min {
+                            errors.push(error(
+                                instance,
+                                &schema.join("minContains"),
+                                format!(
+                                    "{} contains fewer than {} matching items",
+                                    json(value),
+                                    min
+                                ),
+                            ));
+                        } else if max.is_some_and(|max| matched > max) {
+                            errors.push(error(
+                                instance,
+                                &schema.join("maxContains"),
+                                format!(
+                                    "{} contains more than {} matching items",
+                                    json(value),
+                                    max.unwrap_or_default()
+                                ),
+                            ));
+                        }
+                    }
+                }
+                Keyword::MaxProperties(count) => {
+                    if let Value::Object(object) = value {
+                        if object.len() > *count {
+                            fail(
+                                errors,
+                                format!("{} has more than {} properties", json(value), count),
+                            );
+                        }
+                    }
+                }
+                Keyword::MinProperties(count) => {
+                    if let Value::Object(object) = value {
+                        if object.len() < *count {
+                            fail(
+                                errors,
+                                format!("{} has fewer than {} properties", json(value), count),
+                            );
+                        }
+                    }
+                }
+                Keyword::Required(names) => {
+                    if let Value::Object(object) = value {
+                        for name in names {
+                            if object.get(name.as_str()).is_none() {
+                                fail(errors, format!("\"{}\" is a required property", name));
+                            }
+                        }
+                    }
+                }
+                Keyword::DependentRequired(requirements) => {
+                    if let Value::Object(object) = value {
+                        for (name, required) in requirements {
+                            if object.get(name.as_str()).is_none() {
+                                continue;
+                            }
+                            for other in required {
+                                if object.get(other.as_str()).is_none() {
+                                    errors.push(error(
+                                        instance,
+                                        &at.join(name),
+                                        format!(
+                                            "\"{}\" is required when \"{}\" is present",
+                                            other, name
+                                        ),
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                Keyword::Properties(properties) => {
+                    if let Value::Object(object) = value {
+                        for (name, schema) in properties {
+                            if let Some(member) = object.get(name.as_str()) {
+                                self.check(
+                                    *schema,
+                                    member,
+                                    &instance.join(name),
+                                    &at.join(name),
+                                    errors,
+                                );
+                                evaluated.properties.insert(name.clone());
+                            }
+                        }
+                    }
+                }
+                Keyword::PatternProperties(patterns) => {
+                    for (name, member) in members(value) {
+                        for (regex, schema) in patterns {
+                            if regex.is_match(&name) {
+                                self.check(
+                                    *schema,
+                                    member,
+                                    &instance.join(&name),
+                                    &at.join(regex.as_str()),
+                                    errors,
+                                );
+                                evaluated.properties.insert(name.clone());
+                            }
+                        }
+                    }
+                }
+                Keyword::AdditionalProperties {
+                    schema,
+                    properties,
+                    patterns,
+                } => {
+                    for (name, member) in members(value) {
+                        if properties.contains(&name)
+                            || patterns.iter().any(|regex| regex.is_match(&name))
+                        {
+                            continue;
+                        }
+                        self.check(*schema, member, &instance.join(&name), &at, errors);
+                        evaluated.properties.insert(name);
+                    }
+                }
+                Keyword::PropertyNames(schema) => {
+                    for (name, _) in members(value) {
+                        self.check(*schema, &name.to_value(), instance, &at, errors);
+                    }
+                }
+                Keyword::DependentSchemas(schemas) => {
+                    if let Value::Object(object) = value {
+                        for (name, schema) in schemas {
+                            if object.get(name.as_str()).is_some() {
+                                let inner =
+                                    self.check(*schema, value, instance, &at.join(name), errors);
+                                evaluated.extend(inner);
+                            }
+                        }
+                    }
+                }
+                Keyword::Ref(target) => {
+                    let inner = self.check(*target, value, instance, &at, errors);
+                    evaluated.extend(inner);
+                }
+                Keyword::AllOf(schemas) => {
+                    for (index, schema) in schemas.iter().enumerate() {
+                        let inner = self.check(*schema, value, instance, &at.join(index), errors);
+                        evaluated.extend(inner);
+                    }
+                }
+                Keyword::AnyOf(schemas) | Keyword::OneOf(schemas) => {
+                    let mut valid = 0;
+                    for (index, schema) in schemas.iter().enumerate() {
+                        let mut scratch = Vec::new();
+                        let inner =
+                            self.check(*schema, value, instance, &at.join(index), &mut scratch);
+                        if scratch.is_empty() {
+                            valid += 1;
+                            evaluated.extend(inner);
+                        }
+                    }
+
+                    if valid == 0 {
+                        fail(
+                            errors,
+                            format!(
+                                "{} is not valid under any of the schemas in \"{}\"",
+                                json(value),
+                                keyword.name()
+                            ),
+                        );
+                    } else if valid > 1 && matches!(keyword, Keyword::OneOf(_)) {
+                        fail(
+                            errors,
+                            format!(
+                                "{} is valid under more than one of the schemas in \"oneOf\"",
+                                json(value)
+                            ),
+                        );
+                    }
+                }
+                Keyword::Not(schema) => {
+                    if self.is_valid_at(*schema, value, instance, &at) {
+                        fail(
+                            errors,
+                            format!(
+                                "{} should not be valid under the schema in \"not\"",
+                                json(value)
+                            ),
+                        );
+                    }
+                }
+                Keyword::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let mut scratch = Vec::new();
+                    let inner = self.check(*condition, value, instance, &at, &mut scratch);
+                    let branch = if scratch.is_empty() {
+                        evaluated.extend(inner);
+                        then.map(|then| (then, "then"))
+                    } else {
+                        otherwise.map(|otherwise| (otherwise, "else"))
+                    };
+                    if let Some((branch, name)) = branch {
+                        let inner = self.check(branch, value, instance, &schema.join(name), errors);
+                        evaluated.extend(inner);
+                    }
+                }
+                Keyword::UnevaluatedItems(schema) => {
+                    if let Some(items) = items(value) {
+                        for (index, item) in items.iter().enumerate() {
+                            if !evaluated.items.contains(&index) {
+                                self.check(*schema, item, &instance.join(index), &at, errors);
+                            }
+                        }
+                        evaluated.items.extend(0..items.len());
+                    }
+                }
+                Keyword::UnevaluatedProperties(schema) => {
+                    for (name, member) in members(value) {
+                        if !evaluated.properties.contains(&name) {
+                            self.check(*schema, member, &instance.join(&name), &at, errors);
+                            evaluated.properties.insert(name);
+                        }
+                    }
+                }
+            }
+        }
+
+        evaluated
+    }
+
+    fn is_valid_at(
+        &self,
+        node: usize,
+        value: &Value,
+        instance: &JsonPointer,
+        schema: &JsonPointer,
+    ) -> bool {
+        let mut scratch = Vec::new();
+        self.check(node, value, instance, schema, &mut scratch);
+        scratch.is_empty()
+    }
+}
+
+fn error(instance: &JsonPointer, schema: &JsonPointer, message: String) -> ValidationError {
+    ValidationError {
+        instance_path: instance.clone(),
+        schema_path: schema.clone(),
+        message,
+    }
+}
+
+fn json(value: &Value) -> String {
+    value.to_json(JsonMode::Inline)
+}
+
+/// Returns whether the value is of a JSON type. Dates are strings, and integers are the
+/// numbers without a fractional part, such as `1.0`.
+fn is_type(value: &Value, kind: JsonType) -> bool {
+    match (kind, value) {
+        (JsonType::Null, Value::Null)
+        | (JsonType::Boolean, Value::Boolean(_))
+        | (JsonType::Object, Value::Object(_))
+        | (JsonType::Array, Value::Array(_))
+        | (JsonType::Number, Value::Number(_))
+        | (JsonType::String, Value::String(_))
+        | (JsonType::String, Value::DateTime(_)) => true,
+        (JsonType::Integer, Value::Number(number)) => {
+            number.is_integer() || number.to_f64().is_some_and(|number| number.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.to_f64(),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(string) => Some(Cow::Borrowed(string.as_str())),
+        Value::DateTime(date_time) => Some(Cow::Owned(date_time.to_string())),
+        _ => None,
+    }
+}
+
+fn items(value: &Value) -> Option<&Vec<Value>> {
+    match value {
+        Value::Array(array) => Some(&array.values),
+        _ => None,
+    }
+}
+
+/// Returns the members of an object sorted by key, so that errors come in a stable order.
+fn members(value: &Value) -> Vec<(String, &Value)> {
+    let mut members = match value {
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, member)| (key.to_string(), member))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    members.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+    members
+}
@@ -7,6 +7,7 @@ mod tests {
     use std::collections::HashMap;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_serde_number() {
         let value = Value::from(42u64);
         let serialized = serde_json::to_string(&value).unwrap();
@@ -75,7 +76,7 @@ mod tests {
             r#"{"c":3,"b":2,"a":1}"#,
             r#"{"c":3,"a":1,"b":2}"#,
         ];
-        assert_eq!(cases.contains(&serialized.as_str()), true);
+        assert!(cases.contains(&serialized.as_str()));
 
         let deserialized: Value = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, value);
@@ -142,7 +143,7 @@ mod tests {
             r#"{"c":3,"b":2,"a":1}"#,
             r#"{"c":3,"a":1,"b":2}"#,
         ];
-        assert_eq!(cases.contains(&serialized.as_str()), true);
+        assert!(cases.contains(&serialized.as_str()));
 
         let deserialized: Value = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, value);
@@ -1,2 +1,9 @@
+use crate::prelude::*;
+
 #[cfg(feature = "derive")]
-mod derive;
\ No newline at end of file
+mod derive;
+
+/// Parses a JSON document written in a test.
+pub(crate) fn json(json: &str) -> Value {
+    Value::json_to_value(json).unwrap()
+}
@@ -56,7 +56,7 @@ impl Value {
                         format!(
                             "\n\t{}\"{}\": {}",
                             &Self::tabs(children),
-                            name,
+                            escape(&name.to_string()),
                             Value::to_json_inner(value, children + 1)
                         )
                     })
@@ -75,35 +75,7 @@ impl Value {
                     &Self::tabs(children)
                 )
             }
-            Value::String(s) => {
-                let string = s.as_str();
-                let re = Regex::new(r#"""#).unwrap();
-                let list = string
-                    .chars()
-                    .into_iter()
-                    .map(|c| c.to_string())
-                    .collect::<Vec<_>>();
-                let mut result = list.clone();
-                let mut add_posi = 0;
-
-                for item in re.captures_iter(string) {
-                    let range = item.get(0).unwrap().range();
-
-                    if range.start.eq(&0) {
-                        result.insert(range.start + add_posi, r#"\"#.to_string());
-                        add_posi += 1;
-                    } else {
-                        let before = range.start - 1;
-
-                        if list.get(before).unwrap().ne(r#"\"#) {
-                            result.insert(range.start + add_posi, r#"\"#.to_string());
-                            add_posi += 1;
-                        }
-                    }
-                }
-
-                format!("\"{}\"", result.join(""))
-            }
+            Value::String(s) => format!("\"{}\"", escape(&s.as_string())),
             Value::Number(n) => format!("{}", n),
             Value::Boolean(b) => format!("{}", b),
             Value::Null => "null".to_string(),
@@ -113,6 +85,26 @@ impl Value {
     }
 }
 
+/// Escapes a string for the body of a JSON string: quotes, backslashes and control
+/// characters, so that indentation never appears inside a string.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,4 +140,48 @@ mod tests {
             value_boolean.to_json(JsonMode::Indented)
         )
     }
+
+    #[test]
+    fn it_should_escape_strings() {
+        let value = Value::from(vec![
+            Value::from("x\ny"),
+            Value::from("tab\tz"),
+            Value::from("a\\n"),
+            Value::from("q\\\""),
+            Value::from("bell\u{7}"),
+        ]);
+        assert_eq!(
+            value.to_json(JsonMode::Inline),
+            r#"["x\ny","tab\tz","a\\n","q\\\"","bell\u0007"]"#
+        );
+
+        let mut object = Object::default();
+        object.insert("a\"b", Value::from("c"));
+        assert_eq!(
+            Value::from(object).to_json(JsonMode::Inline),
+            r#"{"a\"b": "c"}"#
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_strings() {
+        for json in [
+            r#""x\ny""#,
+            r#""tab\tz""#,
+            r#""a\\n""#,
+            r#""q\\\"""#,
+            r#""\u0001\b\f\r é ☃""#,
+            r#"{"key\n\"quoted\"": ["\\", "\t"]}"#,
+        ] {
+            let value = Value::json_to_value(json).unwrap();
+            for mode in [JsonMode::Inline, JsonMode::Indented] {
+                assert_eq!(
+                    Value::json_to_value(&value.to_json(mode)).unwrap(),
+                    value,
+                    "{}",
+                    json
+                );
+            }
+        }
+    }
 }
@@ -20,7 +20,7 @@ impl Value {
             Value::Null => " null\n".to_string(),
             Value::Boolean(b) => format!(" {}\n", b),
             Value::Number(n) => format!(" {}\n", n),
-            Value::String(s) => format!(r#" "{}"\n"#, s.to_string()),
+            Value::String(s) => format!(r#" "{}"\n"#, s),
             Value::Array(a) => {
                 let elements: Vec<String> = a
                     .into_iter()
@@ -40,7 +40,7 @@ impl Value {
                 }
             }
             Value::Undefined => " ~\n".to_string(),
-            Value::DateTime(dt) => format!(" {}\n", dt.to_string()),
+            Value::DateTime(dt) => format!(" {}\n", dt),
         }
     }
 
@@ -59,6 +59,7 @@ impl Value {
 }
 
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn test_to_yaml() {
     use std::collections::BTreeMap;
     use crate::prelude::*;
@@ -48,7 +48,7 @@ impl ToValueBehavior for &str {
 
 impl ToValueBehavior for StringB {
     fn to_value(&self) -> Value {
-        Value::String(StringB::from(self.clone()))
+        Value::String(self.clone())
     }
 }
 
@@ -103,27 +103,51 @@ where
     V: ToValueBehavior,
 {
     fn to_value(&self) -> Value {
+        Value::Object(self.to_object())
+    }
+}
+
+impl<T, V> ToValueBehavior for BTreeMap<T, V>
+where
+    T: ValueKeyBehavior,
+    V: ToValueBehavior,
+{
+    fn to_value(&self) -> Value {
+        Value::Object(self.to_object())
+    }
+}
+
+impl ToObjectBehavior for Object {
+    fn to_object(&self) -> Object {
+        self.clone()
+    }
+}
+
+impl<K, V> ToObjectBehavior for HashMap<K, V>
+where
+    K: ValueKeyBehavior,
+    V: ToValueBehavior,
+{
+    fn to_object(&self) -> Object {
         Object::from(
             self.iter()
                 .map(|(k, v)| (k.to_value_key(), v.to_value()))
                 .collect::<HashMap<ValueKey, Value>>(),
         )
-        .to_value()
     }
 }
 
-impl<T, V> ToValueBehavior for BTreeMap<T, V>
+impl<T, V> ToObjectBehavior for BTreeMap<T, V>
 where
     T: ValueKeyBehavior,
     V: ToValueBehavior,
 {
-    fn to_value(&self) -> Value {
+    fn to_object(&self) -> Object {
         Object::from(
             self.iter()
                 .map(|(k, v)| (k.to_value_key(), v.to_value()))
                 .collect::<HashMap<ValueKey, Value>>(),
         )
-        .to_value()
     }
 }
 
@@ -272,13 +296,13 @@ mod test {
 
     #[test]
     fn test_from_usize() {
-        let number = 1 as usize;
+        let number = 1_usize;
         assert_eq!(number.to_value(), Value::Number(Number::from(number)));
     }
 
     #[test]
     fn test_from_isize() {
-        let number = 1 as isize;
+        let number = 1_isize;
         assert_eq!(number.to_value(), Value::Number(Number::from(number)));
     }
 }
@@ -8,11 +8,62 @@ pub trait ToValueBehavior {
     /// Converts a type into a `Value`.
     fn to_value(&self) -> Value;
 }
+/// A trait for converting types to `Object`, for those whose value is always an object, such
+/// as structs with named fields and maps. The newtype variants of an internally tagged enum
+/// hold such a type, so that the tag can be added to its members.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not convert to an object",
+    note = "the newtype variants of an internally tagged enum must hold a struct with named fields or a map"
+)]
+pub trait ToObjectBehavior {
+    /// Converts a type into an `Object`.
+    fn to_object(&self) -> Object;
+}
+
 /// A trait for converting `Value` to types.
 pub trait FromValueBehavior {
     type Item;
     /// Converts a `Value` into a type.
     fn from_value(value: Value) -> Option<Self::Item>;
+
+    /// Returns the value of a field missing from an object, for types that may be left out,
+    /// as `Option` may.
+    fn from_missing() -> Option<Self::Item> {
+        None
+    }
+
+    /// Returns the keys a type reads from an object, which a field flattened next to it
+    /// leaves out. Only structs deriving `FromValue` read named keys.
+    fn field_keys() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Converts a number to an integer type when it holds an integer in the range of the type,
+/// whatever the width it is stored with: parsing stores small integers as `i32` and larger
+/// ones as `f64`.
+fn integer<T: TryFrom<i128>>(number: &Number) -> Option<T> {
+    let value = match number.number_type() {
+        NumberType::I8 => number.get_i8_unsafe() as i128,
+        NumberType::I16 => number.get_i16_unsafe() as i128,
+        NumberType::I32 => number.get_i32_unsafe() as i128,
+        NumberType::I64 => number.get_i64_unsafe() as i128,
+        NumberType::I128 => number.get_i128_unsafe(),
+        NumberType::U8 => number.get_u8_unsafe() as i128,
+        NumberType::U16 => number.get_u16_unsafe() as i128,
+        NumberType::U32 => number.get_u32_unsafe() as i128,
+        NumberType::U64 => number.get_u64_unsafe() as i128,
+        NumberType::U128 => i128::try_from(number.get_u128_unsafe()).ok()?,
+        NumberType::F32 | NumberType::F64 => {
+            let value = number.to_f64()?;
+            if value.fract() != 0.0 || value.abs() >= i128::MAX as f64 {
+                return None;
+            }
+            value as i128
+        }
+        NumberType::Unknown => return None,
+    };
+    T::try_from(value).ok()
 }
 
 impl FromValueBehavior for i8 {
@@ -20,7 +71,7 @@ impl FromValueBehavior for i8 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_i8()
+            integer(&number)
         } else {
             None
         }
@@ -32,7 +83,7 @@ impl FromValueBehavior for i16 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_i16()
+            integer(&number)
         } else {
             None
         }
@@ -44,7 +95,7 @@ impl FromValueBehavior for i32 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_i32()
+            integer(&number)
         } else {
             None
         }
@@ -56,7 +107,7 @@ impl FromValueBehavior for i64 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_i64()
+            integer(&number)
         } else {
             None
         }
@@ -68,7 +119,7 @@ impl FromValueBehavior for i128 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_i128()
+            integer(&number)
         } else {
             None
         }
@@ -80,7 +131,7 @@ impl FromValueBehavior for u8 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_u8()
+            integer(&number)
         } else {
             None
         }
@@ -92,7 +143,7 @@ impl FromValueBehavior for u16 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_u16()
+            integer(&number)
         } else {
             None
         }
@@ -104,7 +155,7 @@ impl FromValueBehavior for u32 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_u32()
+            integer(&number)
         } else {
             None
         }
@@ -116,7 +167,7 @@ impl FromValueBehavior for u64 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_u64()
+            integer(&number)
         } else {
             None
         }
@@ -128,19 +179,21 @@ impl FromValueBehavior for u128 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_u128()
+            number.get_u128().or_else(|| integer(&number))
         } else {
             None
         }
     }
 }
 
+/// Reads any number, rounded to the nearest `f32`: parsing stores fractional numbers as `f64`,
+/// so only accepting those an `f32` holds exactly would reject most of them, such as `0.1`.
 impl FromValueBehavior for f32 {
     type Item = f32;
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_f32()
+            number.to_f64().map(|number| number as f32)
         } else {
             None
         }
@@ -152,7 +205,7 @@ impl FromValueBehavior for f64 {
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Number(number) = value {
-            number.get_f64()
+            number.to_f64()
         } else {
             None
         }
@@ -229,12 +282,7 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Array(array) = value {
-            Some(
-                array
-                    .into_iter()
-                    .map(|value| T::from_value(value).unwrap())
-                    .collect::<Vec<_>>(),
-            )
+            array.into_iter().map(T::from_value).collect()
         } else {
             None
         }
@@ -258,13 +306,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(HashMap::new(), |mut map, (key, value)| {
-                map.insert(
-                    key.as_string_b().extract(),
-                    T::from_value(value.clone()).unwrap(),
-                );
-                map
-            }))
+            array
+                .iter()
+                .try_fold(HashMap::new(), |mut map, (key, value)| {
+                    map.insert(key.as_string_b().extract(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -280,13 +327,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(BTreeMap::new(), |mut map, (key, value)| {
-                map.insert(
-                    key.as_string_b().extract(),
-                    T::from_value(value.clone()).unwrap(),
-                );
-                map
-            }))
+            array
+                .iter()
+                .try_fold(BTreeMap::new(), |mut map, (key, value)| {
+                    map.insert(key.as_string_b().extract(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -302,13 +348,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(HashMap::new(), |mut map, (key, value)| {
-                map.insert(
-                    key.as_string_b().as_string(),
-                    T::from_value(value.clone()).unwrap(),
-                );
-                map
-            }))
+            array
+                .iter()
+                .try_fold(HashMap::new(), |mut map, (key, value)| {
+                    map.insert(key.as_string_b().as_string(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -324,13 +369,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(BTreeMap::new(), |mut map, (key, value)| {
-                map.insert(
-                    key.as_string_b().as_string(),
-                    T::from_value(value.clone()).unwrap(),
-                );
-                map
-            }))
+            array
+                .iter()
+                .try_fold(BTreeMap::new(), |mut map, (key, value)| {
+                    map.insert(key.as_string_b().as_string(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -346,13 +390,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(HashMap::new(), |mut map, (key, value)| {
-                map.insert(
-                    key.as_string_b().as_string(),
-                    T::from_value(value.clone()).unwrap(),
-                );
-                map
-            }))
+            array
+                .iter()
+                .try_fold(HashMap::new(), |mut map, (key, value)| {
+                    map.insert(key.as_string_b().as_string(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -368,10 +411,12 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         if let Value::Object(array) = value {
-            Some(array.iter().fold(BTreeMap::new(), |mut map, (key, value)| {
-                map.insert(key.to_string(), T::from_value(value.clone()).unwrap());
-                map
-            }))
+            array
+                .iter()
+                .try_fold(BTreeMap::new(), |mut map, (key, value)| {
+                    map.insert(key.to_string(), T::from_value(value.clone())?);
+                    Some(map)
+                })
         } else {
             None
         }
@@ -386,10 +431,18 @@ where
 
     fn from_value(value: Value) -> Option<Self::Item> {
         match value {
-            Value::Null => None,
-            _ => Some(T::from_value(value)),
+            Value::Null | Value::Undefined => Some(None),
+            _ => T::from_value(value).map(Some),
         }
     }
+
+    fn from_missing() -> Option<Self::Item> {
+        Some(None)
+    }
+
+    fn field_keys() -> Vec<String> {
+        T::field_keys()
+    }
 }
 
 /// A trait for converting types to JSON strings.
@@ -398,6 +451,12 @@ pub trait ToJsonBehavior {
     fn to_json(&self) -> String;
 }
 
+/// A trait for converting types to YAML strings.
+pub trait ToYamlBehavior {
+    /// Converts a type into a YAML string.
+    fn to_yaml(&self) -> String;
+}
+
 pub trait ValueKeyBehavior: Clone {
     fn to_value_key(&self) -> ValueKey;
 
@@ -428,8 +487,62 @@ impl ValueKeyBehavior for usize {
         true
     }
 }
+impl ValueKeyBehavior for &ValueKey {
+    fn to_value_key(&self) -> ValueKey {
+        (*self).clone()
+    }
+
+    fn as_usize(&self) -> usize {
+        match self {
+            ValueKey::Number(n) => *n,
+            ValueKey::String(_) => 0,
+        }
+    }
+}
 impl ValueKeyBehavior for &str {
     fn to_value_key(&self) -> ValueKey {
         ValueKey::String(StringB::from(*self))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_integers_from_parsed_numbers() {
+        assert_eq!(i64::from_value(json("-3")), Some(-3));
+        assert_eq!(u8::from_value(json("255")), Some(255));
+        assert_eq!(u64::from_value(json("10000000000")), Some(10_000_000_000));
+        assert_eq!(i8::from_value(json("-128")), Some(-128));
+    }
+
+    #[test]
+    fn test_integers_out_of_range() {
+        assert_eq!(u8::from_value(json("256")), None);
+        assert_eq!(u32::from_value(json("-1")), None);
+        assert_eq!(i16::from_value(Value::from(40_000u32)), None);
+        assert_eq!(u64::from_value(Value::from(u128::MAX)), None);
+        assert_eq!(u128::from_value(Value::from(u128::MAX)), Some(u128::MAX));
+    }
+
+    #[test]
+    fn test_integers_from_floats() {
+        assert_eq!(u8::from_value(Value::from(3.0)), Some(3));
+        assert_eq!(i32::from_value(Value::from(-2.0f32)), Some(-2));
+        assert_eq!(u8::from_value(Value::from(1.5)), None);
+        assert_eq!(i64::from_value(Value::from(f64::NAN)), None);
+        assert_eq!(i64::from_value(Value::from(f64::INFINITY)), None);
+    }
+
+    #[test]
+    fn test_floats_from_numbers() {
+        assert_eq!(f64::from_value(json("2")), Some(2.0));
+        assert_eq!(f64::from_value(Value::from(7u8)), Some(7.0));
+        assert_eq!(f32::from_value(Value::from(0.5)), Some(0.5));
+        assert_eq!(f32::from_value(json("0.1")), Some(0.1));
+        assert_eq!(f32::from_value(Value::from(16_777_217)), Some(16_777_216.0));
+        assert_eq!(f64::from_value(Value::from("2")), None);
+    }
+}
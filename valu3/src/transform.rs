@@ -0,0 +1,216 @@
+//! Path-aware recursive rewriting of `Value` trees.
+//!
+//! `Value::transform` calls a closure on every node with the node's path, a stack of
+//! `ValueKey`s, and lets it keep, replace or remove the node, or leave its children alone.
+//! Nodes are visited top-down, parents before their children, or bottom-up, children first.
+//! Object members are visited in key order; array elements keep the index they had before any
+//! of their siblings were removed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! let mut value = Value::json_to_value(r#"{"users": [{"email": "A@X.COM", "age": 30}]}"#).unwrap();
+//! let emails = JsonPointer::parse("/users/*/email").unwrap();
+//!
+//! value.transform(TransformOrder::TopDown, |path, value| match value {
+//!     Value::String(email) if emails.matches(path) => Action::Replace(email.to_lowercase().to_value()),
+//!     Value::Number(number) => Action::Replace(number.to_f64().unwrap().to_value()),
+//!     _ => Action::Keep,
+//! });
+//! ```
+use crate::prelude::*;
+
+/// What `Value::transform` does with a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Keep the node as it is.
+    Keep,
+    /// Replace the node. Top-down, the children of the new value are visited next.
+    Replace(Value),
+    /// Remove the node from its parent. Removing the root leaves `Value::Undefined`.
+    Remove,
+    /// Keep the node and do not visit its children. Bottom-up, children were already visited
+    /// and this is the same as `Keep`.
+    Skip,
+}
+
+/// The order in which `Value::transform` visits nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransformOrder {
+    /// Parents before their children.
+    #[default]
+    TopDown,
+    /// Children before their parents, which then see their transformed children.
+    BottomUp,
+}
+
+/// Transforms a node, returning `false` when it must be removed from its parent.
+fn transform<F>(
+    value: &mut Value,
+    path: &mut Vec<ValueKey>,
+    order: TransformOrder,
+    f: &mut F,
+) -> bool
+where
+    F: FnMut(&[ValueKey], &Value) -> Action,
+{
+    if order == TransformOrder::TopDown {
+        match f(path, value) {
+            Action::Keep => {}
+            Action::Replace(replacement) => *value = replacement,
+            Action::Remove => return false,
+            Action::Skip => return true,
+        }
+    }
+
+    match value {
+        Value::Array(array) => {
+            let values = std::mem::take(&mut array.values);
+            for (index, mut child) in values.into_iter().enumerate() {
+                path.push(ValueKey::Number(index));
+                if transform(&mut child, path, order, f) {
+                    array.values.push(child);
+                }
+                path.pop();
+            }
+        }
+        Value::Object(object) => {
+            let mut keys = object.keys().into_iter().cloned().collect::<Vec<_>>();
+            keys.sort();
+            for key in keys {
+                path.push(key.clone());
+                let keep = match object.get_mut(&key) {
+                    Some(child) => transform(child, path, order, f),
+                    None => true,
+                };
+                path.pop();
+                if !keep {
+                    object.remove(&&key);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if order == TransformOrder::BottomUp {
+        match f(path, value) {
+            Action::Keep | Action::Skip => {}
+            Action::Replace(replacement) => *value = replacement,
+            Action::Remove => return false,
+        }
+    }
+
+    true
+}
+
+impl Value {
+    /// Rewrites the value in place by calling `f` on every node, in the given order, with the
+    /// path of the node and its current value. See the [module documentation](crate::transform).
+    pub fn transform<F>(&mut self, order: TransformOrder, mut f: F)
+    where
+        F: FnMut(&[ValueKey], &Value) -> Action,
+    {
+        if !transform(self, &mut Vec::new(), order, &mut f) {
+            *self = Value::Undefined;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    #[test]
+    fn test_rewrite_by_path() {
+        let mut value = json(
+            r#"{"users": [{"email": "A@X.COM", "name": "A"}, {"email": "B@X.COM"}], "email": "C@X.COM"}"#,
+        );
+        let emails = JsonPointer::parse("/users/*/email").unwrap();
+
+        value.transform(TransformOrder::TopDown, |path, value| match value {
+            Value::String(email) if emails.matches(path) => {
+                Action::Replace(email.to_lowercase().to_value())
+            }
+            _ => Action::Keep,
+        });
+
+        assert_value_eq!(
+            value,
+            json(
+                r#"{"users": [{"email": "a@x.com", "name": "A"}, {"email": "b@x.com"}], "email": "C@X.COM"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_numbers_to_f64() {
+        let mut value = json(r#"{"a": 1, "b": [2, {"c": 3}]}"#);
+        value.transform(TransformOrder::BottomUp, |_, value| match value {
+            Value::Number(number) => Action::Replace(number.to_f64().unwrap().to_value()),
+            _ => Action::Keep,
+        });
+
+        let numbers = value.fold(Vec::new(), |mut numbers, _, value| {
+            if let Value::Number(number) = value {
+                numbers.push(number.get_f64());
+            }
+            numbers
+        });
+        assert_eq!(numbers, vec![Some(1.0), Some(2.0), Some(3.0)]);
+    }
+
+    #[test]
+    fn test_remove_bottom_up() {
+        let mut value = json(r#"{"a": null, "b": {"c": null}, "d": [null, 1, null, 2], "e": 3}"#);
+        let mut paths = Vec::new();
+
+        value.transform(TransformOrder::BottomUp, |path, value| {
+            paths.push(
+                path.iter()
+                    .map(|key| format!("/{}", key))
+                    .collect::<String>(),
+            );
+            match value {
+                Value::Null => Action::Remove,
+                Value::Object(object) if object.is_empty() => Action::Remove,
+                _ => Action::Keep,
+            }
+        });
+
+        assert_value_eq!(value, json(r#"{"d": [1, 2], "e": 3}"#));
+        assert_eq!(
+            paths,
+            vec!["/a", "/b/c", "/b", "/d/0", "/d/1", "/d/2", "/d/3", "/d", "/e", ""]
+        );
+    }
+
+    #[test]
+    fn test_skip_and_replace_top_down() {
+        let mut value = json(r#"{"keep": {"n": 1}, "wrap": 2, "n": 3}"#);
+
+        value.transform(TransformOrder::TopDown, |path, value| match value {
+            _ if path == [ValueKey::from("keep")] => Action::Skip,
+            Value::Number(number) if path == [ValueKey::from("wrap")] => {
+                Action::Replace(Value::from(vec![("n", number.to_value())]))
+            }
+            Value::Number(number) => Action::Replace((number.to_i64().unwrap() * 10).to_value()),
+            _ => Action::Keep,
+        });
+
+        assert_value_eq!(
+            value,
+            json(r#"{"keep": {"n": 1}, "wrap": {"n": 20}, "n": 30}"#)
+        );
+    }
+
+    #[test]
+    fn test_remove_root() {
+        let mut value = json(r#"{"a": 1}"#);
+        value.transform(TransformOrder::TopDown, |_, _| Action::Remove);
+        assert_eq!(value, Value::Undefined);
+    }
+}
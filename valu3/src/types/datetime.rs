@@ -1,5 +1,5 @@
 use crate::prelude::*;
-pub use chrono::{
+pub use ::chrono::{
     self, DateTime as ChDateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveTime, TimeZone,
     Timelike, Utc,
 };
@@ -150,6 +150,7 @@ pub trait NumberBehavior {
     /// num.set_u32(42);
     /// assert_eq!(num.number_type(), NumberType::U32);
     /// ```
+    #[allow(clippy::empty_line_after_doc_comments)]
     fn number_type(&self) -> NumberType;
 
     /// Converts the `Number` struct to numeric types.
@@ -590,7 +591,7 @@ impl NumberBehavior for Number {
 
             Some(self.get_i128_unsafe() as i64)
         } else if self.is_i64() {
-            Some(self.get_i64_unsafe() as i64)
+            Some(self.get_i64_unsafe())
         } else if self.is_i32() {
             Some(self.get_i32_unsafe() as i64)
         } else if self.is_i16() {
@@ -654,7 +655,7 @@ impl NumberBehavior for Number {
 
             Some(self.get_u128_unsafe() as u64)
         } else if self.is_u64() {
-            Some(self.get_u64_unsafe() as u64)
+            Some(self.get_u64_unsafe())
         } else if self.is_u32() {
             Some(self.get_u32_unsafe() as u64)
         } else if self.is_u16() {
@@ -982,6 +983,7 @@ mod tests {
     use crate::prelude::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_setters_and_getters() {
         let mut number = Number::default();
 
@@ -1023,6 +1025,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_display() {
         let mut number = Number::default();
 
@@ -1208,6 +1211,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_convert_number_to_f64() {
         let mut number = Number::default();
 
@@ -1225,6 +1229,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_convert_number_to_i64() {
         let mut number = Number::default();
 
@@ -1245,6 +1250,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_convert_number_to_u64() {
         let mut number = Number::default();
 
@@ -1,4 +1,6 @@
 use crate::prelude::*;
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
 use std::collections::{BTreeMap, HashMap};
 use std::iter::Iterator;
 
@@ -20,24 +22,52 @@ pub trait ObjectBehavior {
     fn values(&self) -> Vec<&Value>;
 }
 
-/// An enum representing a JSON object as a `BTreeMap` or a `HashMap`.
-#[derive(Debug, Clone, PartialEq)]
+/// An enum representing a JSON object as a `BTreeMap`, a `HashMap`, or, with the `indexmap`
+/// feature, an `IndexMap`, which keeps its keys in insertion order.
+#[derive(Debug, Clone)]
 pub enum Object {
     BTreeMap(BTreeMap<ValueKey, Value>),
     HashMap(HashMap<ValueKey, Value>),
+    #[cfg(feature = "indexmap")]
+    IndexMap(IndexMap<ValueKey, Value>),
+}
+
+impl PartialEq for Object {
+    /// Objects are equal when they hold the same members, in any order and whichever the
+    /// variant.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
 }
 
 impl PartialOrd for Object {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match (self, other) {
             (Object::BTreeMap(map1), Object::BTreeMap(map2)) => map1.partial_cmp(map2),
-            (Object::HashMap(_), Object::HashMap(_)) => None,
+            _ if self == other => Some(std::cmp::Ordering::Equal),
             _ => None,
         }
     }
 }
 
 impl Object {
+    /// Creates an empty object that keeps its keys in insertion order, as structs deriving
+    /// `ToValue` do with their fields.
+    #[cfg(feature = "indexmap")]
+    pub fn ordered() -> Self {
+        Object::IndexMap(IndexMap::new())
+    }
+
+    /// Creates an empty object for structs deriving `ToValue`. Without the `indexmap` feature
+    /// its keys are sorted rather than kept in insertion order.
+    #[cfg(not(feature = "indexmap"))]
+    pub fn ordered() -> Self {
+        Object::BTreeMap(BTreeMap::new())
+    }
+
     /// Returns a reference to the value associated with the specified key, or `None` if the key is not present.
     pub fn get<T>(&self, key: T) -> Option<&Value>
     where
@@ -47,6 +77,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.get(&value_key),
             Object::HashMap(map) => map.get(&value_key),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.get(&value_key),
         }
     }
 
@@ -58,6 +90,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.get_mut(&value_key),
             Object::HashMap(map) => map.get_mut(&value_key),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.get_mut(&value_key),
         }
     }
 
@@ -66,6 +100,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.clear(),
             Object::HashMap(map) => map.clear(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.clear(),
         }
     }
 
@@ -77,6 +113,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.insert(key, value),
             Object::HashMap(map) => map.insert(key, value),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.insert(key, value),
         }
     }
 
@@ -84,6 +122,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.len(),
             Object::HashMap(map) => map.len(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.len(),
         }
     }
 
@@ -91,6 +131,8 @@ impl Object {
         match self {
             Object::BTreeMap(map) => map.is_empty(),
             Object::HashMap(map) => map.is_empty(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.is_empty(),
         }
     }
 }
@@ -104,6 +146,8 @@ impl ObjectBehavior for Object {
         match self {
             Object::BTreeMap(map) => map.remove(&key),
             Object::HashMap(map) => map.remove(&key),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.shift_remove(&key),
         }
     }
 
@@ -115,6 +159,8 @@ impl ObjectBehavior for Object {
         match self {
             Object::BTreeMap(map) => map.contains_key(&key),
             Object::HashMap(map) => map.contains_key(&key),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.contains_key(&key),
         }
     }
 
@@ -122,6 +168,8 @@ impl ObjectBehavior for Object {
         match self {
             Object::BTreeMap(map) => map.keys().collect(),
             Object::HashMap(map) => map.keys().collect(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.keys().collect(),
         }
     }
 
@@ -129,6 +177,8 @@ impl ObjectBehavior for Object {
         match self {
             Object::BTreeMap(map) => map.values().collect(),
             Object::HashMap(map) => map.values().collect(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.values().collect(),
         }
     }
 }
@@ -172,6 +222,23 @@ where
     }
 }
 
+#[cfg(feature = "indexmap")]
+impl<T, V> From<IndexMap<T, V>> for Object
+where
+    T: ValueKeyBehavior,
+    V: ToValueBehavior,
+{
+    /// Converts IndexMap<ValueKey, Value> into Object.
+    fn from(value: IndexMap<T, V>) -> Self {
+        Object::IndexMap(
+            value
+                .iter()
+                .map(|(k, v)| (k.to_value_key(), v.to_value()))
+                .collect::<IndexMap<ValueKey, Value>>(),
+        )
+    }
+}
+
 impl From<HashMap<ValueKey, Value>> for Object {
     /// Converts HashMap<ValueKey, Value> into Object.
     fn from(value: HashMap<ValueKey, Value>) -> Self {
@@ -202,22 +269,41 @@ where
     }
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<HashMap<ValueKey, Value>> for Object {
     /// Converts Object into HashMap<ValueKey, Value>.
     fn into(self) -> HashMap<ValueKey, Value> {
         match self {
             Object::BTreeMap(map) => map.into_iter().collect(),
             Object::HashMap(map) => map,
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.into_iter().collect(),
         }
     }
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<BTreeMap<ValueKey, Value>> for Object {
     /// Converts Object into BTreeMap<ValueKey, Value>.
     fn into(self) -> BTreeMap<ValueKey, Value> {
         match self {
             Object::BTreeMap(map) => map,
             Object::HashMap(map) => map.into_iter().collect(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl From<Object> for IndexMap<ValueKey, Value> {
+    /// Converts Object into IndexMap<ValueKey, Value>.
+    fn from(val: Object) -> Self {
+        match val {
+            Object::BTreeMap(map) => map.into_iter().collect(),
+            Object::HashMap(map) => map.into_iter().collect(),
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => map,
         }
     }
 }
@@ -229,9 +315,13 @@ pub struct ObjectIter<'a> {
     state: IterState<'a>,
 }
 
+// Named after the variants of `Object`.
+#[allow(clippy::enum_variant_names)]
 enum IterState<'a> {
     BTreeMap(std::collections::btree_map::Iter<'a, ValueKey, Value>),
     HashMap(std::collections::hash_map::Iter<'a, ValueKey, Value>),
+    #[cfg(feature = "indexmap")]
+    IndexMap(indexmap::map::Iter<'a, ValueKey, Value>),
 }
 
 impl<'a> Iterator for ObjectIter<'a> {
@@ -241,6 +331,8 @@ impl<'a> Iterator for ObjectIter<'a> {
         match &mut self.state {
             IterState::BTreeMap(iter) => iter.next(),
             IterState::HashMap(iter) => iter.next(),
+            #[cfg(feature = "indexmap")]
+            IterState::IndexMap(iter) => iter.next(),
         }
     }
 }
@@ -257,6 +349,12 @@ impl<'a> Object {
                 object: self,
                 state: IterState::HashMap(map.iter()),
             },
+
+            #[cfg(feature = "indexmap")]
+            Object::IndexMap(map) => ObjectIter {
+                object: self,
+                state: IterState::IndexMap(map.iter()),
+            },
         }
     }
 }
@@ -276,10 +374,10 @@ mod tests {
         map.insert("key2".to_string(), value2.clone());
         let obj = Object::from(map);
 
-        let mut iter = obj.iter();
+        let iter = obj.iter();
         let mut results = vec![];
 
-        while let Some((key, value)) = iter.next() {
+        for (key, value) in iter {
             results.push((key.clone(), value.clone()));
         }
 
@@ -292,6 +390,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ordered_object() {
+        let mut obj = Object::ordered();
+        obj.insert("b", Value::from(1));
+        obj.insert("a", Value::from(2));
+        obj.insert("c", Value::from(3));
+        obj.insert("b", Value::from(4));
+        obj.remove(&"a");
+
+        assert_eq!(obj.keys(), vec![&"b".to_value_key(), &"c".to_value_key()]);
+        assert_eq!(obj.values(), vec![&Value::from(4), &Value::from(3)]);
+    }
+
+    #[test]
+    fn test_object_eq() {
+        let mut ordered = Object::ordered();
+        ordered.insert("b", Value::from(1));
+        ordered.insert("a", Value::from(vec![Value::Null]));
+
+        let mut hash_map = HashMap::new();
+        hash_map.insert("a".to_string(), Value::from(vec![Value::Null]));
+        hash_map.insert("b".to_string(), Value::from(1));
+        let hash_map = Object::from(hash_map);
+
+        let b_tree_map = Object::from(BTreeMap::from([
+            ("a".to_string(), Value::from(vec![Value::Null])),
+            ("b".to_string(), Value::from(1)),
+        ]));
+
+        assert_eq!(ordered, hash_map);
+        assert_eq!(hash_map, b_tree_map);
+        assert_eq!(b_tree_map, ordered);
+        assert_eq!(
+            ordered.partial_cmp(&b_tree_map),
+            Some(std::cmp::Ordering::Equal)
+        );
+
+        ordered.insert("c", Value::Null);
+        assert_ne!(ordered, hash_map);
+        assert_ne!(b_tree_map, ordered);
+        assert_eq!(ordered.partial_cmp(&hash_map), None);
+    }
+
     #[test]
     fn test_object_from_vec() {
         let vec = vec![
@@ -29,6 +29,13 @@ impl ValueKey {
             ValueKey::Number(n) => n.to_string().len(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ValueKey::String(s) => s.is_empty(),
+            ValueKey::Number(_) => false,
+        }
+    }
 }
 
 impl Display for ValueKey {
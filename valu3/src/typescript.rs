@@ -0,0 +1,317 @@
+//! TypeScript declarations of the `Value` representation of Rust types.
+//!
+//! [`ToTypeScript`] describes the JSON a type converts to, as emitted by `to_value` and
+//! `to_json`, with a TypeScript type. Derived types are declared as interfaces or type aliases
+//! in a [`TypeScriptDeclarations`], which renders as the contents of a `.d.ts` file.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! #[derive(ToValue, FromValue, ToTypeScript)]
+//! enum Role {
+//!     Admin,
+//!     Member,
+//! }
+//!
+//! #[derive(ToValue, FromValue, ToTypeScript)]
+//! struct User {
+//!     id: u32,
+//!     nickname: Option<String>,
+//!     roles: Vec<Role>,
+//! }
+//!
+//! let mut declarations = TypeScriptDeclarations::new();
+//! declarations.add::<User>();
+//! declarations.write("frontend/src/types.d.ts").unwrap();
+//! // export type Role = "Admin" | "Member";
+//! //
+//! // export interface User {
+//! //   id: number;
+//! //   nickname: string | null;
+//! //   roles: Role[];
+//! // }
+//! ```
+use crate::prelude::*;
+use crate::schema::TypeNames;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Types that can describe their `Value` representation with a TypeScript type.
+pub trait ToTypeScript {
+    /// Returns the TypeScript type of the type, declaring the named types it refers to.
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String;
+
+    /// Returns the declarations of the type and of the types it refers to.
+    fn typescript() -> String {
+        let mut declarations = TypeScriptDeclarations::new();
+        declarations.add::<Self>();
+        declarations.to_string()
+    }
+}
+
+/// The named TypeScript declarations types refer to, which make up a `.d.ts` file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypeScriptDeclarations {
+    declarations: BTreeMap<String, String>,
+    types: TypeNames,
+}
+
+impl TypeScriptDeclarations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the name a type is declared under: its name without module paths and with its
+    /// type arguments appended, such as `PageUser` for `Page<User>`.
+    pub fn name_of<T: ?Sized>() -> String {
+        short_type_name::<T>()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '_')
+            .collect()
+    }
+
+    /// Returns the name a type is declared under: `name_of` the type, followed by a number,
+    /// as in `User2`, when a different type of the same name from another module took it.
+    pub fn name_for<T: ?Sized>(&mut self) -> String {
+        let declarations = &self.declarations;
+        self.types
+            .name_for::<T>(Self::name_of::<T>(), |name| declarations.contains_key(name))
+    }
+
+    /// Declares a type under a name, unless it already is, and returns the name. While
+    /// `declaration` runs the name is taken, so that recursive types end. The declaration is
+    /// the full statement, such as `export type Id = number;`.
+    pub fn declare<F>(&mut self, name: &str, declaration: F) -> String
+    where
+        F: FnOnce(&mut Self) -> String,
+    {
+        if !self.declarations.contains_key(name) {
+            self.declarations.insert(name.to_string(), String::new());
+            let declaration = declaration(self);
+            self.declarations.insert(name.to_string(), declaration);
+        }
+        name.to_string()
+    }
+
+    /// Adds the declarations of a type, and returns its TypeScript type.
+    pub fn add<T: ToTypeScript + ?Sized>(&mut self) -> String {
+        T::ts_type(self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.declarations.get(name).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.declarations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    /// Writes the declarations to a `.d.ts` file.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl Display for TypeScriptDeclarations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, declaration) in self.declarations.values().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", declaration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the type of an array of `item`, parenthesizing unions.
+fn array(item: String) -> String {
+    if item.contains(" | ") {
+        format!("({})[]", item)
+    } else {
+        format!("{}[]", item)
+    }
+}
+
+macro_rules! primitive_typescript {
+    ($($ty:ty => $ts:literal),* $(,)?) => {
+        $(
+            impl ToTypeScript for $ty {
+                fn ts_type(_: &mut TypeScriptDeclarations) -> String {
+                    $ts.to_string()
+                }
+            }
+        )*
+    };
+}
+
+primitive_typescript!(
+    u8 => "number",
+    u16 => "number",
+    u32 => "number",
+    u64 => "number",
+    u128 => "number",
+    usize => "number",
+    i8 => "number",
+    i16 => "number",
+    i32 => "number",
+    i64 => "number",
+    i128 => "number",
+    isize => "number",
+    f32 => "number",
+    f64 => "number",
+    Number => "number",
+    bool => "boolean",
+    String => "string",
+    &str => "string",
+    StringB => "string",
+    char => "string",
+    DateTime => "string",
+    Value => "unknown",
+    Object => "Record<string, unknown>",
+    Array => "unknown[]",
+    () => "null",
+);
+
+impl<T: ToTypeScript> ToTypeScript for Option<T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        let ts_type = T::ts_type(declarations);
+        if ts_type == "unknown" || ts_type.split(" | ").any(|part| part == "null") {
+            ts_type
+        } else {
+            format!("{} | null", ts_type)
+        }
+    }
+}
+
+impl<T: ToTypeScript + ?Sized> ToTypeScript for Box<T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        T::ts_type(declarations)
+    }
+}
+
+impl<T: ToTypeScript> ToTypeScript for Vec<T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        array(T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript> ToTypeScript for VecDeque<T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        array(T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript, S> ToTypeScript for HashSet<T, S> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        array(T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript> ToTypeScript for BTreeSet<T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        array(T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript, const N: usize> ToTypeScript for [T; N] {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        array(T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript, S> ToTypeScript for HashMap<String, T, S> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        format!("Record<string, {}>", T::ts_type(declarations))
+    }
+}
+
+impl<T: ToTypeScript> ToTypeScript for BTreeMap<String, T> {
+    fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+        format!("Record<string, {}>", T::ts_type(declarations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use std::collections::{BTreeMap, HashSet};
+
+    struct Node;
+
+    impl ToTypeScript for Node {
+        fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+            declarations.declare("Node", |declarations| {
+                format!(
+                    "export interface Node {{\n  children: {};\n}}",
+                    Vec::<Node>::ts_type(declarations)
+                )
+            })
+        }
+    }
+
+    #[test]
+    fn test_std_types() {
+        let mut declarations = TypeScriptDeclarations::new();
+        assert_eq!(declarations.add::<u64>(), "number");
+        assert_eq!(declarations.add::<Option<Vec<String>>>(), "string[] | null");
+        assert_eq!(
+            declarations.add::<Vec<Option<bool>>>(),
+            "(boolean | null)[]"
+        );
+        assert_eq!(declarations.add::<Option<Option<i8>>>(), "number | null");
+        assert_eq!(declarations.add::<Option<Value>>(), "unknown");
+        assert_eq!(
+            declarations.add::<BTreeMap<String, HashSet<char>>>(),
+            "Record<string, string[]>"
+        );
+        assert!(declarations.is_empty());
+    }
+
+    #[test]
+    fn test_recursive_declarations() {
+        assert_eq!(
+            Node::typescript(),
+            "export interface Node {\n  children: Node[];\n}\n"
+        );
+
+        let mut declarations = TypeScriptDeclarations::new();
+        assert_eq!(declarations.add::<Option<Node>>(), "Node | null");
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(
+            declarations.get("Node"),
+            Some("export interface Node {\n  children: Node[];\n}")
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let path = std::env::temp_dir().join("valu3_typescript_test.d.ts");
+        let mut declarations = TypeScriptDeclarations::new();
+        declarations.add::<Node>();
+        declarations.declare("Id", |_| "export type Id = number;".to_string());
+        declarations.write(&path).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "export type Id = number;\n\nexport interface Node {\n  children: Node[];\n}\n"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_name_of() {
+        assert_eq!(TypeScriptDeclarations::name_of::<Node>(), "Node");
+        assert_eq!(
+            TypeScriptDeclarations::name_of::<BTreeMap<String, Vec<Node>>>(),
+            "BTreeMapStringVecNode"
+        );
+    }
+}
@@ -0,0 +1,571 @@
+//! Declarative validation of typed values and of the `Value`s they are read from.
+//!
+//! [`Validate`] checks the fields of a type against [`Constraint`]s, such as a length, a
+//! numeric range or a pattern, and reports every field that fails as a [`Violation`] placed at
+//! its path, such as `items[3].price`. `#[derive(Validate)]` implements it from
+//! `#[validate(...)]` attributes on the fields of a struct, and checks both an instance of the
+//! struct and a `Value` before it is converted to one.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! #[derive(ToValue, FromValue, Validate)]
+//! struct Signup {
+//!     #[validate(length(min = 1, max = 50))]
+//!     name: String,
+//!     #[validate(range(min = 0, max = 150))]
+//!     age: u8,
+//!     #[validate(email)]
+//!     email: String,
+//! }
+//!
+//! let value = Value::json_to_value(r#"{"name": "", "age": 200, "email": "nobody"}"#).unwrap();
+//! let violations = Signup::validate_value(&value).unwrap_err();
+//! assert_eq!(violations.paths(), vec!["name", "age", "email"]);
+//! ```
+use crate::conversion::{describe, format_path};
+use crate::patch::equivalent;
+use crate::prelude::*;
+use crate::schema::format::Format;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// A rule a value must follow. `null` and undefined values, which stand for missing optional
+/// fields, follow every rule.
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// The number of characters of a string, items of an array or members of an object.
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// The bounds of a number, both inclusive.
+    Range { min: Option<f64>, max: Option<f64> },
+    /// A string matching a regular expression.
+    Pattern(Regex),
+    /// A string holding an email address.
+    Email,
+    /// A string holding an absolute URL.
+    Url,
+    /// A value equivalent to one of these.
+    OneOf(Vec<Value>),
+    /// A function returning the message of the violation when the value does not fit.
+    Custom(fn(&Value) -> Result<(), String>),
+}
+
+impl Constraint {
+    /// Creates a `Pattern` constraint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is not a valid regular expression.
+    pub fn pattern(pattern: &str) -> Self {
+        match Regex::new(pattern) {
+            Ok(regex) => Constraint::Pattern(regex),
+            Err(error) => panic!("invalid pattern {:?}: {}", pattern, error),
+        }
+    }
+
+    /// Returns the name of the constraint, as written in `#[validate(...)]`.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Constraint::Length { .. } => "length",
+            Constraint::Range { .. } => "range",
+            Constraint::Pattern(_) => "regex",
+            Constraint::Email => "email",
+            Constraint::Url => "url",
+            Constraint::OneOf(_) => "one_of",
+            Constraint::Custom(_) => "custom",
+        }
+    }
+
+    /// Checks a value, returning the violation at its root when it does not fit.
+    pub fn check(&self, value: &Value) -> Result<(), Violation> {
+        if value.is_null() || value.is_undefined() {
+            return Ok(());
+        }
+
+        let message = match self {
+            Constraint::Length { min, max } => {
+                let length = match value {
+                    Value::String(string) => string.as_string().chars().count(),
+                    Value::Array(array) => array.len(),
+                    Value::Object(object) => object.len(),
+                    _ => return Err(self.mismatch("a string, array or object", value)),
+                };
+                bounds(
+                    "length",
+                    min.map(|min| min as f64),
+                    max.map(|max| max as f64),
+                    length as f64,
+                )
+            }
+            Constraint::Range { min, max } => match value {
+                Value::Number(number) => match number.to_f64() {
+                    Some(number) => bounds("value", *min, *max, number),
+                    None => return Err(self.mismatch("a number", value)),
+                },
+                _ => return Err(self.mismatch("a number", value)),
+            },
+            Constraint::Pattern(regex) => {
+                let text = self.text(value)?;
+                (!regex.is_match(&text)).then(|| format!("must match `{}`", regex.as_str()))
+            }
+            Constraint::Email => (!Format::Email.is_valid(&self.text(value)?))
+                .then(|| "must be an email address".to_string()),
+            Constraint::Url => {
+                (!Format::Uri.is_valid(&self.text(value)?)).then(|| "must be a URL".to_string())
+            }
+            Constraint::OneOf(values) => {
+                (!values.iter().any(|item| equivalent(item, value))).then(|| {
+                    let values = values
+                        .iter()
+                        .map(|item| item.to_json(JsonMode::Inline))
+                        .collect::<Vec<_>>();
+                    format!("must be one of {}", values.join(", "))
+                })
+            }
+            Constraint::Custom(check) => check(value).err(),
+        };
+
+        match message {
+            Some(message) => Err(Violation::new(self.rule(), message)),
+            None => Ok(()),
+        }
+    }
+
+    fn text(&self, value: &Value) -> Result<String, Violation> {
+        match value {
+            Value::String(string) => Ok(string.as_string()),
+            _ => Err(self.mismatch("a string", value)),
+        }
+    }
+
+    /// Returns the violation of a value of a type the constraint does not apply to.
+    fn mismatch(&self, expected: &str, value: &Value) -> Violation {
+        Violation::new(
+            self.rule(),
+            format!("expected {}, found {}", expected, describe(value)),
+        )
+    }
+}
+
+/// Returns the message of a quantity out of its bounds.
+fn bounds(name: &str, min: Option<f64>, max: Option<f64>, found: f64) -> Option<String> {
+    let fits = min.is_none_or(|min| found >= min) && max.is_none_or(|max| found <= max);
+    if fits {
+        return None;
+    }
+
+    let expected = match (min, max) {
+        (Some(min), Some(max)) => format!("between {} and {}", min, max),
+        (Some(min), None) => format!("at least {}", min),
+        (None, Some(max)) => format!("at most {}", max),
+        (None, None) => return None,
+    };
+    Some(format!("{} must be {}, found {}", name, expected, found))
+}
+
+/// A value that does not follow a constraint.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Violation {
+    path: Vec<PathSegment>,
+    rule: String,
+    message: String,
+}
+
+impl Violation {
+    /// Creates a violation of the `rule` at the root of the value.
+    pub fn new(rule: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: Vec::new(),
+            rule: rule.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Places the violation under a key of an object.
+    pub fn at_key(mut self, key: &str) -> Self {
+        self.path.insert(0, PathSegment::Key(key.to_string()));
+        self
+    }
+
+    /// Places the violation under an index of an array.
+    pub fn at_index(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// Returns where the rule failed, such as `tags[0]`, empty at the root.
+    pub fn path(&self) -> String {
+        format_path(&self.path)
+    }
+
+    /// Returns the name of the rule, such as `length` or `email`.
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path())?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The violations found while validating a value, in the order of its fields.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Violations {
+    violations: Vec<Violation>,
+}
+
+impl Violations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, violation: Violation) {
+        self.violations.push(violation);
+    }
+
+    /// Checks the value at a key of an object against constraints, adding a violation for
+    /// each one it does not follow.
+    pub fn check(&mut self, key: &str, value: &Value, constraints: &[Constraint]) {
+        for constraint in constraints {
+            if let Err(violation) = constraint.check(value) {
+                self.push(violation.at_key(key));
+            }
+        }
+    }
+
+    /// Adds the violations of a value found at a key of an object.
+    pub fn nest_key(&mut self, key: &str, result: Result<(), Violations>) {
+        if let Err(violations) = result {
+            self.violations.extend(
+                violations
+                    .violations
+                    .into_iter()
+                    .map(|violation| violation.at_key(key)),
+            );
+        }
+    }
+
+    /// Adds the violations of a value found at an index of an array.
+    pub fn nest_index(&mut self, index: usize, result: Result<(), Violations>) {
+        if let Err(violations) = result {
+            self.violations.extend(
+                violations
+                    .violations
+                    .into_iter()
+                    .map(|violation| violation.at_index(index)),
+            );
+        }
+    }
+
+    /// Adds the violations of a value found at the same path, as a flattened field is.
+    pub fn merge(&mut self, result: Result<(), Violations>) {
+        if let Err(violations) = result {
+            self.violations.extend(violations.violations);
+        }
+    }
+
+    /// Returns `Ok` when there are no violations.
+    pub fn into_result(self) -> Result<(), Violations> {
+        if self.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.violations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Violation> {
+        self.violations.iter()
+    }
+
+    /// Returns the violations of the value at a path, such as `items[3].price`.
+    pub fn at(&self, path: &str) -> Vec<&Violation> {
+        self.violations
+            .iter()
+            .filter(|violation| violation.path() == path)
+            .collect()
+    }
+
+    /// Returns the paths of the values that have violations, once each, in order.
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for violation in &self.violations {
+            let path = violation.path();
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// Returns the messages of the violations keyed by their paths.
+    pub fn messages(&self) -> BTreeMap<String, Vec<String>> {
+        let mut messages = BTreeMap::<String, Vec<String>>::new();
+        for violation in &self.violations {
+            messages
+                .entry(violation.path())
+                .or_default()
+                .push(violation.message.clone());
+        }
+        messages
+    }
+}
+
+impl Display for Violations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, violation) in self.violations.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Violations {
+    type Item = &'a Violation;
+    type IntoIter = std::slice::Iter<'a, Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Violations> for Error {
+    fn from(violations: Violations) -> Self {
+        Error::Validation(violations)
+    }
+}
+
+/// A trait for types whose values follow constraints.
+pub trait Validate {
+    /// Checks a value of the type, returning every violation found.
+    fn validate(&self) -> Result<(), Violations>;
+
+    /// Checks a `Value` before it is converted to the type, returning every violation found.
+    /// Parts of the value that do not fit the type are left to the conversion.
+    fn validate_value(value: &Value) -> Result<(), Violations>;
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Result<(), Violations> {
+        match self {
+            Some(item) => item.validate(),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_value(value: &Value) -> Result<(), Violations> {
+        if value.is_null() || value.is_undefined() {
+            Ok(())
+        } else {
+            T::validate_value(value)
+        }
+    }
+}
+
+impl<T: Validate> Validate for Box<T> {
+    fn validate(&self) -> Result<(), Violations> {
+        T::validate(self)
+    }
+
+    fn validate_value(value: &Value) -> Result<(), Violations> {
+        T::validate_value(value)
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> Result<(), Violations> {
+        let mut violations = Violations::new();
+        for (index, item) in self.iter().enumerate() {
+            violations.nest_index(index, item.validate());
+        }
+        violations.into_result()
+    }
+
+    fn validate_value(value: &Value) -> Result<(), Violations> {
+        let mut violations = Violations::new();
+        if let Value::Array(array) = value {
+            for (index, item) in array.values.iter().enumerate() {
+                violations.nest_index(index, T::validate_value(item));
+            }
+        }
+        violations.into_result()
+    }
+}
+
+/// Validates the values of an object, each placed at its key.
+fn validate_entries<'a, T, I>(entries: I) -> Result<(), Violations>
+where
+    T: Validate + 'a,
+    I: Iterator<Item = (String, &'a T)>,
+{
+    let mut violations = Violations::new();
+    for (key, item) in entries {
+        violations.nest_key(&key, item.validate());
+    }
+    violations.into_result()
+}
+
+fn validate_value_entries<T: Validate>(value: &Value) -> Result<(), Violations> {
+    let mut violations = Violations::new();
+    if let Value::Object(object) = value {
+        for (key, item) in object.iter() {
+            violations.nest_key(&key.to_string(), T::validate_value(item));
+        }
+    }
+    violations.into_result()
+}
+
+impl<T: Validate> Validate for HashMap<String, T> {
+    fn validate(&self) -> Result<(), Violations> {
+        validate_entries(self.iter().map(|(key, item)| (key.clone(), item)))
+    }
+
+    fn validate_value(value: &Value) -> Result<(), Violations> {
+        validate_value_entries::<T>(value)
+    }
+}
+
+impl<T: Validate> Validate for BTreeMap<String, T> {
+    fn validate(&self) -> Result<(), Violations> {
+        validate_entries(self.iter().map(|(key, item)| (key.clone(), item)))
+    }
+
+    fn validate_value(value: &Value) -> Result<(), Violations> {
+        validate_value_entries::<T>(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn message(constraint: &Constraint, value: &str) -> Option<String> {
+        constraint
+            .check(&json(value))
+            .err()
+            .map(|violation| violation.message().to_string())
+    }
+
+    #[test]
+    fn test_constraints() {
+        let length = Constraint::Length {
+            min: Some(1),
+            max: Some(3),
+        };
+        assert_eq!(message(&length, r#""éé""#), None);
+        assert_eq!(
+            message(&length, r#""""#),
+            Some("length must be between 1 and 3, found 0".to_string())
+        );
+        assert_eq!(
+            message(&length, "[1, 2, 3, 4]"),
+            Some("length must be between 1 and 3, found 4".to_string())
+        );
+        assert_eq!(
+            message(&length, "1"),
+            Some("expected a string, array or object, found number 1".to_string())
+        );
+
+        let range = Constraint::Range {
+            min: Some(0.0),
+            max: None,
+        };
+        assert_eq!(message(&range, "0"), None);
+        assert_eq!(
+            message(&range, "-0.5"),
+            Some("value must be at least 0, found -0.5".to_string())
+        );
+
+        let pattern = Constraint::pattern("^[a-z]+$");
+        assert_eq!(message(&pattern, r#""abc""#), None);
+        assert_eq!(
+            message(&pattern, r#""Abc""#),
+            Some("must match `^[a-z]+$`".to_string())
+        );
+
+        assert_eq!(message(&Constraint::Email, r#""a@b.c""#), None);
+        assert!(message(&Constraint::Email, r#""a@""#).is_some());
+        assert_eq!(message(&Constraint::Url, r#""https://a.b/c""#), None);
+        assert!(message(&Constraint::Url, r#""/c""#).is_some());
+
+        let one_of = Constraint::OneOf(vec![Value::from("a"), Value::from(1)]);
+        assert_eq!(message(&one_of, "1.0"), None);
+        assert_eq!(
+            message(&one_of, r#""b""#),
+            Some(r#"must be one of "a", 1"#.to_string())
+        );
+
+        let custom = Constraint::Custom(|value| match value.is_bool() {
+            true => Ok(()),
+            false => Err("must be a flag".to_string()),
+        });
+        assert_eq!(message(&custom, "true"), None);
+        assert_eq!(message(&custom, "1"), Some("must be a flag".to_string()));
+
+        // Missing optional values follow every constraint.
+        assert_eq!(message(&length, "null"), None);
+        assert_eq!(message(&Constraint::Email, "null"), None);
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut inner = Violations::new();
+        inner.check(
+            "price",
+            &Value::from(-1),
+            &[Constraint::Range {
+                min: Some(0.0),
+                max: None,
+            }],
+        );
+        let mut items = Violations::new();
+        items.nest_index(3, inner.into_result());
+
+        let mut violations = Violations::new();
+        violations.nest_key("items", items.into_result());
+        violations.push(Violation::new("custom", "is closed"));
+        violations.check("a.b", &Value::from(""), &[Constraint::Email]);
+
+        assert_eq!(violations.paths(), vec!["items[3].price", "", "[\"a.b\"]"]);
+        assert_eq!(violations.at("items[3].price")[0].rule(), "range");
+        assert_eq!(
+            violations.to_string(),
+            "items[3].price: value must be at least 0, found -1\nis closed\n[\"a.b\"]: must be an email address"
+        );
+        assert_eq!(
+            violations.messages().get("").unwrap(),
+            &vec!["is closed".to_string()]
+        );
+        assert!(Violations::new().into_result().is_ok());
+    }
+}
@@ -18,24 +18,19 @@ use crate::prelude::*;
 use std::fmt::{Display, Formatter};
 
 /// Represents different data types as an enum.
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
 pub enum Value {
     String(StringB),
     Number(Number),
     Boolean(bool),
     Array(Array),
     Object(Object),
+    #[default]
     Null,
     Undefined,
     DateTime(DateTime),
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Value::Null
-    }
-}
-
 impl ValueTrait for Value {}
 
 impl Display for Value {
@@ -132,6 +127,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant, clippy::bool_assert_comparison)]
     fn test_partial_ord() {
         let value1 = Value::Number(Number::from(3.14));
         let value2 = Value::Number(Number::from(3.141));
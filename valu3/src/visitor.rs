@@ -0,0 +1,486 @@
+//! Traversal of `Value` trees.
+//!
+//! `ValueVisitor` and `ValueVisitorMut` are visited depth first by `Value::visit` and
+//! `Value::visit_mut`. Every node is entered before its children and left after them; the
+//! default `enter` dispatches to one hook per variant (`visit_string`, `visit_object`, ...) and
+//! the default `leave` to `leave_array` and `leave_object`, so a visitor only overrides the
+//! hooks it cares about. Each hook receives the path of the node as a stack of `ValueKey`s —
+//! `ValueKey::Number` for array indexes — and returns a `Visit` that continues, skips the
+//! children of the node or stops the whole traversal. Object members are visited in key order.
+//!
+//! `Value::fold` covers the common case of accumulating a result over every node.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valu3::prelude::*;
+//!
+//! struct Redact;
+//!
+//! impl ValueVisitorMut for Redact {
+//!     fn visit_string(&mut self, path: &[ValueKey], value: &mut StringB) -> Visit {
+//!         if path.last() == Some(&ValueKey::from("password")) {
+//!             *value = StringB::from("***");
+//!         }
+//!         Visit::Continue
+//!     }
+//! }
+//!
+//! let mut value = Value::json_to_value(r#"{"users": [{"name": "a", "password": "secret"}]}"#).unwrap();
+//! value.visit_mut(&mut Redact);
+//!
+//! let strings = value.fold(0, |count, _, value| count + value.is_string() as usize);
+//! assert_eq!(strings, 2);
+//! ```
+use crate::prelude::*;
+
+/// What a traversal does after a hook returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visit {
+    /// Keep going.
+    #[default]
+    Continue,
+    /// Do not visit the children of the node just entered. Its `leave` hook still runs.
+    SkipChildren,
+    /// End the traversal immediately.
+    Stop,
+}
+
+/// A read-only visitor of `Value` trees. See the [module documentation](self).
+#[allow(unused_variables)]
+pub trait ValueVisitor {
+    /// Called before the children of a node are visited.
+    fn enter(&mut self, path: &[ValueKey], value: &Value) -> Visit {
+        match value {
+            Value::String(value) => self.visit_string(path, value),
+            Value::Number(value) => self.visit_number(path, value),
+            Value::Boolean(value) => self.visit_bool(path, *value),
+            Value::Array(value) => self.visit_array(path, value),
+            Value::Object(value) => self.visit_object(path, value),
+            Value::Null => self.visit_null(path),
+            Value::Undefined => self.visit_undefined(path),
+            Value::DateTime(value) => self.visit_datetime(path, value),
+        }
+    }
+
+    /// Called after the children of a node were visited.
+    fn leave(&mut self, path: &[ValueKey], value: &Value) -> Visit {
+        match value {
+            Value::Array(value) => self.leave_array(path, value),
+            Value::Object(value) => self.leave_object(path, value),
+            _ => Visit::Continue,
+        }
+    }
+
+    fn visit_string(&mut self, path: &[ValueKey], value: &StringB) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_number(&mut self, path: &[ValueKey], value: &Number) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_bool(&mut self, path: &[ValueKey], value: bool) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_null(&mut self, path: &[ValueKey]) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_undefined(&mut self, path: &[ValueKey]) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_datetime(&mut self, path: &[ValueKey], value: &DateTime) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_array(&mut self, path: &[ValueKey], value: &Array) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_object(&mut self, path: &[ValueKey], value: &Object) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_array(&mut self, path: &[ValueKey], value: &Array) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_object(&mut self, path: &[ValueKey], value: &Object) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// A visitor that can modify the `Value` tree it visits. Changes made by `enter` are seen by
+/// the rest of the traversal, so replacing a node with a container visits the new children.
+/// See the [module documentation](self).
+#[allow(unused_variables)]
+pub trait ValueVisitorMut {
+    /// Called before the children of a node are visited.
+    fn enter(&mut self, path: &[ValueKey], value: &mut Value) -> Visit {
+        match value {
+            Value::String(value) => self.visit_string(path, value),
+            Value::Number(value) => self.visit_number(path, value),
+            Value::Boolean(value) => self.visit_bool(path, value),
+            Value::Array(value) => self.visit_array(path, value),
+            Value::Object(value) => self.visit_object(path, value),
+            Value::Null => self.visit_null(path),
+            Value::Undefined => self.visit_undefined(path),
+            Value::DateTime(value) => self.visit_datetime(path, value),
+        }
+    }
+
+    /// Called after the children of a node were visited.
+    fn leave(&mut self, path: &[ValueKey], value: &mut Value) -> Visit {
+        match value {
+            Value::Array(value) => self.leave_array(path, value),
+            Value::Object(value) => self.leave_object(path, value),
+            _ => Visit::Continue,
+        }
+    }
+
+    fn visit_string(&mut self, path: &[ValueKey], value: &mut StringB) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_number(&mut self, path: &[ValueKey], value: &mut Number) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_bool(&mut self, path: &[ValueKey], value: &mut bool) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_null(&mut self, path: &[ValueKey]) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_undefined(&mut self, path: &[ValueKey]) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_datetime(&mut self, path: &[ValueKey], value: &mut DateTime) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_array(&mut self, path: &[ValueKey], value: &mut Array) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_object(&mut self, path: &[ValueKey], value: &mut Object) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_array(&mut self, path: &[ValueKey], value: &mut Array) -> Visit {
+        Visit::Continue
+    }
+
+    fn leave_object(&mut self, path: &[ValueKey], value: &mut Object) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// Returns the keys of an object in sorted order.
+fn sorted_keys(object: &Object) -> Vec<ValueKey> {
+    let mut keys = object.keys().into_iter().cloned().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+fn walk<V: ValueVisitor + ?Sized>(
+    value: &Value,
+    path: &mut Vec<ValueKey>,
+    visitor: &mut V,
+) -> Visit {
+    match visitor.enter(path, value) {
+        Visit::Stop => return Visit::Stop,
+        Visit::SkipChildren => {}
+        Visit::Continue => {
+            let children: Vec<(ValueKey, &Value)> = match value {
+                Value::Array(array) => array
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, child)| (ValueKey::Number(index), child))
+                    .collect(),
+                Value::Object(object) => sorted_keys(object)
+                    .into_iter()
+                    .map(|key| {
+                        let child = object.get(&key).unwrap();
+                        (key, child)
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            for (key, child) in children {
+                path.push(key);
+                let visit = walk(child, path, visitor);
+                path.pop();
+                if visit == Visit::Stop {
+                    return Visit::Stop;
+                }
+            }
+        }
+    }
+
+    match visitor.leave(path, value) {
+        Visit::Stop => Visit::Stop,
+        _ => Visit::Continue,
+    }
+}
+
+fn walk_mut<V: ValueVisitorMut + ?Sized>(
+    value: &mut Value,
+    path: &mut Vec<ValueKey>,
+    visitor: &mut V,
+) -> Visit {
+    match visitor.enter(path, value) {
+        Visit::Stop => return Visit::Stop,
+        Visit::SkipChildren => {}
+        Visit::Continue => {
+            let keys = match value {
+                Value::Array(array) => (0..array.len()).map(ValueKey::Number).collect(),
+                Value::Object(object) => sorted_keys(object),
+                _ => Vec::new(),
+            };
+
+            for key in keys {
+                let child = match value {
+                    Value::Array(array) => array.get_mut(key.to_usize()),
+                    Value::Object(object) => object.get_mut(&key),
+                    _ => None,
+                };
+                let child = match child {
+                    Some(child) => child,
+                    None => continue,
+                };
+
+                path.push(key);
+                let visit = walk_mut(child, path, visitor);
+                path.pop();
+                if visit == Visit::Stop {
+                    return Visit::Stop;
+                }
+            }
+        }
+    }
+
+    match visitor.leave(path, value) {
+        Visit::Stop => Visit::Stop,
+        _ => Visit::Continue,
+    }
+}
+
+struct Fold<B, F> {
+    accumulator: Option<B>,
+    f: F,
+}
+
+impl<B, F> ValueVisitor for Fold<B, F>
+where
+    F: FnMut(B, &[ValueKey], &Value) -> B,
+{
+    fn enter(&mut self, path: &[ValueKey], value: &Value) -> Visit {
+        let accumulator = self.accumulator.take().unwrap();
+        self.accumulator = Some((self.f)(accumulator, path, value));
+        Visit::Continue
+    }
+}
+
+impl Value {
+    /// Visits the value and all of its descendants with `visitor`. Returns `Visit::Stop` when a
+    /// hook stopped the traversal and `Visit::Continue` when it ran to the end.
+    pub fn visit<V: ValueVisitor + ?Sized>(&self, visitor: &mut V) -> Visit {
+        walk(self, &mut Vec::new(), visitor)
+    }
+
+    /// Visits the value and all of its descendants with a visitor that can modify them.
+    /// Returns `Visit::Stop` when a hook stopped the traversal and `Visit::Continue` when it
+    /// ran to the end.
+    pub fn visit_mut<V: ValueVisitorMut + ?Sized>(&mut self, visitor: &mut V) -> Visit {
+        walk_mut(self, &mut Vec::new(), visitor)
+    }
+
+    /// Folds every node of the value, parents before their children, into an accumulator.
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where
+        F: FnMut(B, &[ValueKey], &Value) -> B,
+    {
+        let mut fold = Fold {
+            accumulator: Some(init),
+            f,
+        };
+        self.visit(&mut fold);
+        fold.accumulator.unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_value_eq;
+    use crate::prelude::*;
+    use crate::tests::json;
+
+    fn render(path: &[ValueKey]) -> String {
+        path.iter().map(|key| format!("/{}", key)).collect()
+    }
+
+    #[derive(Default)]
+    struct Trace {
+        events: Vec<String>,
+        skip: Option<String>,
+        stop: Option<String>,
+    }
+
+    impl ValueVisitor for Trace {
+        fn enter(&mut self, path: &[ValueKey], _: &Value) -> Visit {
+            let path = render(path);
+            self.events.push(format!("enter {}", path));
+            if self.stop.as_ref() == Some(&path) {
+                Visit::Stop
+            } else if self.skip.as_ref() == Some(&path) {
+                Visit::SkipChildren
+            } else {
+                Visit::Continue
+            }
+        }
+
+        fn leave(&mut self, path: &[ValueKey], _: &Value) -> Visit {
+            self.events.push(format!("leave {}", render(path)));
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn test_order_and_control() {
+        let value = json(r#"{"b": [1, 2], "a": {"c": true}}"#);
+
+        let mut trace = Trace::default();
+        assert_eq!(value.visit(&mut trace), Visit::Continue);
+        assert_eq!(
+            trace.events,
+            vec![
+                "enter ",
+                "enter /a",
+                "enter /a/c",
+                "leave /a/c",
+                "leave /a",
+                "enter /b",
+                "enter /b/0",
+                "leave /b/0",
+                "enter /b/1",
+                "leave /b/1",
+                "leave /b",
+                "leave ",
+            ]
+        );
+
+        let mut trace = Trace {
+            skip: Some("/a".to_string()),
+            stop: Some("/b/0".to_string()),
+            ..Trace::default()
+        };
+        assert_eq!(value.visit(&mut trace), Visit::Stop);
+        assert_eq!(
+            trace.events,
+            vec!["enter ", "enter /a", "leave /a", "enter /b", "enter /b/0"]
+        );
+    }
+
+    #[derive(Default)]
+    struct Statistics {
+        strings: usize,
+        numbers: usize,
+        containers: usize,
+        depth: usize,
+    }
+
+    impl ValueVisitor for Statistics {
+        fn visit_string(&mut self, path: &[ValueKey], _: &StringB) -> Visit {
+            self.strings += 1;
+            self.depth = self.depth.max(path.len());
+            Visit::Continue
+        }
+
+        fn visit_number(&mut self, path: &[ValueKey], _: &Number) -> Visit {
+            self.numbers += 1;
+            self.depth = self.depth.max(path.len());
+            Visit::Continue
+        }
+
+        fn leave_array(&mut self, _: &[ValueKey], _: &Array) -> Visit {
+            self.containers += 1;
+            Visit::Continue
+        }
+
+        fn leave_object(&mut self, _: &[ValueKey], _: &Object) -> Visit {
+            self.containers += 1;
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn test_variant_hooks() {
+        let value = json(r#"{"a": [1, "x", {"b": 2.5}], "c": "y", "d": null}"#);
+        let mut statistics = Statistics::default();
+        value.visit(&mut statistics);
+
+        assert_eq!(statistics.strings, 2);
+        assert_eq!(statistics.numbers, 2);
+        assert_eq!(statistics.containers, 3);
+        assert_eq!(statistics.depth, 3);
+    }
+
+    struct Redact;
+
+    impl ValueVisitorMut for Redact {
+        fn enter(&mut self, path: &[ValueKey], value: &mut Value) -> Visit {
+            if path.last() == Some(&ValueKey::from("secret")) {
+                *value = "***".to_value();
+                return Visit::SkipChildren;
+            }
+            if let Value::Number(number) = value {
+                return self.visit_number(path, number);
+            }
+            Visit::Continue
+        }
+
+        fn visit_number(&mut self, _: &[ValueKey], value: &mut Number) -> Visit {
+            *value = Number::from(value.to_f64().unwrap() * 2.0);
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        let mut value = json(r#"{"a": [1, {"secret": {"key": 1}}], "secret": "x", "n": 2}"#);
+        value.visit_mut(&mut Redact);
+
+        assert_value_eq!(
+            value,
+            json(r#"{"a": [2, {"secret": "***"}], "secret": "***", "n": 4}"#)
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        let value = json(r#"{"a": [1, 2, {"b": 3}], "c": "x"}"#);
+
+        let sum = value.fold(0.0, |sum, _, value| match value {
+            Value::Number(number) => sum + number.to_f64().unwrap(),
+            _ => sum,
+        });
+        assert_eq!(sum, 6.0);
+
+        let paths = value.fold(Vec::new(), |mut paths, path, value| {
+            if !value.is_array() && !value.is_object() {
+                paths.push(render(path));
+            }
+            paths
+        });
+        assert_eq!(paths, vec!["/a/0", "/a/1", "/a/2/b", "/c"]);
+    }
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "jmespath")]
+//! Runs the official JMESPath compliance suite found in `tests/jmespath`.
+
+use std::fs;
+use std::path::Path;
+use valu3::prelude::*;
+
+fn error_name(error: &Error) -> &'static str {
+    match error {
+        Error::JmesPath(JmesPathError::Syntax(_)) => "syntax",
+        Error::JmesPath(JmesPathError::InvalidType(_)) => "invalid-type",
+        Error::JmesPath(JmesPathError::InvalidValue(_)) => "invalid-value",
+        Error::JmesPath(JmesPathError::InvalidArity(_)) => "invalid-arity",
+        Error::JmesPath(JmesPathError::UnknownFunction(_)) => "unknown-function",
+        _ => "other",
+    }
+}
+
+fn equivalent(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => lhs.to_f64() == rhs.to_f64(),
+        (Value::Array(lhs), Value::Array(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .values
+                    .iter()
+                    .zip(rhs.values.iter())
+                    .all(|(lhs, rhs)| equivalent(lhs, rhs))
+        }
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(key, lhs)| match rhs.get(key) {
+                    Some(rhs) => equivalent(lhs, rhs),
+                    None => false,
+                })
+        }
+        (lhs, rhs) => lhs == rhs,
+    }
+}
+
+fn run_file(path: &Path) -> Vec<String> {
+    let source = fs::read_to_string(path).unwrap();
+    let suites = Value::json_to_value(&source).unwrap();
+    let file = path.file_name().unwrap().to_string_lossy();
+    let mut failures = Vec::new();
+
+    for suite in suites.as_array().unwrap().values.iter() {
+        let given = suite.get("given").unwrap();
+
+        for case in suite.get("cases").unwrap().as_array().unwrap().values.iter() {
+            let expression = case.get("expression").unwrap().as_string();
+            let outcome = JmesPath::compile(&expression).and_then(|path| path.search(given));
+
+            if let Some(expected) = case.get("error") {
+                let expected = expected.as_string();
+                match outcome {
+                    Err(error) if error_name(&error) == expected => {}
+                    other => failures.push(format!(
+                        "{}: `{}` expected {} error, got {:?}",
+                        file, expression, expected, other
+                    )),
+                }
+            } else if let Some(expected) = case.get("result") {
+                match outcome {
+                    Ok(result) if equivalent(&result, expected) => {}
+                    other => failures.push(format!(
+                        "{}: `{}` expected {}, got {:?}",
+                        file,
+                        expression,
+                        expected.to_json(JsonMode::Inline),
+                        other
+                    )),
+                }
+            }
+            // Benchmark cases carry no expectation; some of them are not even valid expressions.
+        }
+    }
+
+    failures
+}
+
+#[test]
+fn compliance() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/jmespath");
+    let mut paths = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let failures = paths
+        .iter()
+        .flat_map(|path| run_file(path))
+        .collect::<Vec<_>>();
+
+    assert!(
+        failures.is_empty(),
+        "{} failing cases:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,294 @@
+//! The `#[attr(...)]` helper attributes shared by the derives.
+//!
+//! Containers accept `rename_all = "..."`, and enums `tag = "..."`, `content = "..."` and
+//! `untagged`, which select how variants with data are represented. Fields accept `rename = "..."`, `skip`,
+//! `skip_serializing_if = "path"`, `default`, `default = "path"`, `alias = "..."`, which
+//! may repeat, `flatten`, which merges the members of the field into the object of its
+//! struct, and `to_with = "path"` and `from_with = "path"`, which convert the field with
+//! functions of the signatures of `to_value` and `from_value`, or `with = "module"` for both
+//! functions of a module. Enum variants accept `rename` and `alias`.
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{parse_quote, Attribute, ExprPath, Ident, LitStr, Path};
+
+/// The naming conventions `rename_all` converts field and variant names to.
+#[derive(Clone, Copy)]
+pub(crate) enum RenameRule {
+    Camel,
+    Snake,
+    Kebab,
+    ScreamingSnake,
+}
+
+impl RenameRule {
+    fn parse(rule: &str) -> Option<Self> {
+        match rule {
+            "camelCase" => Some(RenameRule::Camel),
+            "snake_case" => Some(RenameRule::Snake),
+            "kebab-case" => Some(RenameRule::Kebab),
+            "SCREAMING_SNAKE_CASE" => Some(RenameRule::ScreamingSnake),
+            _ => None,
+        }
+    }
+
+    /// Converts a snake_case field name.
+    fn apply_to_field(&self, name: &str) -> String {
+        match self {
+            RenameRule::Camel => {
+                let mut renamed = String::new();
+                let mut upper = false;
+                for c in name.chars() {
+                    if c == '_' {
+                        upper = !renamed.is_empty();
+                    } else if upper {
+                        renamed.push(c.to_ascii_uppercase());
+                        upper = false;
+                    } else {
+                        renamed.push(c);
+                    }
+                }
+                renamed
+            }
+            RenameRule::Snake => name.to_string(),
+            RenameRule::Kebab => name.replace('_', "-"),
+            RenameRule::ScreamingSnake => name.to_ascii_uppercase(),
+        }
+    }
+
+    /// Converts a PascalCase variant name.
+    fn apply_to_variant(&self, name: &str) -> String {
+        let mut snake = String::new();
+        for (index, c) in name.chars().enumerate() {
+            if c.is_ascii_uppercase() && index > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+
+        match self {
+            RenameRule::Camel => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Snake => snake,
+            RenameRule::Kebab => snake.replace('_', "-"),
+            RenameRule::ScreamingSnake => snake.to_ascii_uppercase(),
+        }
+    }
+}
+
+/// How the variants of an enum are told apart in its representation.
+pub(crate) enum Tagging<'a> {
+    /// `"Unit"` or `{"Variant": content}`.
+    External,
+    /// `{"tag": "Variant", ...fields}`. A newtype variant must hold a `ToObjectBehavior` type.
+    Internal { tag: &'a str },
+    /// `{"tag": "Variant", "content": content}`.
+    Adjacent { tag: &'a str, content: &'a str },
+    /// The content alone, read as the first variant that accepts it.
+    Untagged,
+}
+
+/// The attributes of a struct or enum.
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = ContainerAttrs::default();
+        let mut content_span = None;
+        let mut untagged_span = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("attr")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule = meta.value()?.parse::<LitStr>()?;
+                    let message = format!(
+                        "unknown rename_all rule `{}`, expected one of `camelCase`, \
+                         `snake_case`, `kebab-case` or `SCREAMING_SNAKE_CASE`",
+                        rule.value()
+                    );
+                    container.rename_all = Some(
+                        RenameRule::parse(&rule.value())
+                            .ok_or_else(|| syn::Error::new(rule.span(), message))?,
+                    );
+                } else if meta.path.is_ident("tag") {
+                    container.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("content") {
+                    content_span = Some(meta.path.span());
+                    container.content = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("untagged") {
+                    untagged_span = Some(meta.path.span());
+                    container.untagged = true;
+                } else {
+                    return Err(unknown(&meta, "container"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if let (Some(span), true) = (
+            untagged_span,
+            container.tag.is_some() || container.content.is_some(),
+        ) {
+            return Err(syn::Error::new(
+                span,
+                "`untagged` cannot be combined with `tag` or `content`",
+            ));
+        }
+        if let (Some(span), None) = (content_span, &container.tag) {
+            return Err(syn::Error::new(span, "`content` requires `tag`"));
+        }
+        Ok(container)
+    }
+
+    pub fn tagging(&self) -> Tagging<'_> {
+        match (&self.tag, &self.content, self.untagged) {
+            (Some(tag), Some(content), _) => Tagging::Adjacent { tag, content },
+            (Some(tag), None, _) => Tagging::Internal { tag },
+            (None, _, true) => Tagging::Untagged,
+            (None, _, false) => Tagging::External,
+        }
+    }
+}
+
+/// Returns the error of an attribute not accepted on a `kind` of item.
+fn unknown(meta: &ParseNestedMeta, kind: &str) -> syn::Error {
+    let name = meta
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    meta.error(format!("unknown {} attribute `{}`", kind, name))
+}
+
+/// How a field missing from an object is filled in.
+pub(crate) enum FieldDefault {
+    Trait,
+    Path(ExprPath),
+}
+
+/// The attributes of a field or of an enum variant.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub skip_serializing_if: Option<ExprPath>,
+    pub default: Option<FieldDefault>,
+    pub aliases: Vec<String>,
+    pub flatten: bool,
+    pub to_with: Option<ExprPath>,
+    pub from_with: Option<ExprPath>,
+}
+
+impl FieldAttrs {
+    /// Parses the attributes of a field.
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        Self::parse_item(attrs, false)
+    }
+
+    /// Parses the attributes of an enum variant, which accepts `rename` and `alias` only.
+    pub fn parse_variant(attrs: &[Attribute]) -> syn::Result<Self> {
+        Self::parse_item(attrs, true)
+    }
+
+    fn parse_item(attrs: &[Attribute], variant: bool) -> syn::Result<Self> {
+        let mut field = FieldAttrs::default();
+        // Whether the converters come from `with`, to tell a conflict from a duplicate.
+        let mut with = false;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("attr")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    field.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("alias") {
+                    field.aliases.push(meta.value()?.parse::<LitStr>()?.value());
+                } else if variant {
+                    return Err(unknown(&meta, "variant"));
+                } else if meta.path.is_ident("skip") {
+                    field.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                } else if meta.path.is_ident("with") {
+                    let module = meta.value()?.parse::<LitStr>()?.parse::<Path>()?;
+                    if with {
+                        return Err(meta.error("duplicate `with`"));
+                    }
+                    if field.to_with.is_some() || field.from_with.is_some() {
+                        return Err(
+                            meta.error("`with` cannot be combined with `to_with` or `from_with`")
+                        );
+                    }
+                    with = true;
+                    field.to_with = Some(parse_quote!(#module::to_value));
+                    field.from_with = Some(parse_quote!(#module::from_value));
+                } else if meta.path.is_ident("to_with") {
+                    if with {
+                        return Err(meta.error("`to_with` cannot be combined with `with`"));
+                    }
+                    if field.to_with.is_some() {
+                        return Err(meta.error("duplicate `to_with`"));
+                    }
+                    field.to_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("from_with") {
+                    if with {
+                        return Err(meta.error("`from_with` cannot be combined with `with`"));
+                    }
+                    if field.from_with.is_some() {
+                        return Err(meta.error("duplicate `from_with`"));
+                    }
+                    field.from_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("skip_serializing_if") {
+                    let path = meta.value()?.parse::<LitStr>()?;
+                    field.skip_serializing_if = Some(path.parse()?);
+                } else if meta.path.is_ident("default") {
+                    field.default = if meta.input.peek(syn::Token![=]) {
+                        let path = meta.value()?.parse::<LitStr>()?;
+                        Some(FieldDefault::Path(path.parse()?))
+                    } else {
+                        Some(FieldDefault::Trait)
+                    };
+                } else {
+                    return Err(unknown(&meta, "field"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(field)
+    }
+
+    /// Returns the key of a field in the object of its struct.
+    pub fn key(&self, ident: &Ident, container: &ContainerAttrs) -> String {
+        let name = ident.unraw().to_string();
+        match (&self.rename, container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_field(&name),
+            (None, None) => name,
+        }
+    }
+
+    /// Returns the key of a variant in the representation of its enum.
+    pub fn variant_key(&self, ident: &Ident, container: &ContainerAttrs) -> String {
+        let name = ident.unraw().to_string();
+        match (&self.rename, container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_variant(&name),
+            (None, None) => name,
+        }
+    }
+
+    /// Returns whether a field may be missing from an object: it has a default, or it is left
+    /// out when empty.
+    pub fn is_optional(&self) -> bool {
+        self.default.is_some() || self.skip_serializing_if.is_some()
+    }
+}
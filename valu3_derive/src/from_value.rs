@@ -0,0 +1,575 @@
+//! The code reading a type from a `Value`, shared by the `FromValue` derive, which returns
+//! `None` on failure, and the `TryFromValue` derive, which returns an error placed at the path
+//! of the value that failed.
+use crate::attr::{ContainerAttrs, FieldAttrs, FieldDefault, Tagging};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, ExprPath, Fields, FieldsNamed, Ident};
+
+/// The trait a derive implements.
+#[derive(Clone, Copy)]
+pub(crate) enum Conversion {
+    FromValue,
+    TryFromValue,
+}
+
+impl Conversion {
+    /// Returns the return type of a conversion.
+    fn output(self) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! { Option<Self> },
+            Conversion::TryFromValue => quote! { Result<Self, Error> },
+        }
+    }
+
+    fn ok(self, item: TokenStream) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! { Some(#item) },
+            Conversion::TryFromValue => quote! { Ok(#item) },
+        }
+    }
+
+    /// Returns the failure of a conversion given a reference to the value that does not fit.
+    fn fail(self, value: TokenStream) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! { None },
+            Conversion::TryFromValue => quote! {
+                Err(Error::from(ConversionError::new(<Self as TryFromValue>::expected(), #value)))
+            },
+        }
+    }
+
+    /// Returns the expression converting `item` to `ty`, which returns early on failure. Errors
+    /// are placed with `at`, such as `at_key("name")`.
+    fn convert(self, ty: &syn::Type, item: TokenStream, at: TokenStream) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! {
+                match <#ty as FromValueBehavior>::from_value(#item) {
+                    Some(item) => item,
+                    None => return None,
+                }
+            },
+            Conversion::TryFromValue => quote! {
+                match <#ty as TryFromValue>::try_from_value(#item) {
+                    Ok(item) => item,
+                    Err(error) => return Err(error #at),
+                }
+            },
+        }
+    }
+
+    /// Returns the expression of a field of type `ty` missing from an object, which returns
+    /// early when the type may not be left out.
+    fn missing(self, ty: &syn::Type, key: &str) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! {
+                match <#ty as FromValueBehavior>::from_missing() {
+                    Some(item) => item,
+                    None => return None,
+                }
+            },
+            Conversion::TryFromValue => quote! {
+                match <#ty as TryFromValue>::try_from_missing() {
+                    Some(item) => item,
+                    None => {
+                        return Err(Error::from(
+                            ConversionError::missing(<#ty as TryFromValue>::expected())
+                                .at_key(#key),
+                        ))
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns the expression converting `item` to `ty` with the function at `path`, which
+    /// returns early on failure, as `convert` does.
+    fn convert_with(
+        self,
+        path: &ExprPath,
+        ty: &syn::Type,
+        item: TokenStream,
+        at: TokenStream,
+    ) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! {
+                match #path(#item) {
+                    Some(item) => item,
+                    None => return None,
+                }
+            },
+            Conversion::TryFromValue => quote! {
+                {
+                    let item: Value = #item;
+                    match #path(item.clone()) {
+                        Some(item) => item,
+                        None => {
+                            return Err(Error::from(ConversionError::new(
+                                short_type_name::<#ty>(),
+                                &item,
+                            )) #at)
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns the expression of a field of type `ty` converted with a function and missing
+    /// from an object, which returns early.
+    fn missing_with(self, ty: &syn::Type, key: &str) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! { return None },
+            Conversion::TryFromValue => quote! {
+                return Err(Error::from(
+                    ConversionError::missing(short_type_name::<#ty>()).at_key(#key),
+                ))
+            },
+        }
+    }
+
+    /// Returns the expression of the keys `ty` reads from an object.
+    fn field_keys(self, ty: &syn::Type) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! { <#ty as FromValueBehavior>::field_keys() },
+            Conversion::TryFromValue => quote! { <#ty as TryFromValue>::field_keys() },
+        }
+    }
+
+    /// Places the errors of a conversion result with `at`.
+    fn nest(self, result: TokenStream, at: TokenStream) -> TokenStream {
+        match self {
+            Conversion::FromValue => result,
+            Conversion::TryFromValue => quote! { #result.map_err(|error| error #at) },
+        }
+    }
+
+    /// Returns the statement returning the result of a conversion if it succeeded.
+    fn attempt(self, result: TokenStream) -> TokenStream {
+        match self {
+            Conversion::FromValue => quote! {
+                if let Some(item) = #result {
+                    return Some(item);
+                }
+            },
+            Conversion::TryFromValue => quote! {
+                if let Ok(item) = #result {
+                    return Ok(item);
+                }
+            },
+        }
+    }
+}
+
+/// Returns the keys named fields are read from, aliases included, and the types of the
+/// flattened fields that read named keys.
+fn read_keys<'a>(
+    fields: &'a FieldsNamed,
+    container: &ContainerAttrs,
+) -> syn::Result<(Vec<String>, Vec<&'a syn::Type>)> {
+    let mut keys = Vec::new();
+    let mut flattened = Vec::new();
+
+    for field in fields.named.iter() {
+        let field_name = match field.ident.as_ref() {
+            Some(name) => name,
+            None => continue,
+        };
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        // The keys of a type converted with a function are unknown.
+        if attrs.flatten {
+            if attrs.from_with.is_none() {
+                flattened.push(&field.ty);
+            }
+        } else {
+            keys.push(attrs.key(field_name, container));
+            keys.extend(attrs.aliases.iter().cloned());
+        }
+    }
+
+    Ok((keys, flattened))
+}
+
+/// Returns the body of `field_keys` for a struct with named fields: the keys of its fields and
+/// those of its flattened fields.
+fn field_keys(
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+    conversion: Conversion,
+) -> syn::Result<TokenStream> {
+    let (keys, flattened) = read_keys(fields, container)?;
+    let flattened = flattened.into_iter().map(|ty| conversion.field_keys(ty));
+    Ok(quote! {
+        let mut keys: Vec<String> = vec![#(#keys.to_string()),*];
+        #(keys.extend(#flattened);)*
+        keys
+    })
+}
+
+/// Returns the `name: value` initializers of named fields read from a `map`. A field that fails
+/// to convert, or is missing without a default, returns early; an `Undefined` field counts as
+/// missing. A flattened field reads the members no other field reads.
+fn named_fields(
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+    conversion: Conversion,
+) -> syn::Result<Vec<TokenStream>> {
+    let mut from_value_exprs = Vec::new();
+    let (read_keys, flattened) = read_keys(fields, container)?;
+
+    for field in fields.named.iter() {
+        let field_name = match field.ident.as_ref() {
+            Some(name) => name,
+            None => continue,
+        };
+        let field_type = &field.ty;
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let key = attrs.key(field_name, container);
+
+        if attrs.flatten && !attrs.skip {
+            // The keys read by the fields next to this one.
+            let others = flattened
+                .iter()
+                .filter(|ty| !std::ptr::eq(**ty, field_type))
+                .map(|ty| conversion.field_keys(ty));
+            let item = match &attrs.from_with {
+                Some(path) => conversion.convert_with(path, field_type, quote! { rest }, quote! {}),
+                None => conversion.convert(field_type, quote! { rest }, quote! {}),
+            };
+            from_value_exprs.push(quote! {
+                #field_name: {
+                    let mut others: Vec<String> = Vec::new();
+                    #(others.extend(#others);)*
+                    let mut rest = Object::ordered();
+                    for (key, item) in map.iter() {
+                        let name = key.to_string();
+                        if ![#(#read_keys),*].contains(&name.as_str()) && !others.contains(&name) {
+                            rest.insert(key, item.clone());
+                        }
+                    }
+                    let rest = Value::Object(rest);
+                    #item
+                }
+            });
+            continue;
+        }
+
+        let default = match &attrs.default {
+            Some(FieldDefault::Trait) => quote! { Default::default() },
+            Some(FieldDefault::Path(path)) => quote! { #path() },
+            None if attrs.skip => quote! { Default::default() },
+            None if attrs.from_with.is_some() => conversion.missing_with(field_type, &key),
+            None => conversion.missing(field_type, &key),
+        };
+        if attrs.skip {
+            from_value_exprs.push(quote! { #field_name: #default });
+            continue;
+        }
+
+        // The key comes first, then the aliases it is also accepted under.
+        let mut keys = vec![key];
+        keys.extend(attrs.aliases.iter().cloned());
+        let at = quote! { .at_key(key) };
+        let item = match &attrs.from_with {
+            Some(path) => conversion.convert_with(path, field_type, quote! { item.clone() }, at),
+            None => conversion.convert(field_type, quote! { item.clone() }, at),
+        };
+
+        from_value_exprs.push(quote! {
+            #field_name: {
+                match [#(#keys),*].iter().find_map(|key| {
+                    map.get(*key)
+                        .filter(|item| !item.is_undefined())
+                        .map(|item| (*key, item))
+                }) {
+                    Some((key, item)) => #item,
+                    None => #default,
+                }
+            }
+        });
+    }
+
+    Ok(from_value_exprs)
+}
+
+/// Returns the expression reading a struct, or the data of a variant, built with `constructor`
+/// from the value named `value`.
+fn fields_body(
+    fields: &Fields,
+    container: &ContainerAttrs,
+    constructor: TokenStream,
+    value: &Ident,
+    conversion: Conversion,
+) -> syn::Result<TokenStream> {
+    let fail = conversion.fail(quote! { &#value });
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let from_value_exprs = named_fields(fields, container, conversion)?;
+            let item = conversion.ok(quote! { #constructor { #(#from_value_exprs),* } });
+            quote! {
+                match &#value {
+                    Value::Object(map) => #item,
+                    _ => #fail,
+                }
+            }
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let item = conversion.convert(&fields.unnamed[0].ty, quote! { #value }, quote! {});
+            conversion.ok(quote! { #constructor(#item) })
+        }
+        Fields::Unnamed(fields) => {
+            let count = fields.unnamed.len();
+            let items = fields.unnamed.iter().enumerate().map(|(index, field)| {
+                conversion.convert(
+                    &field.ty,
+                    quote! { items.values[#index].clone() },
+                    quote! { .at_index(#index) },
+                )
+            });
+            let item = conversion.ok(quote! { #constructor(#(#items),*) });
+            quote! {
+                match &#value {
+                    Value::Array(items) if items.values.len() == #count => #item,
+                    _ => #fail,
+                }
+            }
+        }
+        Fields::Unit => {
+            let item = conversion.ok(quote! { #constructor });
+            quote! {
+                if #value.is_null() {
+                    #item
+                } else {
+                    #fail
+                }
+            }
+        }
+    })
+}
+
+/// Returns the implementation of `FromValueBehavior` or `TryFromValue` for a struct or enum.
+pub(crate) fn derive(ast: DeriveInput, conversion: Conversion) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::parse(&ast.attrs)?;
+    let target_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let value = Ident::new("value", proc_macro2::Span::call_site());
+    let content = Ident::new("content", proc_macro2::Span::call_site());
+    let output = conversion.output();
+    let fail = conversion.fail(quote! { &value });
+
+    let body = match &ast.data {
+        Data::Struct(data_struct) => fields_body(
+            &data_struct.fields,
+            &container,
+            quote! { Self },
+            &value,
+            conversion,
+        )?,
+        Data::Enum(data_enum) => {
+            let tagging = container.tagging();
+            let mut unit_arms = Vec::new();
+            let mut data_arms = Vec::new();
+            let mut untagged_attempts = Vec::new();
+
+            // Where the content of a data variant sits, for errors.
+            let content_at = match tagging {
+                Tagging::External => quote! { .at_key(&name) },
+                Tagging::Adjacent { content, .. } => quote! { .at_key(#content) },
+                Tagging::Internal { .. } | Tagging::Untagged => quote! {},
+            };
+
+            for variant in data_enum.variants.iter() {
+                let variant_name = &variant.ident;
+                let attrs = FieldAttrs::parse_variant(&variant.attrs)?;
+                let mut keys = vec![attrs.variant_key(variant_name, &container)];
+                keys.extend(attrs.aliases.iter().cloned());
+
+                if let Fields::Unit = variant.fields {
+                    let item = conversion.ok(quote! { #target_name::#variant_name });
+                    unit_arms.push(quote! {
+                        #(#keys)|* => #item,
+                    });
+                    untagged_attempts.push(quote! {
+                        if value.is_null() {
+                            return #item;
+                        }
+                    });
+                    continue;
+                }
+                if let (Tagging::Internal { .. }, Fields::Unnamed(fields)) =
+                    (&tagging, &variant.fields)
+                {
+                    if fields.unnamed.len() > 1 {
+                        return Err(syn::Error::new_spanned(
+                            variant,
+                            "internally tagged enums cannot have tuple variants",
+                        ));
+                    }
+                }
+
+                // Reads the variant from its content, in a closure so that failing returns
+                // from the attempt only.
+                let decode = fields_body(
+                    &variant.fields,
+                    &ContainerAttrs::default(),
+                    quote! { #target_name::#variant_name },
+                    &content,
+                    conversion,
+                )?;
+                let decode = quote! {
+                    (|content: Value| -> #output { #decode })
+                };
+
+                let result = conversion.nest(quote! { #decode(content) }, content_at.clone());
+                data_arms.push(quote! {
+                    #(#keys)|* => #result,
+                });
+                untagged_attempts.push(conversion.attempt(quote! { #decode(value.clone()) }));
+            }
+
+            let has_data = !data_arms.is_empty();
+            let tag_fail = |tag: &str| match conversion {
+                Conversion::FromValue => quote! { None },
+                Conversion::TryFromValue => quote! {
+                    Err(Error::from(
+                        ConversionError::new(<Self as TryFromValue>::expected(), &tag_value)
+                            .at_key(#tag),
+                    ))
+                },
+            };
+
+            match tagging {
+                Tagging::External => {
+                    let data = has_data.then(|| {
+                        quote! {
+                            Value::Object(map) if map.len() == 1 => {
+                                let (name, content) = match map.iter().next() {
+                                    Some((name, content)) => (name.to_string(), content.clone()),
+                                    None => return #fail,
+                                };
+                                match name.as_str() {
+                                    #(#data_arms)*
+                                    _ => #fail,
+                                }
+                            }
+                        }
+                    });
+                    quote! {
+                        match &value {
+                            Value::String(name) => match name.as_str() {
+                                #(#unit_arms)*
+                                _ => #fail,
+                            },
+                            #data
+                            _ => #fail,
+                        }
+                    }
+                }
+                // The content of a variant is the whole object, tag included.
+                Tagging::Internal { tag } => {
+                    let tag_fail = tag_fail(tag);
+                    quote! {
+                        let tag_value = match &value {
+                            Value::Object(map) => map.get(#tag).cloned().unwrap_or(Value::Undefined),
+                            _ => return #fail,
+                        };
+                        let name = match &tag_value {
+                            Value::String(name) => name.as_string(),
+                            _ => return #tag_fail,
+                        };
+                        #[allow(unused_variables)]
+                        let content = value;
+                        match name.as_str() {
+                            #(#unit_arms)*
+                            #(#data_arms)*
+                            _ => #tag_fail,
+                        }
+                    }
+                }
+                Tagging::Adjacent { tag, content } => {
+                    let tag_fail = tag_fail(tag);
+                    quote! {
+                        let map = match &value {
+                            Value::Object(map) => map,
+                            _ => return #fail,
+                        };
+                        let tag_value = map.get(#tag).cloned().unwrap_or(Value::Undefined);
+                        let name = match &tag_value {
+                            Value::String(name) => name.as_string(),
+                            _ => return #tag_fail,
+                        };
+                        #[allow(unused_variables)]
+                        let content = map.get(#content).cloned().unwrap_or(Value::Null);
+                        match name.as_str() {
+                            #(#unit_arms)*
+                            #(#data_arms)*
+                            _ => #tag_fail,
+                        }
+                    }
+                }
+                Tagging::Untagged => quote! {
+                    #(#untagged_attempts)*
+                    #fail
+                },
+            }
+        }
+        Data::Union(data_union) => {
+            let message = match conversion {
+                Conversion::FromValue => "FromValue cannot be derived for unions",
+                Conversion::TryFromValue => "TryFromValue cannot be derived for unions",
+            };
+            return Err(syn::Error::new(data_union.union_token.span, message));
+        }
+    };
+
+    let field_keys = match &ast.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => {
+                let keys = field_keys(fields, &container, conversion)?;
+                Some(quote! {
+                    fn field_keys() -> Vec<String> {
+                        #keys
+                    }
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    Ok(match conversion {
+        Conversion::FromValue => {
+            // Enums keep being primitive types, as they were before data variants.
+            let primitive = matches!(ast.data, Data::Enum(_)).then(|| {
+                quote! {
+                    impl #impl_generics PrimitiveType for #target_name #ty_generics #where_clause {}
+                }
+            });
+            quote! {
+                #primitive
+
+                impl #impl_generics FromValueBehavior for #target_name #ty_generics #where_clause {
+                    type Item = Self;
+
+                    fn from_value(value: Value) -> Option<Self> {
+                        #body
+                    }
+
+                    #field_keys
+                }
+            }
+        }
+        Conversion::TryFromValue => quote! {
+            impl #impl_generics TryFromValue for #target_name #ty_generics #where_clause {
+                fn try_from_value(value: Value) -> Result<Self, Error> {
+                    #body
+                }
+
+                #field_keys
+            }
+        },
+    })
+}
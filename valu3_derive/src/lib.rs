@@ -1,98 +1,273 @@
 extern crate proc_macro;
+mod attr;
+mod from_value;
+mod validate;
+
+use attr::{ContainerAttrs, FieldAttrs, Tagging};
+use from_value::Conversion;
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Generics, Meta, Variant};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, FieldsNamed, Generics, Ident, Variant};
 
 #[proc_macro_derive(ToValue, attributes(attr))]
 pub fn to_value_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_to_value(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
+fn expand_to_value(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = input.ident;
     let generics = input.generics;
+    let container = ContainerAttrs::parse(&input.attrs)?;
 
-    let to_value_impl = match input.data {
-        Data::Struct(data) => to_value_struct_impl(name, generics, data.fields),
-        Data::Enum(data) => to_value_enum_impl(name, generics, data.variants),
-        Data::Union(_) => panic!("ToValueBehavior cannot be derived for unions"),
-    };
+    match input.data {
+        Data::Struct(data) => to_value_struct_impl(name, generics, data.fields, &container),
+        Data::Enum(data) => to_value_enum_impl(name, generics, data.variants, &container),
+        Data::Union(data) => Err(syn::Error::new(
+            data.union_token.span,
+            "ToValue cannot be derived for unions",
+        )),
+    }
+}
 
-    let expanded = quote! {
-        #to_value_impl
-    };
+/// Returns the statements inserting named fields into a `map`, given the expression of a
+/// reference to each field.
+fn to_value_fields<F>(
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+    access: F,
+) -> syn::Result<Vec<proc_macro2::TokenStream>>
+where
+    F: Fn(&Ident) -> proc_macro2::TokenStream,
+{
+    let mut inserts = Vec::new();
+    for field in fields.named.iter() {
+        let name = match field.ident.as_ref() {
+            Some(name) => name,
+            None => continue,
+        };
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
 
-    TokenStream::from(expanded)
+        let field_name = attrs.key(name, container);
+        let field = access(name);
+        let value = match &attrs.to_with {
+            Some(path) => quote! { #path(#field) },
+            None => quote! { ToValueBehavior::to_value(#field) },
+        };
+        // A flattened field adds its members, if it is an object.
+        let insert = if attrs.flatten {
+            quote! {
+                if let Value::Object(object) = #value {
+                    for (key, value) in object.iter() {
+                        map.insert(key, value.clone());
+                    }
+                }
+            }
+        } else {
+            quote! {
+                map.insert(#field_name, #value);
+            }
+        };
+        inserts.push(match &attrs.skip_serializing_if {
+            Some(path) => quote! {
+                if !#path(#field) {
+                    #insert
+                }
+            },
+            None => insert,
+        });
+    }
+    Ok(inserts)
 }
 
 fn to_value_struct_impl(
     name: syn::Ident,
     generics: Generics,
     fields: Fields,
-) -> proc_macro2::TokenStream {
+    container: &ContainerAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let field_transforms = match fields {
-        Fields::Named(fields) => fields
-            .named
-            .iter()
-            .map(|field| {
-                let name = match field.ident.as_ref() {
-                    Some(name) => name,
-                    None => panic!("ToValueBehavior cannot be derived for unnamed fields"),
-                };
-                let field_name = format!("{}", name);
-                quote! {
-                    map.insert(#field_name.to_string(), self.#name.clone().into());
-                }
-            })
-            .collect::<Vec<_>>(),
-        Fields::Unnamed(fields) => fields
-            .unnamed
-            .iter()
-            .enumerate()
-            .map(|(index, _field)| {
-                let index = syn::Index::from(index);
-                quote! {
-                    Value::from(self.#index.clone())
+        Fields::Named(fields) => {
+            to_value_fields(&fields, container, |name| quote! { &self.#name })?
+        }
+        // A newtype is its field, other tuple structs are arrays of their fields.
+        Fields::Unnamed(fields) => {
+            let value = if fields.unnamed.len() == 1 {
+                quote! { ToValueBehavior::to_value(&self.0) }
+            } else {
+                let items = (0..fields.unnamed.len()).map(syn::Index::from);
+                quote! { Value::from(vec![#(ToValueBehavior::to_value(&self.#items)),*]) }
+            };
+            return Ok(quote! {
+                impl #impl_generics ToValueBehavior for #name #ty_generics #where_clause {
+                    fn to_value(&self) -> Value {
+                        #value
+                    }
                 }
-            })
-            .collect::<Vec<_>>(),
+            });
+        }
         Fields::Unit => {
-            return quote! {
+            return Ok(quote! {
                 impl #impl_generics ToValueBehavior for  #name #ty_generics #where_clause {
                     fn to_value(&self) -> Value {
                         Value::Null
                     }
                 }
-            }
+            })
         }
     };
 
-    quote! {
+    Ok(quote! {
+        impl #impl_generics ToObjectBehavior for #name #ty_generics #where_clause {
+            fn to_object(&self) -> Object {
+                let mut map = Object::ordered();
+                #(#field_transforms)*
+                map
+            }
+        }
+
         impl #impl_generics ToValueBehavior  for #name #ty_generics #where_clause {
             fn to_value(&self) -> Value {
-                let mut map: std::collections::HashMap<String, Value>= std::collections::HashMap::new();
-                #(#field_transforms)*
-                Value::from(map)
+                Value::Object(ToObjectBehavior::to_object(self))
             }
         }
-    }
+    })
 }
 
 fn to_value_enum_impl(
     name: syn::Ident,
     generics: Generics,
     variants: syn::punctuated::Punctuated<Variant, syn::Token![,]>,
-) -> proc_macro2::TokenStream {
+    container: &ContainerAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let tagging = container.tagging();
 
-    let variant_transforms = variants.iter().map(|variant| {
+    let mut variant_transforms = Vec::new();
+    for variant in variants.iter() {
         let variant_name = &variant.ident;
-        quote! {
-            #name::#variant_name => Value::from(stringify!(#variant_name)),
-        }
-    });
+        let key = FieldAttrs::parse_variant(&variant.attrs)?.variant_key(variant_name, container);
 
-    quote! {
+        // The pattern binding the fields of the variant, and the value of its content. The
+        // content of an internally tagged enum is an `Object`, to which the tag is added.
+        let internal = matches!(tagging, Tagging::Internal { .. });
+        let (pattern, content) = match &variant.fields {
+            Fields::Unit => (quote! { #name::#variant_name }, None),
+            Fields::Unnamed(fields) => {
+                let bindings = (0..fields.unnamed.len())
+                    .map(|index| format_ident!("field{}", index))
+                    .collect::<Vec<_>>();
+                let content = if bindings.len() == 1 {
+                    if internal {
+                        let span = fields.unnamed[0].ty.span();
+                        quote_spanned! {span=> ToObjectBehavior::to_object(field0) }
+                    } else {
+                        quote! { ToValueBehavior::to_value(field0) }
+                    }
+                } else {
+                    if internal {
+                        return Err(syn::Error::new_spanned(
+                            variant,
+                            "internally tagged enums cannot have tuple variants",
+                        ));
+                    }
+                    quote! {
+                        Value::from(vec![#(ToValueBehavior::to_value(#bindings)),*])
+                    }
+                };
+                (
+                    quote! { #name::#variant_name(#(#bindings),*) },
+                    Some(content),
+                )
+            }
+            Fields::Named(fields) => {
+                let mut bindings = Vec::new();
+                for field in fields.named.iter() {
+                    if !FieldAttrs::parse(&field.attrs)?.skip {
+                        bindings.extend(field.ident.as_ref());
+                    }
+                }
+                let inserts =
+                    to_value_fields(fields, &ContainerAttrs::default(), |name| quote! { #name })?;
+                let object = quote! {
+                    {
+                        let mut map = Object::ordered();
+                        #(#inserts)*
+                        map
+                    }
+                };
+                let content = if internal {
+                    object
+                } else {
+                    quote! { Value::Object(#object) }
+                };
+                (
+                    quote! { #name::#variant_name { #(#bindings,)* .. } },
+                    Some(content),
+                )
+            }
+        };
+
+        let value = match (&tagging, content) {
+            (Tagging::External, None) => quote! { Value::from(#key) },
+            (Tagging::External, Some(content)) => quote! {
+                {
+                    let mut map = Object::ordered();
+                    map.insert(#key, #content);
+                    Value::Object(map)
+                }
+            },
+            (Tagging::Internal { tag } | Tagging::Adjacent { tag, .. }, None) => quote! {
+                {
+                    let mut map = Object::ordered();
+                    map.insert(#tag, Value::from(#key));
+                    Value::Object(map)
+                }
+            },
+            // The tag comes first among the members of the content.
+            (Tagging::Internal { tag }, Some(content)) => quote! {
+                {
+                    let object: Object = #content;
+                    let mut map = Object::ordered();
+                    map.insert(#tag, Value::from(#key));
+                    for (key, value) in object.iter() {
+                        map.insert(key, value.clone());
+                    }
+                    Value::Object(map)
+                }
+            },
+            (
+                Tagging::Adjacent {
+                    tag,
+                    content: content_key,
+                },
+                Some(content),
+            ) => quote! {
+                {
+                    let mut map = Object::ordered();
+                    map.insert(#tag, Value::from(#key));
+                    map.insert(#content_key, #content);
+                    Value::Object(map)
+                }
+            },
+            (Tagging::Untagged, None) => quote! { Value::Null },
+            (Tagging::Untagged, Some(content)) => content,
+        };
+
+        variant_transforms.push(quote! {
+            #pattern => #value,
+        });
+    }
+
+    Ok(quote! {
         impl #impl_generics ToValueBehavior for #name #ty_generics #where_clause {
             fn to_value(&self) -> Value {
                 match self {
@@ -100,112 +275,681 @@ fn to_value_enum_impl(
                 }
             }
         }
-    }
+    })
 }
 
-#[proc_macro_derive(FromValue)]
+#[proc_macro_derive(FromValue, attributes(attr))]
 pub fn from_value_derive(input: TokenStream) -> TokenStream {
-    // Parse a `DeriveInput` AST from the input tokens.
     let ast = parse_macro_input!(input as DeriveInput);
+    from_value::derive(ast, Conversion::FromValue)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    // Get the name and fields of the struct being derived.
-    let target_name = &ast.ident;
-    let target_generics = &ast.generics;
-    let (impl_generics, ty_generics, where_clause) = target_generics.split_for_impl();
+#[proc_macro_derive(TryFromValue, attributes(attr))]
+pub fn try_from_value_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    from_value::derive(ast, Conversion::TryFromValue)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    match ast.data {
-        Data::Struct(data_struct) => {
-            // Define a new implementation of the `FromValueBehavior` trait for the struct.
-            let mut field_names = Vec::new();
-            let mut from_value_exprs = Vec::new();
+#[proc_macro_derive(Validate, attributes(attr, validate))]
+pub fn validate_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    validate::derive(ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-            if let Fields::Named(fields) = data_struct.fields {
-                for field in fields.named.iter() {
-                    let field_name = match field.ident.as_ref() {
-                        Some(name) => name,
-                        None => panic!(
-                            "Can only derive FromValueBehavior for a struct with named fields."
-                        ),
-                    };
-                    let field_type = &field.ty;
-
-                    field_names.push(field_name.clone());
-
-                    from_value_exprs.push(quote! {
-                        #field_name: {
-                            let item = match map.get(stringify!(#field_name)) {
-                                Some(item) => item.clone(),
-                                None => return None,
-                            };
-                            match <#field_type as FromValueBehavior>::from_value(item) {
-                                Some(item) => item,
-                                None => return None,
-                            }
-                        }
-                    });
+#[proc_macro_derive(ToSchema, attributes(attr))]
+pub fn to_schema_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_schema(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_to_schema(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = input.ident;
+    let title = name.to_string();
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let description = doc_comment(&input.attrs).map(|description| {
+        quote! {
+            schema.insert("description".to_string(), Value::from(#description));
+        }
+    });
+
+    // Every type parameter appears in the schema, so it must describe itself too.
+    let mut generics = input.generics;
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(ToSchema));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let members = match input.data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => {
+                let members = schema_fields(&fields, &container, None)?;
+                quote! {
+                    schema.extend(#members);
+                }
+            }
+            // A newtype has the schema of its field, other tuple structs are arrays.
+            Fields::Unnamed(fields) => {
+                let content = schema_tuple(&fields);
+                quote! {
+                    schema.insert("allOf".to_string(), Value::from(vec![#content]));
+                }
+            }
+            Fields::Unit => quote! {
+                schema.insert("type".to_string(), Value::from("null"));
+            },
+        },
+        Data::Enum(data) => {
+            let tagging = container.tagging();
+            let mut keys = Vec::new();
+            let mut variants = Vec::new();
+            for variant in data.variants.iter() {
+                let key = FieldAttrs::parse_variant(&variant.attrs)?
+                    .variant_key(&variant.ident, &container);
+                variants.push(schema_variant(variant, &key, &tagging)?);
+                keys.push(key);
+            }
+
+            // The unit variants of an externally tagged enum are their names.
+            let unit = data
+                .variants
+                .iter()
+                .all(|variant| matches!(variant.fields, Fields::Unit));
+            if unit && matches!(tagging, Tagging::External) {
+                quote! {
+                    schema.insert("type".to_string(), Value::from("string"));
+                    schema.insert(
+                        "enum".to_string(),
+                        Value::from(vec![#(Value::from(#keys)),*]),
+                    );
                 }
             } else {
-                panic!("Can only derive FromValueBehavior for a struct with named fields.");
-            }
-
-            let expanded = quote! {
-                impl #impl_generics FromValueBehavior for #target_name #ty_generics #where_clause {
-                    type Item = Self;
-
-                    fn from_value(value: Value) -> Option<Self> {
-                        if let Value::Object(map) = value {
-                            Some(
-                                Self {
-                                    #(#from_value_exprs),*
-                                }
-                            )
-                        } else {
-                            None
-                        }
-                    }
+                quote! {
+                    schema.insert("anyOf".to_string(), Value::from(vec![#(#variants),*]));
                 }
-            };
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "ToSchema cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ToSchema for #name #ty_generics #where_clause {
+            fn schema_with(definitions: &mut SchemaDefinitions) -> Value {
+                let name = definitions.name_for::<Self>();
+                definitions.define(&name, |definitions| {
+                    let mut schema = std::collections::BTreeMap::<String, Value>::new();
+                    schema.insert("title".to_string(), Value::from(#title));
+                    #description
+                    #members
+                    Value::Object(Object::from(schema))
+                })
+            }
+        }
+    })
+}
+
+/// Returns the expression of the members of the schema of named fields, as a `BTreeMap`. The
+/// `tag` of an internally tagged variant, with the key of the variant, comes first.
+fn schema_fields(
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+    tag: Option<(&str, &str)>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut properties = Vec::new();
+    if let Some((tag, key)) = tag {
+        let schema = schema_const(key);
+        properties.push(quote! {
+            properties.insert(#tag.to_string(), #schema);
+            required.push(Value::from(#tag));
+        });
+    }
+    for field in fields.named.iter() {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let field_type = &field.ty;
+        // A field converted with a function may take any value.
+        let (schema, optional_type) = match attrs.to_with {
+            Some(_) => (
+                quote! { Value::Object(Object::default()) },
+                quote! { false },
+            ),
+            None => (
+                quote! { <#field_type as ToSchema>::schema_with(definitions) },
+                quote! { <#field_type as ToSchema>::is_optional() },
+            ),
+        };
+        if attrs.flatten {
+            properties.push(quote! {
+                flattened.push(#schema);
+            });
+            continue;
+        }
+        let field_name = match field.ident.as_ref() {
+            Some(name) => attrs.key(name, container),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "ToSchema cannot be derived for unnamed fields",
+                ))
+            }
+        };
+        let optional = attrs.is_optional();
+        let description = doc_comment(&field.attrs).map(|description| {
+            quote! {
+                if let Value::Object(object) = &mut property {
+                    object.insert("description", Value::from(#description));
+                }
+            }
+        });
+
+        properties.push(quote! {
+            let mut property = #schema;
+            #description
+            properties.insert(#field_name.to_string(), property);
+            if !#optional && !#optional_type {
+                required.push(Value::from(#field_name));
+            }
+        });
+    }
+
+    // Flattened fields make the schema the conjunction of the own members and theirs.
+    Ok(quote! {
+        {
+            let mut properties = std::collections::BTreeMap::<String, Value>::new();
+            let mut required = Vec::<Value>::new();
+            let mut flattened = Vec::<Value>::new();
+            #(#properties)*
+
+            let mut members = std::collections::BTreeMap::<String, Value>::new();
+            members.insert("type".to_string(), Value::from("object"));
+            members.insert("properties".to_string(), Value::Object(Object::from(properties)));
+            if !required.is_empty() {
+                members.insert("required".to_string(), Value::from(required));
+            }
+            if flattened.is_empty() {
+                members
+            } else {
+                flattened.insert(0, Value::Object(Object::from(members)));
+                let mut conjunction = std::collections::BTreeMap::<String, Value>::new();
+                conjunction.insert("allOf".to_string(), Value::from(flattened));
+                conjunction
+            }
+        }
+    })
+}
+
+/// Returns the expression of the schema of unnamed fields: the schema of the field of a
+/// newtype, or an array of the schemas of the fields.
+fn schema_tuple(fields: &syn::FieldsUnnamed) -> proc_macro2::TokenStream {
+    if fields.unnamed.len() == 1 {
+        let field_type = &fields.unnamed[0].ty;
+        return quote! { <#field_type as ToSchema>::schema_with(definitions) };
+    }
+
+    let types = fields.unnamed.iter().map(|field| &field.ty);
+    let len = fields.unnamed.len();
+    quote! {
+        {
+            let mut members = std::collections::BTreeMap::<String, Value>::new();
+            members.insert("type".to_string(), Value::from("array"));
+            members.insert(
+                "prefixItems".to_string(),
+                Value::from(vec![#(<#types as ToSchema>::schema_with(definitions)),*]),
+            );
+            members.insert("items".to_string(), Value::from(false));
+            members.insert("minItems".to_string(), Value::from(#len));
+            Value::Object(Object::from(members))
+        }
+    }
+}
+
+/// Returns the expression of the schema of a value equal to a string.
+fn schema_const(value: &str) -> proc_macro2::TokenStream {
+    quote! {
+        Value::Object(Object::from(std::collections::BTreeMap::from([(
+            "const".to_string(),
+            Value::from(#value),
+        )])))
+    }
+}
+
+/// Returns the expression of the schema of an object with the given members, all required.
+fn schema_object(members: Vec<(&str, proc_macro2::TokenStream)>) -> proc_macro2::TokenStream {
+    let (names, schemas): (Vec<_>, Vec<_>) = members.into_iter().unzip();
+    quote! {
+        {
+            let mut members = std::collections::BTreeMap::<String, Value>::new();
+            members.insert("type".to_string(), Value::from("object"));
+            members.insert(
+                "properties".to_string(),
+                Value::Object(Object::from(std::collections::BTreeMap::<String, Value>::from([
+                    #((#names.to_string(), #schemas)),*
+                ]))),
+            );
+            members.insert("required".to_string(), Value::from(vec![#(Value::from(#names)),*]));
+            Value::Object(Object::from(members))
+        }
+    }
+}
 
-            TokenStream::from(expanded)
+/// Returns the expression of the schema of a variant, in the representation `ToValue` gives
+/// it.
+fn schema_variant(
+    variant: &Variant,
+    key: &str,
+    tagging: &Tagging,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let content = match &variant.fields {
+        Fields::Unit => None,
+        Fields::Unnamed(fields) => {
+            if matches!(tagging, Tagging::Internal { .. }) && fields.unnamed.len() > 1 {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "internally tagged enums cannot have tuple variants",
+                ));
+            }
+            Some(schema_tuple(fields))
         }
-        Data::Enum(data_enum) => {
-            let variants = data_enum.variants;
-            let mut variant_names = Vec::new();
+        Fields::Named(fields) => {
+            let members = schema_fields(fields, &ContainerAttrs::default(), None)?;
+            Some(quote! { Value::Object(Object::from(#members)) })
+        }
+    };
 
-            for variant in variants.iter() {
-                let variant_name = &variant.ident;
-                variant_names.push(variant_name.clone());
+    Ok(match (tagging, content) {
+        (Tagging::External, None) => schema_const(key),
+        (Tagging::External, Some(content)) => schema_object(vec![(key, content)]),
+        (Tagging::Internal { tag } | Tagging::Adjacent { tag, .. }, None) => {
+            schema_object(vec![(tag, schema_const(key))])
+        }
+        // The tag is a member of the content, which holds a struct or a map.
+        (Tagging::Internal { tag }, Some(content)) => match &variant.fields {
+            Fields::Named(fields) => {
+                let members = schema_fields(fields, &ContainerAttrs::default(), Some((tag, key)))?;
+                quote! { Value::Object(Object::from(#members)) }
             }
+            _ => {
+                let tag_object = schema_object(vec![(tag, schema_const(key))]);
+                quote! {
+                    Value::Object(Object::from(std::collections::BTreeMap::from([(
+                        "allOf".to_string(),
+                        Value::from(vec![#tag_object, #content]),
+                    )])))
+                }
+            }
+        },
+        (
+            Tagging::Adjacent {
+                tag,
+                content: content_key,
+            },
+            Some(content),
+        ) => schema_object(vec![(tag, schema_const(key)), (content_key, content)]),
+        (Tagging::Untagged, None) => quote! {
+            Value::Object(Object::from(std::collections::BTreeMap::from([(
+                "type".to_string(),
+                Value::from("null"),
+            )])))
+        },
+        (Tagging::Untagged, Some(content)) => content,
+    })
+}
+
+#[proc_macro_derive(ToTypeScript, attributes(attr))]
+pub fn to_typescript_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_typescript(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-            let expanded = quote! {
-                impl #impl_generics PrimitiveType for #target_name #ty_generics #where_clause {}
+fn expand_to_typescript(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = input.ident;
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let doc = doc_comment(&input.attrs)
+        .map(|doc| ts_doc(&doc, ""))
+        .unwrap_or_default();
 
-                impl #impl_generics FromValueBehavior for #target_name #ty_generics #where_clause {
-                    type Item = Self;
+    let mut generics = input.generics;
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(ToTypeScript));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-                    fn from_value(value: Value) -> Option<Self> {
-                        match value {
-                            Value::String(value) => {
-                                match value.as_str() {
-                                    #(
-                                        v if v == stringify!(#variant_names) => Some(#target_name::#variant_names),
-                                    )*
-                                    _ => None,
-                                }
-                            },
-                            _ => None,
-                        }
+    let body = match input.data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => {
+                let mut properties = Vec::new();
+                for field in fields.named.iter() {
+                    let attrs = FieldAttrs::parse(&field.attrs)?;
+                    if attrs.skip {
+                        continue;
                     }
+                    let ts_type = ts_field_type(field, &attrs);
+                    if attrs.flatten {
+                        properties.push(quote! {
+                            intersection.push(#ts_type);
+                        });
+                        continue;
+                    }
+                    let field_name = ts_field_name(field, &attrs, &container)?;
+                    let doc = doc_comment(&field.attrs)
+                        .map(|doc| ts_doc(&doc, "  "))
+                        .unwrap_or_default();
+
+                    properties.push(quote! {
+                        members.push_str(#doc);
+                        members.push_str(&format!(
+                            "  {}: {};\n",
+                            #field_name,
+                            #ts_type
+                        ));
+                    });
                 }
-            };
 
-            TokenStream::from(expanded)
+                // Flattened fields are joined to the own members with `&`.
+                let intersection = ts_intersection(quote! { intersection });
+                quote! {
+                    let mut members = String::new();
+                    let mut intersection = Vec::<String>::new();
+                    #(#properties)*
+
+                    let declaration = if intersection.is_empty() {
+                        format!("export interface {} {{\n{}}}", name, members)
+                    } else {
+                        format!("export type {} = {{\n{}}} & {};", name, members, #intersection)
+                    };
+                }
+            }
+            Fields::Unnamed(fields) => {
+                let ts_type = ts_tuple(&fields);
+                quote! {
+                    let declaration = format!("export type {} = {};", name, #ts_type);
+                }
+            }
+            Fields::Unit => quote! {
+                let declaration = format!("export type {} = null;", name);
+            },
+        },
+        Data::Enum(data) => {
+            let tagging = container.tagging();
+            let mut variants = Vec::new();
+            for variant in data.variants.iter() {
+                let key = FieldAttrs::parse_variant(&variant.attrs)?
+                    .variant_key(&variant.ident, &container);
+                variants.push(ts_variant(variant, &key, &tagging)?);
+            }
+
+            quote! {
+                let variants: Vec<String> = vec![#(#variants),*];
+                let declaration = format!("export type {} = {};", name, variants.join(" | "));
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "ToTypeScript cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ToTypeScript for #name #ty_generics #where_clause {
+            fn ts_type(declarations: &mut TypeScriptDeclarations) -> String {
+                let name = declarations.name_for::<Self>();
+                declarations.declare(&name, |declarations| {
+                    #body
+                    format!("{}{}", #doc, declaration)
+                })
+            }
+        }
+    })
+}
+
+/// Returns the expression of the TypeScript type of a field. A field converted with a function
+/// may take any value.
+fn ts_field_type(field: &syn::Field, attrs: &FieldAttrs) -> proc_macro2::TokenStream {
+    let field_type = &field.ty;
+    match attrs.to_with {
+        Some(_) => quote! { "unknown".to_string() },
+        None => quote! { <#field_type as ToTypeScript>::ts_type(declarations) },
+    }
+}
+
+/// Returns the name of a field as written in an object type, with a `?` when it is left out
+/// when empty.
+fn ts_field_name(
+    field: &syn::Field,
+    attrs: &FieldAttrs,
+    container: &ContainerAttrs,
+) -> syn::Result<String> {
+    let mut field_name = match field.ident.as_ref() {
+        Some(name) => ts_property(&attrs.key(name, container)),
+        None => {
+            return Err(syn::Error::new_spanned(
+                field,
+                "ToTypeScript cannot be derived for unnamed fields",
+            ))
+        }
+    };
+    if attrs.skip_serializing_if.is_some() {
+        field_name.push('?');
+    }
+    Ok(field_name)
+}
+
+/// Returns the expression joining types with `&`, parenthesizing unions.
+fn ts_intersection(types: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        #types
+            .iter()
+            .map(|ts_type| {
+                if ts_type.contains(" | ") {
+                    format!("({})", ts_type)
+                } else {
+                    ts_type.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" & ")
+    }
+}
+
+/// Returns the expression of the TypeScript type of unnamed fields: the type of the field of a
+/// newtype, or a tuple of the types of the fields.
+fn ts_tuple(fields: &syn::FieldsUnnamed) -> proc_macro2::TokenStream {
+    let types = fields.unnamed.iter().map(|field| &field.ty);
+    if fields.unnamed.len() == 1 {
+        let field_type = &fields.unnamed[0].ty;
+        quote! { <#field_type as ToTypeScript>::ts_type(declarations) }
+    } else {
+        quote! {
+            format!(
+                "[{}]",
+                vec![#(<#types as ToTypeScript>::ts_type(declarations)),*].join(", ")
+            )
+        }
+    }
+}
+
+/// Returns the expression of an object type on one line, with the `members` given as
+/// `name: type` before those of the named fields, which are not renamed by the container.
+fn ts_inline_object(
+    fields: &FieldsNamed,
+    members: Vec<String>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let container = ContainerAttrs::default();
+    let mut properties = Vec::new();
+    for field in fields.named.iter() {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ts_type = ts_field_type(field, &attrs);
+        if attrs.flatten {
+            properties.push(quote! {
+                intersection.push(#ts_type);
+            });
+        } else {
+            let field_name = ts_field_name(field, &attrs, &container)?;
+            properties.push(quote! {
+                members.push(format!("{}: {}", #field_name, #ts_type));
+            });
+        }
+    }
+
+    let intersection = ts_intersection(quote! { intersection });
+    Ok(quote! {
+        {
+            let mut members: Vec<String> = vec![#(#members.to_string()),*];
+            let mut intersection = Vec::<String>::new();
+            #(#properties)*
+            let object = format!("{{ {} }}", members.join("; "));
+            if intersection.is_empty() {
+                object
+            } else {
+                format!("{} & {}", object, #intersection)
+            }
+        }
+    })
+}
+
+/// Returns the expression of the TypeScript type of a variant, in the representation
+/// `ToValue` gives it.
+fn ts_variant(
+    variant: &Variant,
+    key: &str,
+    tagging: &Tagging,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let literal = format!("{:?}", key);
+    let content = match &variant.fields {
+        Fields::Unit => None,
+        Fields::Unnamed(fields) => {
+            if matches!(tagging, Tagging::Internal { .. }) && fields.unnamed.len() > 1 {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "internally tagged enums cannot have tuple variants",
+                ));
+            }
+            Some(ts_tuple(fields))
+        }
+        Fields::Named(fields) => Some(ts_inline_object(fields, Vec::new())?),
+    };
+
+    Ok(match (tagging, content) {
+        (Tagging::External, None) => quote! { #literal.to_string() },
+        (Tagging::External, Some(content)) => {
+            let property = ts_property(key);
+            quote! { format!("{{ {}: {} }}", #property, #content) }
+        }
+        (Tagging::Internal { tag } | Tagging::Adjacent { tag, .. }, None) => {
+            let object = format!("{{ {}: {} }}", ts_property(tag), literal);
+            quote! { #object.to_string() }
+        }
+        // The tag is a member of the content, which holds a struct or a map.
+        (Tagging::Internal { tag }, Some(content)) => {
+            let tag_member = format!("{}: {}", ts_property(tag), literal);
+            match &variant.fields {
+                Fields::Named(fields) => ts_inline_object(fields, vec![tag_member])?,
+                _ => {
+                    let tag_object = format!("{{ {} }}", tag_member);
+                    let content = ts_intersection(quote! { [#content] });
+                    quote! { format!("{} & {}", #tag_object, #content) }
+                }
+            }
+        }
+        (
+            Tagging::Adjacent {
+                tag,
+                content: content_key,
+            },
+            Some(content),
+        ) => {
+            let members = format!(
+                "{}: {}; {}: ",
+                ts_property(tag),
+                literal,
+                ts_property(content_key)
+            );
+            quote! { format!("{{ {}{} }}", #members, #content) }
+        }
+        (Tagging::Untagged, None) => quote! { "null".to_string() },
+        (Tagging::Untagged, Some(content)) => content,
+    })
+}
+
+/// Renders a doc comment as a TSDoc comment, ending with a newline.
+fn ts_doc(doc: &str, indent: &str) -> String {
+    if !doc.contains('\n') {
+        return format!("{}/** {} */\n", indent, doc);
+    }
+
+    let mut comment = format!("{}/**\n", indent);
+    for line in doc.lines() {
+        if line.is_empty() {
+            comment.push_str(&format!("{} *\n", indent));
+        } else {
+            comment.push_str(&format!("{} * {}\n", indent, line));
         }
-        _ => panic!("Can only derive FromValueBehavior for a struct."),
+    }
+    comment.push_str(&format!("{} */\n", indent));
+    comment
+}
+
+/// Returns a property name as written in a TypeScript interface, quoted unless it is an
+/// identifier.
+fn ts_property(name: &str) -> String {
+    let mut chars = name.chars();
+    let identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+/// Returns the doc comment of an item, without the leading spaces of its lines.
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc)
     }
 }
 
-#[proc_macro_derive(ToJson)]
+#[proc_macro_derive(ToJson, attributes(attr))]
 pub fn to_json_derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     let name = &ast.ident;
@@ -223,7 +967,7 @@ pub fn to_json_derive(input: TokenStream) -> TokenStream {
     gen.into()
 }
 
-#[proc_macro_derive(ToYaml)]
+#[proc_macro_derive(ToYaml, attributes(attr))]
 pub fn to_yaml_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
@@ -0,0 +1,270 @@
+//! The `Validate` derive, which checks the fields of a struct against the rules of their
+//! `#[validate(...)]` attributes, both on an instance and on a `Value` read before conversion.
+//!
+//! Fields accept `length(min = .., max = ..)`, `range(min = .., max = ..)`, `regex = "..."`,
+//! `email`, `url`, `one_of(..)`, `custom = "path"`, which may repeat, and `dive`, which
+//! validates the field with its own `Validate` implementation. Violations are placed at the
+//! keys of the fields, as `#[attr(...)]` names them.
+use crate::attr::{ContainerAttrs, FieldAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, ExprPath, ExprUnary, Fields, Lit, LitStr};
+
+/// The rules of a field.
+#[derive(Default)]
+struct Rules {
+    /// The expressions of the `Constraint`s the value of the field follows.
+    constraints: Vec<TokenStream>,
+    dive: bool,
+}
+
+impl Rules {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut rules = Rules::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("validate")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("length") {
+                    let (min, max) = bounds(&meta)?;
+                    let (min, max) = (option(min), option(max));
+                    rules.constraints.push(quote! {
+                        Constraint::Length { min: #min, max: #max }
+                    });
+                } else if meta.path.is_ident("range") {
+                    let (min, max) = bounds(&meta)?;
+                    let (min, max) = (option(min.map(float)), option(max.map(float)));
+                    rules.constraints.push(quote! {
+                        Constraint::Range { min: #min, max: #max }
+                    });
+                } else if meta.path.is_ident("regex") {
+                    let pattern = meta.value()?.parse::<LitStr>()?;
+                    if let Err(error) = regex::Regex::new(&pattern.value()) {
+                        return Err(syn::Error::new(pattern.span(), error));
+                    }
+                    rules
+                        .constraints
+                        .push(quote! { Constraint::pattern(#pattern) });
+                } else if meta.path.is_ident("email") {
+                    rules.constraints.push(quote! { Constraint::Email });
+                } else if meta.path.is_ident("url") {
+                    rules.constraints.push(quote! { Constraint::Url });
+                } else if meta.path.is_ident("one_of") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let values = Punctuated::<Expr, syn::Token![,]>::parse_terminated(&content)?;
+                    if values.is_empty() {
+                        return Err(meta.error("`one_of` requires at least one value"));
+                    }
+                    let values = values.iter();
+                    rules.constraints.push(quote! {
+                        Constraint::OneOf(vec![#(Value::from(#values)),*])
+                    });
+                } else if meta.path.is_ident("custom") {
+                    let path = meta.value()?.parse::<LitStr>()?.parse::<ExprPath>()?;
+                    rules.constraints.push(quote! { Constraint::Custom(#path) });
+                } else if meta.path.is_ident("dive") {
+                    rules.dive = true;
+                } else {
+                    let name = meta
+                        .path
+                        .get_ident()
+                        .map(|ident| ident.to_string())
+                        .unwrap_or_default();
+                    return Err(meta.error(format!("unknown validation rule `{}`", name)));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(rules)
+    }
+
+    /// Returns the expression of the constraints, built once.
+    fn constraints(&self) -> TokenStream {
+        let constraints = &self.constraints;
+        quote! {
+            {
+                static CONSTRAINTS: std::sync::OnceLock<Vec<Constraint>> =
+                    std::sync::OnceLock::new();
+                CONSTRAINTS.get_or_init(|| vec![#(#constraints),*])
+            }
+        }
+    }
+}
+
+/// Parses the `(min = .., max = ..)` of `length` and `range`.
+fn bounds(meta: &syn::meta::ParseNestedMeta) -> syn::Result<(Option<Expr>, Option<Expr>)> {
+    let mut min = None;
+    let mut max = None;
+    meta.parse_nested_meta(|bound| {
+        if bound.path.is_ident("min") {
+            min = Some(bound.value()?.parse::<Expr>()?);
+        } else if bound.path.is_ident("max") {
+            max = Some(bound.value()?.parse::<Expr>()?);
+        } else {
+            return Err(bound.error("expected `min` or `max`"));
+        }
+        Ok(())
+    })?;
+    Ok((min, max))
+}
+
+/// Returns an `f64` expression of a bound, written as a float literal when it is an integer
+/// literal.
+fn float(expr: Expr) -> TokenStream {
+    let (negative, lit) = match &expr {
+        Expr::Lit(ExprLit { lit, .. }) => (false, lit),
+        Expr::Unary(ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => match expr.as_ref() {
+            Expr::Lit(ExprLit { lit, .. }) => (true, lit),
+            _ => return quote! { (#expr) as f64 },
+        },
+        _ => return quote! { (#expr) as f64 },
+    };
+    match lit {
+        Lit::Int(int) => match int.base10_parse::<f64>() {
+            Ok(value) => {
+                let value = if negative { -value } else { value };
+                let literal = proc_macro2::Literal::f64_suffixed(value);
+                quote! { #literal }
+            }
+            Err(error) => error.into_compile_error(),
+        },
+        _ => quote! { (#expr) as f64 },
+    }
+}
+
+fn option<T: quote::ToTokens>(item: Option<T>) -> TokenStream {
+    match item {
+        Some(item) => quote! { Some(#item) },
+        None => quote! { None },
+    }
+}
+
+/// Returns the implementation of `Validate` for a struct with named fields.
+pub(crate) fn derive(ast: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::parse(&ast.attrs)?;
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(fields) => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "Validate cannot be derived for tuple structs",
+                ))
+            }
+        },
+        Data::Enum(data) => {
+            return Err(syn::Error::new(
+                data.enum_token.span(),
+                "Validate cannot be derived for enums",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "Validate cannot be derived for unions",
+            ))
+        }
+    };
+
+    let mut checks = Vec::new();
+    let mut value_checks = Vec::new();
+    for field in fields {
+        let rules = Rules::parse(&field.attrs)?;
+        if rules.constraints.is_empty() && !rules.dive {
+            continue;
+        }
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let field_name = match &field.ident {
+            Some(field_name) => field_name,
+            None => continue,
+        };
+        let field_type = &field.ty;
+
+        // A flattened field validates the members of the object of its struct.
+        if attrs.flatten {
+            if !rules.constraints.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "flattened fields accept `dive` only",
+                ));
+            }
+            checks.push(quote! {
+                violations.merge(Validate::validate(&self.#field_name));
+            });
+            value_checks.push(quote! {
+                violations.merge(<#field_type as Validate>::validate_value(value));
+            });
+            continue;
+        }
+
+        let key = attrs.key(field_name, &container);
+        let mut check = Vec::new();
+        let mut value_check = Vec::new();
+        if !rules.constraints.is_empty() {
+            let constraints = rules.constraints();
+            let item = match &attrs.to_with {
+                Some(path) => quote! { #path(&self.#field_name) },
+                None => quote! { ToValueBehavior::to_value(&self.#field_name) },
+            };
+            check.push(quote! {
+                violations.check(#key, &#item, #constraints);
+            });
+            value_check.push(quote! {
+                violations.check(key, item, #constraints);
+            });
+        }
+        if rules.dive {
+            check.push(quote! {
+                violations.nest_key(#key, Validate::validate(&self.#field_name));
+            });
+            value_check.push(quote! {
+                violations.nest_key(key, <#field_type as Validate>::validate_value(item));
+            });
+        }
+        checks.push(quote! { #(#check)* });
+
+        // The key comes first, then the aliases it is also read from.
+        let mut keys = vec![key];
+        keys.extend(attrs.aliases.iter().cloned());
+        value_checks.push(quote! {
+            if let Some((key, item)) = [#(#keys),*]
+                .iter()
+                .find_map(|key| map.get(*key).map(|item| (*key, item)))
+            {
+                #(#value_check)*
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> Result<(), Violations> {
+                #[allow(unused_mut)]
+                let mut violations = Violations::new();
+                #(#checks)*
+                violations.into_result()
+            }
+
+            #[allow(unused_variables)]
+            fn validate_value(value: &Value) -> Result<(), Violations> {
+                let map = match value {
+                    Value::Object(map) => map,
+                    _ => return Ok(()),
+                };
+                #[allow(unused_mut)]
+                let mut violations = Violations::new();
+                #(#value_checks)*
+                violations.into_result()
+            }
+        }
+    })
+}
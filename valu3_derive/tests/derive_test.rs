@@ -1,7 +1,7 @@
 // tests/derive_tests.rs
 use std::collections::HashMap;
+use valu3::assert_value_eq;
 use valu3::prelude::*;
-use valu3_derive::*;
 
 #[derive(ToValue, FromValue, ToJson)]
 struct User {
@@ -10,7 +10,8 @@ struct User {
     status: Status,
 }
 
-#[derive(Clone, ToValue, FromValue, ToJson, PartialEq, Debug)]
+/// Whether a user can sign in.
+#[derive(Clone, ToValue, FromValue, ToJson, ToTypeScript, PartialEq, Debug)]
 enum Status {
     Active,
     Inactive,
@@ -49,6 +50,18 @@ fn test_from_value_struct() {
     assert_eq!(user.status, Status::Active);
 }
 
+#[test]
+fn test_to_value_equals_parsed_and_built_values() {
+    let value = Point { x: 1, y: 2 }.to_value();
+
+    let mut map = HashMap::new();
+    map.insert("y".to_string(), Value::from(2));
+    map.insert("x".to_string(), Value::from(1));
+
+    assert_eq!(value, map.to_value());
+    assert_eq!(value, json(r#"{"y": 2, "x": 1}"#));
+}
+
 #[test]
 fn test_to_value_enum() {
     let status = Status::Active;
@@ -87,3 +100,1276 @@ fn test_to_json() {
 
     assert_eq!(result, expect);
 }
+
+/// A customer account.
+#[allow(dead_code)]
+#[derive(ToSchema, ToTypeScript)]
+struct Account {
+    /// The account number.
+    id: u16,
+    owner: Option<String>,
+    roles: Vec<Role>,
+    limits: HashMap<String, f64>,
+    parent: Option<Box<Account>>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema, ToTypeScript)]
+enum Role {
+    Admin,
+    Viewer,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema, ToTypeScript)]
+struct Page<T> {
+    items: Vec<T>,
+}
+
+#[test]
+fn test_to_schema() {
+    let expected = Value::json_to_value(
+        r##"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Account",
+            "description": "A customer account.",
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "format": "uint16", "minimum": 0, "maximum": 65535, "description": "The account number."},
+                "owner": {"type": ["string", "null"]},
+                "roles": {"type": "array", "items": {"$ref": "#/$defs/Role"}},
+                "limits": {"type": "object", "additionalProperties": {"type": "number", "format": "double"}},
+                "parent": {"anyOf": [{"$ref": "#/$defs/Account"}, {"type": "null"}]}
+            },
+            "required": ["id", "roles", "limits"]
+        }"##,
+    )
+    .unwrap();
+
+    let schema = Account::schema();
+    for key in ["title", "description", "type", "properties", "required"] {
+        assert_value_eq!(
+            schema.get(key).unwrap(),
+            expected.get(key).unwrap(),
+            "{}",
+            key
+        );
+    }
+    assert_value_eq!(
+        schema.get("$defs").unwrap().get("Role").unwrap(),
+        Value::json_to_value(r#"{"title": "Role", "type": "string", "enum": ["Admin", "Viewer"]}"#)
+            .unwrap()
+    );
+
+    let validator = Schema::compile(&schema).unwrap();
+    let valid = r#"{"id": 1, "owner": null, "roles": ["Admin"], "limits": {"daily": 10.5},
+        "parent": {"id": 2, "roles": [], "limits": {}}}"#;
+    assert!(validator.is_valid(&Value::json_to_value(valid).unwrap()));
+    let invalid = r#"{"id": 70000, "roles": ["Owner"], "limits": {}, "parent": {"roles": []}}"#;
+    let paths = validator
+        .validate(&Value::json_to_value(invalid).unwrap())
+        .unwrap_err()
+        .iter()
+        .map(|error| error.instance_path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec!["/id", "/parent", "/roles/0"]);
+}
+
+#[test]
+fn test_to_schema_generics() {
+    let schema = Page::<Role>::schema();
+    assert_eq!(schema.get("title"), Some(&Value::from("Page")));
+    assert!(schema.get("$defs").unwrap().get("Role").is_some());
+    assert_eq!(
+        SchemaDefinitions::name_of::<Page<Role>>(),
+        "Page<Role>".to_string()
+    );
+}
+
+#[test]
+fn test_to_typescript() {
+    assert_eq!(
+        Account::typescript(),
+        r#"/** A customer account. */
+export interface Account {
+  /** The account number. */
+  id: number;
+  owner: string | null;
+  roles: Role[];
+  limits: Record<string, number>;
+  parent: Account | null;
+}
+
+export type Role = "Admin" | "Viewer";
+"#
+    );
+}
+
+#[test]
+fn test_to_typescript_declarations() {
+    let mut declarations = TypeScriptDeclarations::new();
+    assert_eq!(declarations.add::<Page<Role>>(), "PageRole");
+    assert_eq!(declarations.add::<Vec<Status>>(), "Status[]");
+    assert_eq!(declarations.len(), 3);
+    assert_eq!(
+        declarations.get("PageRole"),
+        Some("export interface PageRole {\n  items: Role[];\n}")
+    );
+    assert_eq!(
+        declarations.get("Status"),
+        Some("/** Whether a user can sign in. */\nexport type Status = \"Active\" | \"Inactive\";")
+    );
+}
+
+mod billing {
+    use valu3::prelude::*;
+
+    #[allow(dead_code)]
+    #[derive(ToSchema, ToTypeScript)]
+    pub struct Address {
+        pub street: String,
+    }
+}
+
+mod shipping {
+    use valu3::prelude::*;
+
+    #[allow(dead_code)]
+    #[derive(ToSchema, ToTypeScript)]
+    pub struct Address {
+        pub zip: u32,
+    }
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema, ToTypeScript)]
+struct Shipment {
+    billing: billing::Address,
+    shipping: shipping::Address,
+}
+
+#[test]
+fn test_same_names_from_different_modules() {
+    let schema = Shipment::schema();
+    let definitions = schema.get("$defs").unwrap();
+    assert!(definitions
+        .get("Address")
+        .unwrap()
+        .get("properties")
+        .unwrap()
+        .get("street")
+        .is_some());
+    assert!(definitions
+        .get("Address2")
+        .unwrap()
+        .get("properties")
+        .unwrap()
+        .get("zip")
+        .is_some());
+    assert_eq!(
+        schema
+            .get("properties")
+            .unwrap()
+            .get("shipping")
+            .unwrap()
+            .get("$ref"),
+        Some(&Value::from("#/$defs/Address2"))
+    );
+
+    assert_eq!(
+        Shipment::typescript(),
+        r#"export interface Address {
+  street: string;
+}
+
+export interface Address2 {
+  zip: number;
+}
+
+export interface Shipment {
+  billing: Address;
+  shipping: Address2;
+}
+"#
+    );
+}
+
+fn default_limit() -> u32 {
+    10
+}
+
+#[derive(
+    ToValue, FromValue, TryFromValue, ToJson, ToYaml, ToSchema, ToTypeScript, Debug, PartialEq,
+)]
+#[attr(rename_all = "camelCase")]
+struct Profile {
+    user_id: u32,
+    #[attr(rename = "display-name", alias = "name", alias = "nick")]
+    display_name: String,
+    #[attr(skip)]
+    session: Option<String>,
+    #[attr(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<String>,
+    #[attr(default = "default_limit")]
+    page_limit: u32,
+    visibility: Visibility,
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq)]
+#[attr(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Visibility {
+    FriendsOnly,
+    #[attr(rename = "hidden", alias = "private")]
+    Hidden,
+}
+
+#[derive(ToValue, FromValue, Debug, PartialEq)]
+#[attr(rename_all = "kebab-case")]
+enum Theme {
+    DarkHighContrast,
+}
+
+#[test]
+fn test_attributes_to_value() {
+    let profile = Profile {
+        user_id: 1,
+        display_name: "Ana".to_string(),
+        session: Some("secret".to_string()),
+        tags: Vec::new(),
+        page_limit: 20,
+        visibility: Visibility::FriendsOnly,
+    };
+
+    assert_value_eq!(
+        profile.to_value(),
+        Value::json_to_value(
+            r#"{"userId": 1, "display-name": "Ana", "pageLimit": 20, "visibility": "FRIENDS_ONLY"}"#
+        )
+        .unwrap()
+    );
+    assert!(profile.to_json().contains(r#""display-name": "Ana""#));
+    assert!(profile.to_yaml().contains("pageLimit: 20"));
+    assert_eq!(Visibility::Hidden.to_value(), Value::from("hidden"));
+    assert_eq!(
+        Theme::DarkHighContrast.to_value(),
+        Value::from("dark-high-contrast")
+    );
+
+    let tagged = Profile {
+        tags: vec!["admin".to_string()],
+        ..profile
+    };
+    assert_eq!(
+        tagged.to_value().get("tags"),
+        Some(&Value::from(vec!["admin"]))
+    );
+}
+
+#[test]
+fn test_attributes_from_value() {
+    let value = Value::json_to_value(
+        r#"{"userId": 1, "nick": "Ana", "visibility": "private", "session": "x"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Profile::from_value(value),
+        Some(Profile {
+            user_id: 1,
+            display_name: "Ana".to_string(),
+            session: None,
+            tags: Vec::new(),
+            page_limit: 10,
+            visibility: Visibility::Hidden,
+        })
+    );
+
+    let missing = Value::json_to_value(r#"{"userId": 1, "visibility": "hidden"}"#).unwrap();
+    assert_eq!(Profile::from_value(missing), None);
+    let original = Value::json_to_value(
+        r#"{"user_id": 1, "display_name": "Ana", "visibility": "FRIENDS_ONLY"}"#,
+    )
+    .unwrap();
+    assert_eq!(Profile::from_value(original), None);
+    assert_eq!(
+        Theme::from_value(Value::from("dark-high-contrast")),
+        Some(Theme::DarkHighContrast)
+    );
+}
+
+#[test]
+fn test_attributes_descriptions() {
+    let schema = Profile::schema();
+    assert_value_eq!(
+        schema.get("required").unwrap(),
+        Value::json_to_value(r#"["userId", "display-name", "visibility"]"#).unwrap()
+    );
+    assert!(schema.get("properties").unwrap().get("session").is_none());
+
+    assert_eq!(
+        Profile::typescript(),
+        r#"export interface Profile {
+  userId: number;
+  "display-name": string;
+  tags?: string[];
+  pageLimit: number;
+  visibility: Visibility;
+}
+
+export type Visibility = "FRIENDS_ONLY" | "hidden";
+"#
+    );
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Line(Point, Point),
+    Rect {
+        #[attr(rename = "topLeft")]
+        top_left: Point,
+        width: u32,
+        #[attr(skip)]
+        cached_area: u32,
+    },
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+#[attr(tag = "type", rename_all = "snake_case")]
+enum Event {
+    Started,
+    Moved(Point),
+    Renamed { from: String, to: String },
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+#[attr(tag = "t", content = "c")]
+enum Message {
+    Ping,
+    Text(String),
+    Pair(i32, i32),
+    Move { point: Point },
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+#[attr(untagged)]
+enum Scalar {
+    Nothing,
+    Number(i64),
+    Text(String),
+    Pair(bool, bool),
+    Point { x: i32, y: i32 },
+}
+
+fn json(json: &str) -> Value {
+    Value::json_to_value(json).unwrap()
+}
+
+#[track_caller]
+fn assert_round_trip<T>(item: T, expected: &str)
+where
+    T: ToValueBehavior + FromValueBehavior<Item = T> + PartialEq + std::fmt::Debug + Clone,
+{
+    let value = item.to_value();
+    assert_value_eq!(value.clone(), json(expected));
+    assert_eq!(T::from_value(value), Some(item.clone()));
+    assert_eq!(T::from_value(json(expected)), Some(item));
+}
+
+#[test]
+fn test_externally_tagged_enum() {
+    let origin = Point { x: 0, y: 0 };
+    assert_round_trip(Shape::Empty, r#""Empty""#);
+    assert_round_trip(Shape::Circle(1.5), r#"{"Circle": 1.5}"#);
+    assert_round_trip(
+        Shape::Line(origin.clone(), Point { x: 1, y: 2 }),
+        r#"{"Line": [{"x": 0, "y": 0}, {"x": 1, "y": 2}]}"#,
+    );
+    assert_round_trip(
+        Shape::Rect {
+            top_left: origin,
+            width: 3,
+            cached_area: 0,
+        },
+        r#"{"Rect": {"topLeft": {"x": 0, "y": 0}, "width": 3}}"#,
+    );
+
+    assert_eq!(
+        Shape::from_value(json(r#"{"Line": [{"x": 0, "y": 0}]}"#)),
+        None
+    );
+    assert_eq!(
+        Shape::from_value(json(r#"{"Circle": 1, "Empty": null}"#)),
+        None
+    );
+    assert_eq!(Shape::from_value(json(r#""Circle""#)), None);
+}
+
+#[test]
+fn test_internally_tagged_enum() {
+    assert_round_trip(Event::Started, r#"{"type": "started"}"#);
+    assert_round_trip(
+        Event::Moved(Point { x: 1, y: 2 }),
+        r#"{"type": "moved", "x": 1, "y": 2}"#,
+    );
+    assert_round_trip(
+        Event::Renamed {
+            from: "a".to_string(),
+            to: "b".to_string(),
+        },
+        r#"{"type": "renamed", "from": "a", "to": "b"}"#,
+    );
+
+    assert_eq!(
+        Event::from_value(json(r#"{"type": "moved", "x": 1}"#)),
+        None
+    );
+    assert_eq!(Event::from_value(json(r#"{"kind": "started"}"#)), None);
+}
+
+#[derive(ToValue)]
+#[attr(tag = "type")]
+enum Reading {
+    Counts(HashMap<String, i32>),
+}
+
+#[test]
+fn test_internally_tagged_newtype_of_a_map() {
+    let counts = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+    assert_eq!(
+        Reading::Counts(counts).to_value(),
+        json(r#"{"type": "Counts", "a": 1, "b": 2}"#)
+    );
+}
+
+#[test]
+fn test_adjacently_tagged_enum() {
+    assert_round_trip(Message::Ping, r#"{"t": "Ping"}"#);
+    assert_round_trip(
+        Message::Text("hi".to_string()),
+        r#"{"t": "Text", "c": "hi"}"#,
+    );
+    assert_round_trip(Message::Pair(1, 2), r#"{"t": "Pair", "c": [1, 2]}"#);
+    assert_round_trip(
+        Message::Move {
+            point: Point { x: 1, y: 2 },
+        },
+        r#"{"t": "Move", "c": {"point": {"x": 1, "y": 2}}}"#,
+    );
+
+    assert_eq!(Message::from_value(json(r#"{"t": "Text"}"#)), None);
+}
+
+#[test]
+fn test_untagged_enum() {
+    assert_round_trip(Scalar::Nothing, "null");
+    assert_round_trip(Scalar::Number(7), "7");
+    assert_round_trip(Scalar::Text("a".to_string()), r#""a""#);
+    assert_round_trip(Scalar::Pair(true, false), "[true, false]");
+    assert_round_trip(Scalar::Point { x: 1, y: 2 }, r#"{"x": 1, "y": 2}"#);
+
+    assert_eq!(Scalar::from_value(json("[1, 2]")), None);
+}
+
+#[derive(ToValue, FromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct UserId(u64);
+
+#[derive(ToValue, FromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct Wrapper(Point);
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct Range(i32, i32, Option<String>);
+
+#[derive(ToValue, FromValue, Debug, PartialEq, Clone)]
+struct Marker;
+
+#[derive(ToValue, FromValue, Debug, PartialEq, Clone)]
+struct Owner {
+    id: UserId,
+    range: Range,
+    marker: Marker,
+}
+
+#[test]
+fn test_newtype_struct() {
+    assert_round_trip(UserId(7), "7");
+    assert_round_trip(Wrapper(Point { x: 1, y: 2 }), r#"{"x": 1, "y": 2}"#);
+    assert_eq!(UserId::from_value(json(r#""7""#)), None);
+}
+
+#[test]
+fn test_tuple_struct() {
+    assert_round_trip(Range(1, 2, Some("a".to_string())), r#"[1, 2, "a"]"#);
+    assert_eq!(Range::from_value(json("[1, 2]")), None);
+    assert_eq!(Range::from_value(json(r#"[1, 2, "a", 3]"#)), None);
+    assert_eq!(Range::from_value(json(r#"{"0": 1}"#)), None);
+}
+
+#[test]
+fn test_unit_struct() {
+    assert_round_trip(Marker, "null");
+    assert_eq!(Marker::from_value(json("0")), None);
+    assert_round_trip(
+        Owner {
+            id: UserId(1),
+            range: Range(0, 9, Some("b".to_string())),
+            marker: Marker,
+        },
+        r#"{"id": 1, "range": [0, 9, "b"], "marker": null}"#,
+    );
+}
+
+#[derive(ToValue, FromValue, Debug, PartialEq, Clone, Default)]
+struct Settings {
+    name: String,
+    theme: Option<String>,
+    limits: Option<Vec<u32>>,
+    #[attr(default)]
+    retries: u8,
+    #[attr(default)]
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_optional_fields() {
+    let partial = Settings::from_value(json(r#"{"name": "a"}"#)).unwrap();
+    assert_eq!(
+        partial,
+        Settings {
+            name: "a".to_string(),
+            ..Settings::default()
+        }
+    );
+
+    let nulls = json(r#"{"name": "a", "theme": null, "limits": null, "retries": 2}"#);
+    assert_eq!(
+        Settings::from_value(nulls),
+        Some(Settings {
+            name: "a".to_string(),
+            retries: 2,
+            ..Settings::default()
+        })
+    );
+
+    let mut undefined = json(r#"{"name": "a", "theme": "dark"}"#);
+    if let Value::Object(object) = &mut undefined {
+        object.insert("theme", Value::Undefined);
+        object.insert("retries", Value::Undefined);
+    }
+    assert_eq!(
+        Settings::from_value(undefined),
+        Some(Settings {
+            name: "a".to_string(),
+            ..Settings::default()
+        })
+    );
+
+    let full = Settings {
+        name: "b".to_string(),
+        theme: Some("dark".to_string()),
+        limits: Some(vec![1, 2]),
+        retries: 3,
+        tags: vec!["x".to_string()],
+    };
+    assert_eq!(Settings::from_value(full.to_value()), Some(full));
+    assert_eq!(
+        Settings::from_value(Settings::default().to_value()),
+        Some(Settings::default())
+    );
+}
+
+#[test]
+fn test_optional_fields_reject_wrong_types() {
+    assert_eq!(
+        Settings::from_value(json(r#"{"name": "a", "theme": 1}"#)),
+        None
+    );
+    assert_eq!(Settings::from_value(json(r#"{"name": null}"#)), None);
+    assert_eq!(Settings::from_value(json(r#"{"theme": "dark"}"#)), None);
+    assert_eq!(
+        <Option<u8> as FromValueBehavior>::from_value(Value::from("1")),
+        None
+    );
+    assert_eq!(
+        <Option<u8> as FromValueBehavior>::from_value(Value::Null),
+        Some(None)
+    );
+    assert_round_trip(Range(1, 2, None), "[1, 2, null]");
+}
+
+#[derive(TryFromValue, Debug, PartialEq)]
+struct Item {
+    name: String,
+    price: u8,
+}
+
+#[derive(TryFromValue, Debug, PartialEq)]
+struct Order {
+    id: u32,
+    items: Vec<Item>,
+    note: Option<String>,
+}
+
+#[track_caller]
+fn assert_conversion_error<T: TryFromValue + std::fmt::Debug>(value: Value, expected: &str) {
+    match T::try_from_value(value) {
+        Err(Error::Conversion(error)) => assert_eq!(error.to_string(), expected),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_try_from_value_struct() {
+    let items = r#"[{"name": "a", "price": 1}, {"name": "b", "price": 2}]"#;
+    assert_eq!(
+        Order::try_from_value(json(&format!(r#"{{"id": 1, "items": {}}}"#, items))),
+        Ok(Order {
+            id: 1,
+            items: vec![
+                Item {
+                    name: "a".to_string(),
+                    price: 1
+                },
+                Item {
+                    name: "b".to_string(),
+                    price: 2
+                },
+            ],
+            note: None,
+        })
+    );
+
+    assert_conversion_error::<Order>(
+        json(
+            r#"{"id": 1, "items": [
+                {"name": "a", "price": 1},
+                {"name": "b", "price": 2},
+                {"name": "c", "price": 3},
+                {"name": "d", "price": 300}
+            ]}"#,
+        ),
+        "items[3].price: expected u8, found number 300",
+    );
+    assert_conversion_error::<Order>(
+        json(r#"{"id": 1, "items": [{"price": 1}]}"#),
+        "items[0].name: expected String, found no value",
+    );
+    assert_conversion_error::<Order>(
+        json(r#"{"id": 1, "items": {}}"#),
+        "items: expected Vec<Item>, found object",
+    );
+    assert_conversion_error::<Order>(
+        json(r#"{"id": 1, "items": [], "note": false}"#),
+        "note: expected String, found boolean false",
+    );
+    assert_conversion_error::<Order>(json("[]"), "expected Order, found array");
+}
+
+#[test]
+fn test_try_from_value_attributes() {
+    let value = json(r#"{"userId": 1, "nick": "x", "visibility": "hidden"}"#);
+    assert_eq!(
+        Profile::try_from_value(value.clone()).ok(),
+        Profile::from_value(value)
+    );
+
+    assert_conversion_error::<Profile>(
+        json(r#"{"userId": 1, "nick": 2, "visibility": "hidden"}"#),
+        "nick: expected String, found number 2",
+    );
+    assert_conversion_error::<Profile>(
+        json(r#"{"userId": 1, "visibility": "hidden"}"#),
+        "display-name: expected String, found no value",
+    );
+    assert_conversion_error::<Profile>(
+        json(r#"{"userId": 1, "name": "x", "visibility": "public"}"#),
+        r#"visibility: expected Visibility, found string "public""#,
+    );
+}
+
+#[test]
+fn test_try_from_value_tuple_struct() {
+    assert_eq!(
+        Range::try_from_value(json(r#"[1, 2, null]"#)),
+        Ok(Range(1, 2, None))
+    );
+    assert_conversion_error::<Range>(
+        json(r#"[1, "2", null]"#),
+        r#"[1]: expected i32, found string "2""#,
+    );
+    assert_conversion_error::<Range>(json("[1, 2]"), "expected Range, found array");
+}
+
+#[test]
+fn test_try_from_value_enums() {
+    let origin = Point { x: 0, y: 0 };
+    for (json_value, shape) in [
+        (r#""Empty""#, Shape::Empty),
+        (r#"{"Circle": 1.5}"#, Shape::Circle(1.5)),
+        (
+            r#"{"Line": [{"x": 0, "y": 0}, {"x": 0, "y": 0}]}"#,
+            Shape::Line(origin.clone(), origin.clone()),
+        ),
+    ] {
+        assert_eq!(Shape::try_from_value(json(json_value)), Ok(shape));
+    }
+    assert_conversion_error::<Shape>(
+        json(r#"{"Line": [{"x": 0, "y": 0}, {"x": 0}]}"#),
+        "Line[1].y: expected i32, found no value",
+    );
+    assert_conversion_error::<Shape>(
+        json(r#"{"Rect": {"topLeft": {"x": 0, "y": 0}, "width": -1}}"#),
+        "Rect.width: expected u32, found number -1",
+    );
+    assert_conversion_error::<Shape>(
+        json(r#""Square""#),
+        r#"expected Shape, found string "Square""#,
+    );
+
+    assert_eq!(
+        Event::try_from_value(json(r#"{"type": "moved", "x": 1, "y": 2}"#)),
+        Ok(Event::Moved(Point { x: 1, y: 2 }))
+    );
+    assert_conversion_error::<Event>(
+        json(r#"{"type": "renamed", "from": "a", "to": 1}"#),
+        "to: expected String, found number 1",
+    );
+    assert_conversion_error::<Event>(
+        json(r#"{"type": "stopped"}"#),
+        r#"type: expected Event, found string "stopped""#,
+    );
+    assert_conversion_error::<Event>(json(r#"{"x": 1}"#), "type: expected Event, found undefined");
+
+    assert_eq!(
+        Message::try_from_value(json(r#"{"t": "Pair", "c": [1, 2]}"#)),
+        Ok(Message::Pair(1, 2))
+    );
+    assert_conversion_error::<Message>(
+        json(r#"{"t": "Move", "c": {"point": {"x": 1, "y": "2"}}}"#),
+        r#"c.point.y: expected i32, found string "2""#,
+    );
+
+    assert_eq!(
+        Scalar::try_from_value(json(r#"[true, false]"#)),
+        Ok(Scalar::Pair(true, false))
+    );
+    assert_conversion_error::<Scalar>(json("1.5"), "expected Scalar, found number 1.5");
+}
+
+#[track_caller]
+fn object_keys(value: &Value) -> Vec<String> {
+    match value {
+        Value::Object(object) => object
+            .keys()
+            .into_iter()
+            .map(|key| key.to_string())
+            .collect(),
+        _ => panic!("Expected Value::Object"),
+    }
+}
+
+#[test]
+fn test_to_value_field_order() {
+    let user = User {
+        id: 1,
+        name: "Alice".to_string(),
+        status: Status::Active,
+    };
+    assert_eq!(object_keys(&user.to_value()), ["id", "name", "status"]);
+    let json = user.to_json();
+    let positions = [r#""id""#, r#""name""#, r#""status""#].map(|key| json.find(key).unwrap());
+    assert!(
+        positions.windows(2).all(|pair| pair[0] < pair[1]),
+        "{}",
+        json
+    );
+
+    let profile = Profile {
+        user_id: 1,
+        display_name: "a".to_string(),
+        session: None,
+        tags: vec!["x".to_string()],
+        page_limit: 5,
+        visibility: Visibility::Hidden,
+    };
+    assert_eq!(
+        object_keys(&profile.to_value()),
+        ["userId", "display-name", "tags", "pageLimit", "visibility"]
+    );
+
+    let renamed = Event::Renamed {
+        from: "a".to_string(),
+        to: "b".to_string(),
+    };
+    assert_eq!(object_keys(&renamed.to_value()), ["type", "from", "to"]);
+    let moved = Message::Move {
+        point: Point { x: 1, y: 2 },
+    };
+    let value = moved.to_value();
+    assert_eq!(object_keys(&value), ["t", "c"]);
+    assert_eq!(
+        object_keys(value.get("c").unwrap().get("point").unwrap()),
+        ["x", "y"]
+    );
+}
+
+#[derive(
+    ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone, Default,
+)]
+#[attr(rename_all = "camelCase")]
+struct Audit {
+    created_by: String,
+    #[attr(default)]
+    updated_by: Option<String>,
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct Article {
+    id: u32,
+    #[attr(alias = "name")]
+    title: String,
+    #[attr(flatten)]
+    audit: Audit,
+    #[attr(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[test]
+fn test_flatten() {
+    let article = Article {
+        id: 1,
+        title: "a".to_string(),
+        audit: Audit {
+            created_by: "ann".to_string(),
+            updated_by: None,
+        },
+        extra: HashMap::from([("views".to_string(), Value::from(3))]),
+    };
+    let value = article.to_value();
+    assert_value_eq!(
+        value.clone(),
+        json(r#"{"id": 1, "title": "a", "createdBy": "ann", "updatedBy": null, "views": 3}"#)
+    );
+    assert_eq!(
+        object_keys(&value)[..4],
+        ["id", "title", "createdBy", "updatedBy"]
+    );
+    assert_eq!(Article::from_value(value.clone()), Some(article.clone()));
+    assert_eq!(Article::try_from_value(value), Ok(article.clone()));
+
+    let aliased = json(r#"{"id": 1, "name": "a", "createdBy": "ann", "views": 3}"#);
+    assert_eq!(Article::from_value(aliased), Some(article));
+
+    assert_eq!(
+        <Article as FromValueBehavior>::field_keys(),
+        ["id", "title", "name", "createdBy", "updatedBy"]
+    );
+    assert_eq!(
+        Article::from_value(json(r#"{"id": 1, "title": "a"}"#)),
+        None
+    );
+    match Article::try_from_value(json(r#"{"id": 1, "title": "a", "createdBy": 2}"#)) {
+        Err(Error::Conversion(error)) => {
+            assert_eq!(
+                error.to_string(),
+                "createdBy: expected String, found number 2"
+            )
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_flatten_descriptions() {
+    let schema = Article::schema();
+    let all_of = schema.get("allOf").unwrap();
+    assert_value_eq!(
+        all_of.clone(),
+        json(
+            r##"[
+                {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "integer", "format": "uint32", "minimum": 0, "maximum": 4294967295},
+                        "title": {"type": "string"}
+                    },
+                    "required": ["id", "title"]
+                },
+                {"$ref": "#/$defs/Audit"},
+                {"type": "object", "additionalProperties": true}
+            ]"##
+        )
+    );
+
+    assert_eq!(
+        Article::typescript(),
+        "export type Article = {\n  id: number;\n  title: string;\n} & Audit & Record<string, unknown>;\n\n\
+         export interface Audit {\n  createdBy: string;\n  updatedBy: string | null;\n}\n"
+    );
+}
+
+/// Durations as ISO-8601 strings of seconds, such as `PT1.5S`.
+mod iso_duration {
+    use std::time::Duration;
+    use valu3::prelude::*;
+
+    pub fn to_value(duration: &Duration) -> Value {
+        Value::from(format!("PT{}S", duration.as_secs_f64()))
+    }
+
+    pub fn from_value(value: Value) -> Option<Duration> {
+        match value {
+            Value::String(string) => {
+                let seconds = string.as_str().strip_prefix("PT")?.strip_suffix('S')?;
+                seconds.parse().ok().map(Duration::from_secs_f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> Value {
+    Value::from(
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>(),
+    )
+}
+
+fn bytes_from_hex(value: Value) -> Option<Vec<u8>> {
+    let hex = match value {
+        Value::String(string) => string.as_string(),
+        _ => return None,
+    };
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn default_delay() -> std::time::Duration {
+    std::time::Duration::from_secs(1)
+}
+
+#[derive(ToValue, FromValue, TryFromValue, ToSchema, ToTypeScript, Debug, PartialEq, Clone)]
+struct Job {
+    #[attr(with = "iso_duration")]
+    timeout: std::time::Duration,
+    #[attr(with = "iso_duration", default = "default_delay")]
+    delay: std::time::Duration,
+    #[attr(to_with = "bytes_to_hex", from_with = "bytes_from_hex")]
+    checksum: Vec<u8>,
+}
+
+#[test]
+fn test_with() {
+    let job = Job {
+        timeout: std::time::Duration::from_millis(1500),
+        delay: std::time::Duration::from_secs(2),
+        checksum: vec![0, 171, 255],
+    };
+    assert_round_trip(
+        job.clone(),
+        r#"{"timeout": "PT1.5S", "delay": "PT2S", "checksum": "00abff"}"#,
+    );
+    assert_eq!(
+        Job::try_from_value(json(
+            r#"{"timeout": "PT1.5S", "delay": "PT2S", "checksum": "00abff"}"#
+        )),
+        Ok(job)
+    );
+    assert_eq!(
+        Job::from_value(json(r#"{"timeout": "PT3S", "checksum": ""}"#)),
+        Some(Job {
+            timeout: std::time::Duration::from_secs(3),
+            delay: default_delay(),
+            checksum: Vec::new(),
+        })
+    );
+
+    assert_eq!(
+        Job::from_value(json(r#"{"timeout": 3, "checksum": ""}"#)),
+        None
+    );
+    assert_conversion_error::<Job>(
+        json(r#"{"timeout": "PT3S", "checksum": "xyz"}"#),
+        r#"checksum: expected Vec<u8>, found string "xyz""#,
+    );
+    assert_conversion_error::<Job>(
+        json(r#"{"checksum": ""}"#),
+        "timeout: expected Duration, found no value",
+    );
+
+    let schema = Job::schema();
+    assert_value_eq!(
+        schema
+            .get("properties")
+            .unwrap()
+            .get("timeout")
+            .unwrap()
+            .clone(),
+        json("{}")
+    );
+    assert_eq!(
+        Job::typescript(),
+        "export interface Job {\n  timeout: unknown;\n  delay: unknown;\n  checksum: unknown;\n}\n"
+    );
+}
+
+fn not_reserved(value: &Value) -> Result<(), String> {
+    match value {
+        Value::String(name) if name.as_string() == "admin" => Err("is reserved".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[derive(ToValue, FromValue, Validate, Debug, PartialEq)]
+struct Address {
+    #[validate(length(min = 1))]
+    city: String,
+    #[validate(regex = "^[0-9]{5}$")]
+    zip: String,
+}
+
+#[derive(ToValue, FromValue, Validate, Debug, PartialEq)]
+#[attr(rename_all = "camelCase")]
+struct Signup {
+    #[validate(length(min = 3, max = 20), custom = "not_reserved")]
+    #[attr(alias = "login")]
+    user_name: String,
+    #[validate(range(min = 13, max = 150))]
+    age: u8,
+    #[validate(email)]
+    email: String,
+    #[validate(url)]
+    home_page: Option<String>,
+    #[validate(one_of("free", "pro"))]
+    plan: String,
+    #[validate(length(max = 2), dive)]
+    addresses: Vec<Address>,
+    #[validate(dive)]
+    billing: Option<Address>,
+}
+
+#[test]
+fn test_validate() {
+    let signup = Signup {
+        user_name: "ana".to_string(),
+        age: 30,
+        email: "ana@example.com".to_string(),
+        home_page: None,
+        plan: "free".to_string(),
+        addresses: vec![Address {
+            city: "Lisbon".to_string(),
+            zip: "11000".to_string(),
+        }],
+        billing: None,
+    };
+    assert_eq!(signup.validate(), Ok(()));
+    assert_eq!(Signup::validate_value(&signup.to_value()), Ok(()));
+
+    let value = json(
+        r#"{
+            "login": "admin",
+            "age": 9,
+            "email": "ana@",
+            "homePage": "example.com",
+            "plan": "gold",
+            "addresses": [{"city": "Lisbon", "zip": "11000"}, {"city": "", "zip": "1"}],
+            "billing": {"city": "Porto", "zip": "4000"}
+        }"#,
+    );
+    let violations = Signup::validate_value(&value).unwrap_err();
+    assert_eq!(
+        violations.paths(),
+        vec![
+            "login",
+            "age",
+            "email",
+            "homePage",
+            "plan",
+            "addresses[1].city",
+            "addresses[1].zip",
+            "billing.zip",
+        ]
+    );
+    assert_eq!(
+        violations.to_string(),
+        [
+            "login: is reserved",
+            "age: value must be between 13 and 150, found 9",
+            "email: must be an email address",
+            "homePage: must be a URL",
+            r#"plan: must be one of "free", "pro""#,
+            "addresses[1].city: length must be at least 1, found 0",
+            "addresses[1].zip: must match `^[0-9]{5}$`",
+            "billing.zip: must match `^[0-9]{5}$`",
+        ]
+        .join("\n")
+    );
+
+    // The same rules apply to the struct read from the value.
+    let signup = Signup::from_value(value).unwrap();
+    let violations = signup.validate().unwrap_err();
+    assert_eq!(violations.len(), 8);
+    assert_eq!(violations.at("userName")[0].rule(), "custom");
+    assert_eq!(violations.at("addresses[1].zip")[0].rule(), "regex");
+
+    // Missing fields and values of other types to dive into are left to the conversion,
+    // while rules report values they do not apply to.
+    assert_eq!(
+        Signup::validate_value(&json(r#"{"userName": "ana", "billing": 1}"#)),
+        Ok(())
+    );
+    assert_eq!(Signup::validate_value(&json("[]")), Ok(()));
+    let violations =
+        Signup::validate_value(&json(r#"{"userName": 1, "addresses": [{}, {}, {}]}"#))
+            .unwrap_err();
+    assert_eq!(
+        violations.messages(),
+        vec![
+            (
+                "userName".to_string(),
+                vec!["expected a string, array or object, found number 1".to_string()]
+            ),
+            (
+                "addresses".to_string(),
+                vec!["length must be at most 2, found 3".to_string()]
+            ),
+        ]
+        .into_iter()
+        .collect()
+    );
+    assert!(matches!(Error::from(violations), Error::Validation(_)));
+}
+
+#[test]
+fn test_to_typescript_data_enums() {
+    let mut declarations = TypeScriptDeclarations::new();
+    declarations.add::<Shape>();
+    declarations.add::<Event>();
+    declarations.add::<Message>();
+    declarations.add::<Scalar>();
+    assert_eq!(declarations.len(), 5);
+    assert_eq!(
+        declarations.get("Shape"),
+        Some(
+            r#"export type Shape = "Empty" | { Circle: number } | { Line: [Point, Point] } | { Rect: { topLeft: Point; width: number } };"#
+        )
+    );
+    assert_eq!(
+        declarations.get("Event"),
+        Some(
+            r#"export type Event = { type: "started" } | { type: "moved" } & Point | { type: "renamed"; from: string; to: string };"#
+        )
+    );
+    assert_eq!(
+        declarations.get("Message"),
+        Some(
+            r#"export type Message = { t: "Ping" } | { t: "Text"; c: string } | { t: "Pair"; c: [number, number] } | { t: "Move"; c: { point: Point } };"#
+        )
+    );
+    assert_eq!(
+        declarations.get("Scalar"),
+        Some("export type Scalar = null | number | string | [boolean, boolean] | { x: number; y: number };")
+    );
+}
+
+#[test]
+fn test_to_typescript_tuple_structs() {
+    assert_eq!(UserId::typescript(), "export type UserId = number;\n");
+    assert_eq!(
+        Wrapper::typescript(),
+        "export interface Point {\n  x: number;\n  y: number;\n}\n\nexport type Wrapper = Point;\n"
+    );
+    assert_eq!(
+        Range::typescript(),
+        "export type Range = [number, number, string | null];\n"
+    );
+}
+
+#[test]
+fn test_to_schema_data_enums() {
+    let shape = Schema::compile(&Shape::schema()).unwrap();
+    let shapes = vec![
+        Shape::Empty,
+        Shape::Circle(1.5),
+        Shape::Line(Point { x: 0, y: 0 }, Point { x: 1, y: 1 }),
+        Shape::Rect {
+            top_left: Point { x: 0, y: 0 },
+            width: 2,
+            cached_area: 0,
+        },
+    ];
+    for value in shapes {
+        assert!(shape.is_valid(&value.to_value()), "{:?}", value);
+    }
+    assert!(!shape.is_valid(&json(r#""Circle""#)));
+    assert!(!shape.is_valid(&json(r#"{"Line": [{"x": 0, "y": 0}]}"#)));
+
+    let event = Schema::compile(&Event::schema()).unwrap();
+    let events = vec![
+        Event::Started,
+        Event::Moved(Point { x: 1, y: 2 }),
+        Event::Renamed {
+            from: "a".to_string(),
+            to: "b".to_string(),
+        },
+    ];
+    for value in events {
+        assert!(event.is_valid(&value.to_value()), "{:?}", value);
+    }
+    assert!(!event.is_valid(&json(r#"{"type": "moved", "x": 1}"#)));
+    assert!(!event.is_valid(&json(r#"{"type": "stopped"}"#)));
+
+    let message = Schema::compile(&Message::schema()).unwrap();
+    let messages = vec![
+        Message::Ping,
+        Message::Text("hi".to_string()),
+        Message::Pair(1, 2),
+        Message::Move {
+            point: Point { x: 1, y: 2 },
+        },
+    ];
+    for value in messages {
+        assert!(message.is_valid(&value.to_value()), "{:?}", value);
+    }
+    assert!(!message.is_valid(&json(r#"{"t": "Text", "c": 1}"#)));
+
+    let scalar = Schema::compile(&Scalar::schema()).unwrap();
+    let scalars = vec![
+        Scalar::Nothing,
+        Scalar::Number(1),
+        Scalar::Text("a".to_string()),
+        Scalar::Pair(true, false),
+        Scalar::Point { x: 1, y: 2 },
+    ];
+    for value in scalars {
+        assert!(scalar.is_valid(&value.to_value()), "{:?}", value);
+    }
+    assert!(!scalar.is_valid(&json("[1, 2]")));
+}
+
+#[test]
+fn test_to_schema_tuple_structs() {
+    let user_id = Schema::compile(&UserId::schema()).unwrap();
+    assert!(user_id.is_valid(&UserId(1).to_value()));
+    assert!(!user_id.is_valid(&json(r#""1""#)));
+
+    let wrapper = Schema::compile(&Wrapper::schema()).unwrap();
+    assert!(wrapper.is_valid(&Wrapper(Point { x: 1, y: 2 }).to_value()));
+    assert!(!wrapper.is_valid(&json(r#"{"x": 1}"#)));
+
+    let range = Schema::compile(&Range::schema()).unwrap();
+    assert!(range.is_valid(&Range(1, 2, None).to_value()));
+    assert!(range.is_valid(&Range(1, 2, Some("a".to_string())).to_value()));
+    assert!(!range.is_valid(&json("[1, 2]")));
+    assert!(!range.is_valid(&json(r#"[1, 2, null, 3]"#)));
+}
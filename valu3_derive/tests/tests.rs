@@ -2,3 +2,8 @@
 pub fn pass() {
     macrotest::expand("tests/*.rs");
 }
+
+#[test]
+pub fn fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
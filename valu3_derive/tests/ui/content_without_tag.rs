@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+#[attr(content = "data")]
+enum Message {
+    Text(String),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use valu3::prelude::*;
+
+#[derive(FromValue)]
+struct Job {
+    #[attr(from_with = "duration::from_value")]
+    #[attr(from_with = "duration::from_value")]
+    delay: std::time::Duration,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+struct Job {
+    #[attr(to_with = "duration::to_value", to_with = "duration::to_value")]
+    delay: std::time::Duration,
+}
+
+fn main() {}
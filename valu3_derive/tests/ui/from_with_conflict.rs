@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(FromValue)]
+struct Job {
+    #[attr(with = "duration", from_with = "duration::from_value")]
+    delay: std::time::Duration,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+#[attr(tag = "type")]
+enum Reading {
+    Count(u32),
+}
+
+fn main() {}
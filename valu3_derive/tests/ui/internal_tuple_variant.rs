@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(TryFromValue)]
+#[attr(tag = "type")]
+enum Shape {
+    Point(i32, i32),
+}
+
+fn main() {}
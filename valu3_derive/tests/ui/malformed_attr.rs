@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(TryFromValue)]
+struct User {
+    #[attr(default = some_default)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+union Number {
+    int: i64,
+    float: f64,
+}
+
+fn main() {}
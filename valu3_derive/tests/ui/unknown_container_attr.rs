@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+#[attr(deny_unknown_fields)]
+struct User {
+    name: String,
+}
+
+fn main() {}
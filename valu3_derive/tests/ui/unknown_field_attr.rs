@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue, FromValue)]
+struct User {
+    #[attr(rename = "userName", skip_if_empty)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+#[attr(rename_all = "PascalCase")]
+struct User {
+    user_name: String,
+}
+
+fn main() {}
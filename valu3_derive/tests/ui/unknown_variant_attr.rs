@@ -0,0 +1,10 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+enum Status {
+    #[attr(skip)]
+    Active,
+    Inactive,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(FromValue)]
+#[attr(tag = "type", untagged)]
+enum Message {
+    Text(String),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue, Validate)]
+enum Plan {
+    Free,
+    Pro,
+}
+
+fn main() {}
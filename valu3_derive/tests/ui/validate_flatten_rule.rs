@@ -0,0 +1,12 @@
+use std::collections::HashMap;
+use valu3::prelude::*;
+
+#[derive(ToValue, Validate)]
+struct User {
+    name: String,
+    #[attr(flatten)]
+    #[validate(length(max = 10))]
+    extra: HashMap<String, Value>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue, Validate)]
+struct User {
+    #[validate(length())]
+    name: String,
+}
+
+fn main() {}
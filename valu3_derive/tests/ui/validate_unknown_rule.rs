@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue, Validate)]
+struct User {
+    #[validate(length(min = 1), not_empty)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use valu3::prelude::*;
+
+#[derive(ToValue)]
+struct Job {
+    #[attr(with = "duration", to_with = "duration::to_value")]
+    delay: std::time::Duration,
+}
+
+fn main() {}